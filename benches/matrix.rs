@@ -0,0 +1,59 @@
+#![feature(test)]
+
+extern crate test;
+
+use test::{black_box, Bencher};
+
+use dognut::department::preview::matrix::HMat;
+use dognut::department::preview::vector::{HVec4, Vector3};
+
+fn transform() -> HMat {
+    HMat::from_vec(vec![
+        0.9, 0.1, 0.0, 0.0,
+        -0.1, 0.9, 0.2, 0.0,
+        0.0, -0.2, 0.9, 1.0,
+        1.5, -2.0, 3.0, 1.0,
+    ])
+}
+
+// the per vertex transform of the rasterizer
+#[bench]
+fn hvec4_mul_hmat_simd(b: &mut Bencher) {
+    let m = transform();
+    let v = HVec4::from_v3(Vector3::from_xyz(1., 2., 3.));
+    b.iter(|| black_box(&v) * black_box(&m));
+}
+
+#[bench]
+fn hvec4_mul_hmat_naive(b: &mut Bencher) {
+    let m = transform();
+    let v = HVec4::from_v3(Vector3::from_xyz(1., 2., 3.));
+    b.iter(|| black_box(&v).mul_naive(black_box(&m)));
+}
+
+// building the mvp and viewport chain
+#[bench]
+fn hmat_mul_hmat_simd(b: &mut Bencher) {
+    let m = transform();
+    let n = transform().t();
+    b.iter(|| black_box(&m) * black_box(&n));
+}
+
+#[bench]
+fn hmat_mul_hmat_naive(b: &mut Bencher) {
+    let m = transform();
+    let n = transform().t();
+    b.iter(|| black_box(&m).mul_naive(black_box(&n)));
+}
+
+// what one shaded pixel does with vectors: no allocation left in here
+#[bench]
+fn vector3_shading_ops(b: &mut Bencher) {
+    let bar = Vector3::from_xyz(0.2, 0.3, 0.5);
+    let light = Vector3::from_xyz(0., 1., 0.);
+    b.iter(|| {
+        let mut n = black_box(&bar).cross(black_box(&light));
+        n.norm();
+        black_box(n.dot(&light))
+    });
+}
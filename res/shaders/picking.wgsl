@@ -0,0 +1,67 @@
+// Picking and highlight shader
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct PickInput {
+    @location(0) position: vec3<f32>,
+    @location(1) id: u32,
+}
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+}
+
+struct PickOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) id: u32,
+    @location(1) @interpolate(flat) instance: u32,
+}
+
+fn to_clip(position: vec3<f32>, instance: InstanceInput) -> vec4<f32> {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    return camera.view_proj * model_matrix * vec4<f32>(position, 1.0);
+}
+
+@vertex
+fn vs_pick(
+    model: PickInput,
+    instance: InstanceInput,
+    @builtin(instance_index) instance_index: u32,
+) -> PickOutput {
+    var out: PickOutput;
+    out.clip_position = to_clip(model.position, instance);
+    out.id = model.id;
+    out.instance = instance_index;
+    return out;
+}
+
+// 0 is cleared background, ids are written with an offset of one. the instance goes next to it
+@fragment
+fn fs_pick(in: PickOutput) -> @location(0) vec2<u32> {
+    return vec2<u32>(in.id, in.instance);
+}
+
+@vertex
+fn vs_highlight(
+    model: PickInput,
+    instance: InstanceInput,
+) -> @builtin(position) vec4<f32> {
+    return to_clip(model.position, instance);
+}
+
+@fragment
+fn fs_highlight() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.86, 0.0, 1.0);
+}
@@ -11,6 +11,8 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
     @location(2) normal: vec3<f32>,
+    @location(3) joints: vec4<u32>,
+    @location(4) weights: vec4<f32>,
 }
 struct InstanceInput {
     @location(5) model_matrix_0: vec4<f32>,
@@ -22,6 +24,10 @@ struct InstanceInput {
     @location(11) normal_matrix_2: vec3<f32>,
 }
 
+// skinning matrices of the current pose, a single identity for models without a rig
+@group(2) @binding(0)
+var<storage, read> joint_matrices: array<mat4x4<f32>>;
+
 // struct Light {
 //     position: vec3<f32>,
 //     color: vec3<f32>,
@@ -36,6 +42,24 @@ struct VertexOutput {
     @location(2) world_position: vec3<f32>,
 }
 
+// linear blend of the joints moving this vertex, all weights 0 keeps the bind pose
+fn skin_matrix(model: VertexInput) -> mat4x4<f32> {
+    let total = model.weights.x + model.weights.y + model.weights.z + model.weights.w;
+    if (total <= 0.0) {
+        return mat4x4<f32>(
+            vec4<f32>(1.0, 0.0, 0.0, 0.0),
+            vec4<f32>(0.0, 1.0, 0.0, 0.0),
+            vec4<f32>(0.0, 0.0, 1.0, 0.0),
+            vec4<f32>(0.0, 0.0, 0.0, 1.0),
+        );
+    }
+    let w = model.weights / total;
+    return joint_matrices[model.joints.x] * w.x
+        + joint_matrices[model.joints.y] * w.y
+        + joint_matrices[model.joints.z] * w.z
+        + joint_matrices[model.joints.w] * w.w;
+}
+
 @vertex
 fn vs_main(
     model: VertexInput,
@@ -54,10 +78,13 @@ fn vs_main(
         instance.normal_matrix_2,
     );
 
+    let skin = skin_matrix(model);
+    let skinned_normal = normalize((skin * vec4<f32>(model.normal, 0.0)).xyz);
+
     var out: VertexOutput;
     out.tex_coords = model.tex_coords;
-    out.world_normal = normal_matrix * model.normal;
-    var world_position: vec4<f32> = model_matrix * vec4<f32>(model.position, 1.0);
+    out.world_normal = normal_matrix * skinned_normal;
+    var world_position: vec4<f32> = model_matrix * skin * vec4<f32>(model.position, 1.0);
     out.world_position = world_position.xyz;
     out.clip_position = camera.view_proj * world_position;
     return out;
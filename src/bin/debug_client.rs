@@ -0,0 +1,101 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use protobuf::{EnumOrUnknown, MessageField};
+
+use dognut::department::common::constant;
+use dognut::department::net::debug_service::{to_vec3, DebugClient};
+use dognut::pb::debugger::{CameraState, DebugInfo, RenderState, RenderStateUpdate, ShaderMode};
+
+/// query and drive a running renderer started with `--debug-server`
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// address of the debug service
+    #[arg(long, default_value_t=format!("127.0.0.1:{}", constant::DEBUG_PORT))]
+    addr: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// print the current render state
+    Get,
+    /// change parts of the render state, anything not given is left alone
+    Set {
+        /// highlight this triangle
+        #[arg(long)]
+        triangle: Option<u32>,
+        /// camera position as x,y,z
+        #[arg(long, value_delimiter = ',', num_args = 3)]
+        position: Option<Vec<f32>>,
+        /// camera forward as x,y,z
+        #[arg(long, value_delimiter = ',', num_args = 3)]
+        forward: Option<Vec<f32>>,
+        #[arg(long, value_enum)]
+        shader: Option<Mode>,
+    },
+    /// report a triangle through onGetDebugInfo
+    Info { triangle: u32 },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Mode {
+    Lambertian,
+    Unlit,
+    Normal,
+}
+
+impl From<Mode> for ShaderMode {
+    fn from(m: Mode) -> Self {
+        match m {
+            Mode::Lambertian => ShaderMode::Lambertian,
+            Mode::Unlit => ShaderMode::Unlit,
+            Mode::Normal => ShaderMode::Normal,
+        }
+    }
+}
+
+fn to_array(v: Vec<f32>) -> [f32; 3] {
+    [v[0], v[1], v[2]]
+}
+
+fn print_state(state: &RenderState) {
+    let stats = state.stats.get_or_default();
+    let camera = state.camera.get_or_default();
+    let position = camera.position.get_or_default();
+    let forward = camera.forward.get_or_default();
+    println!("triangle:  {}", state.debugInfo.get_or_default().triangleIndex);
+    println!("camera:    position ({}, {}, {}) forward ({}, {}, {})",
+             position.x, position.y, position.z, forward.x, forward.y, forward.z);
+    println!("shader:    {:?}", state.shaderMode.enum_value_or_default());
+    println!("frames:    {} ({:.1} fps, {:.2} ms)", stats.frameCount, stats.fps, stats.frameTimeMs);
+    println!("triangles: {}", stats.triangleCount);
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let mut client = DebugClient::connect(&args.addr)?;
+
+    match args.command {
+        Command::Get => print_state(&client.get_render_state()?),
+        Command::Set { triangle, position, forward, shader } => {
+            let mut update = RenderStateUpdate::new();
+            update.triangleIndex = triangle;
+            if position.is_some() || forward.is_some() {
+                let mut camera = CameraState::new();
+                camera.position = position.map(|p| to_vec3(to_array(p))).into();
+                camera.forward = forward.map(|f| to_vec3(to_array(f))).into();
+                update.camera = MessageField::some(camera);
+            }
+            update.shaderMode = shader.map(|m| EnumOrUnknown::new(m.into()));
+            print_state(&client.set_render_state(&update)?);
+        }
+        Command::Info { triangle } => {
+            let mut info = DebugInfo::new();
+            info.triangleIndex = triangle;
+            client.on_get_debug_info(&info)?;
+        }
+    }
+    Ok(())
+}
@@ -1,15 +1,29 @@
 use std::fs::OpenOptions;
+use std::path::Path;
 
 use dognut::department::{
     common::constant::{self},
-    pipeline::{rasterizer::RasterRunner, shader::LambertianShader},
-    preview::vector::Vector3,
+    model::{
+        auto_fit::{ModelBounds, UnitFit},
+        object_loader::ObjectLoader,
+        object_writer::ObjectWriter,
+        triangle_resources::TriangleResources,
+    },
+    net::debug_service::DebugService,
+    pipeline::{
+        path_tracer::PathTracer, rasterizer::RasterRunner, ray_tracer::RayTracer,
+        shader::LambertianShader,
+    },
+    preview::{frame_export, homo_transformation::HomoTransform, output_buffer::OutputBuffer, vector::Vector3},
     tui::TuiApp,
     types::{msg, multi_sender::MultiSender},
 };
 use log::LevelFilter;
 
-use dognut::{department::view::camera::Camera, util::ARG};
+use dognut::{
+    department::view::{camera::Camera, camera_trait::CameraTrait},
+    util::{AnimationFormat, Backend, ARG},
+};
 
 use log::{error, info};
 
@@ -40,19 +54,23 @@ fn main() {
 
     let ms = MultiSender::new(net_sender, enc_sender, win_sender);
 
-    let camera = Camera::new(
-        45.,
-        (constant::WIDTH / constant::HEIGHT) as f32,
-        -5.,
-        -50.,
-        Vector3::from_xyz(0., 0., 10.),
-        Vector3::from_xyz(0., 0., -1.),
-        Vector3::from_xyz(0., -1., 0.),
-    );
+    let new_camera = || {
+        Camera::new(
+            45.,
+            (constant::WIDTH / constant::HEIGHT) as f32,
+            -0.1,
+            -1000.,
+            Vector3::from_xyz(0., 0., 10.),
+            Vector3::from_xyz(0., 0., -1.),
+            Vector3::from_xyz(0., -1., 0.),
+        )
+    };
+    let camera = new_camera();
+    let light_source = Vector3::from_xyz(0., 1., 0.);
 
-    let shader = LambertianShader::new(Vector3::from_xyz(0., 1., 0.), 0.8, 1., &camera, arg.term);
+    let shader = LambertianShader::new(light_source.clone(), 0.8, 1., &camera, arg.term);
 
-    let raster = RasterRunner::new(ms.clone(), camera, Box::new(shader), arg.term);
+    let mut raster = RasterRunner::new(ms.clone(), camera, Box::new(shader), arg.term);
 
 
     let inner_rt = tokio::runtime::Builder::new_multi_thread()
@@ -60,8 +78,116 @@ fn main() {
         .build()
         .unwrap();
 
+    if let Some(export) = &arg.export {
+        let models = match ObjectLoader::load_all_triangle_resources(&arg.obj_path) {
+            Ok(models) => models,
+            Err(e) => {
+                error!("failed to load {}: {:?}", arg.obj_path, e);
+                eprintln!("dognut: {}", e);
+                std::process::exit(1);
+            }
+        };
+        // fitted as a whole, so the models keep their places relative to each other
+        let transform = if arg.normalize {
+            let bounds = ModelBounds::from_models(models.iter().map(|r| &r.model));
+            UnitFit::new(models.iter().map(|r| &r.model), &bounds).to_transform()
+        } else {
+            HomoTransform::identity_matrix()
+        };
+        if let Err(e) = ObjectWriter::save_triangle_resources(export, &models, &transform, !arg.export_ascii) {
+            error!("failed to export {}: {:?}", export, e);
+            eprintln!("dognut: can't export {}: {}", export, e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut resources = None;
+    let mut ray_tracer = None;
+    let mut path_tracer = None;
+    if !arg.use_gpu {
+        let mut res = load_resources(&arg.obj_path);
+        if arg.normalize {
+            res.normalize();
+        }
+        if let Some(sequence) = res.sequence.as_mut() {
+            sequence.set_fps(arg.sequence_fps);
+        }
+        let (center, radius) = (res.bounds.center(), res.bounds.radius());
+        let framed_camera = || {
+            let mut camera = new_camera();
+            camera.frame(center, radius);
+            camera
+        };
+        raster.frame(center, radius);
+
+        if arg.backend == Backend::Ray {
+            let mut rt = RayTracer::new(framed_camera(), light_source.clone(), 1., !arg.render_a_picture);
+            rt.set_scene(&res);
+            ray_tracer = Some(rt);
+        }
+        if arg.backend == Backend::Path {
+            let mut pt = PathTracer::new(framed_camera(), light_source.clone(), 1., arg.samples, !arg.render_a_picture);
+            pt.set_scene(&res);
+            path_tracer = Some(pt);
+        }
+
+        if arg.render_a_picture {
+            let output = |default: &str| arg.output.clone().unwrap_or_else(|| default.to_string());
+            if let Some(duration) = res.sequence.as_ref().map(|s| s.duration()) {
+                // one loop of the sequence, the tracers rebuild their scene for every frame
+                let fps = arg.export_fps.unwrap_or(arg.sequence_fps).max(0.1);
+                let count = ((duration * fps).round() as usize).max(1);
+                let mut images = Vec::with_capacity(count);
+                for i in 0..count {
+                    res.pose_sequence(i as f32 / fps);
+                    if let Some(pt) = &mut path_tracer {
+                        pt.set_scene(&res);
+                    }
+                    if let Some(rt) = &mut ray_tracer {
+                        rt.set_scene(&res);
+                    }
+                    images.push(render_picture(&res, &raster, &ray_tracer, &mut path_tracer).to_image());
+                }
+                let saved = match arg.animation_format {
+                    AnimationFormat::Gif => frame_export::save_gif(Path::new(&output("dognut.gif")), images, fps),
+                    AnimationFormat::Png => {
+                        frame_export::save_png_sequence(Path::new(&output("dognut.png")), &images).map(|_| ())
+                    }
+                };
+                match saved {
+                    Ok(()) => info!("{} frames saved", count),
+                    Err(e) => error!("could not save animation {}", e),
+                }
+                return;
+            }
+            let out = render_picture(&res, &raster, &ray_tracer, &mut path_tracer);
+            let path = output("dognut.png");
+            out.save_to_image(&path);
+            info!("picture saved to {}", path);
+            return;
+        }
+        resources = Some(res);
+    }
+
+    let mut app = TuiApp::new(raster);
+    if let Some(res) = resources {
+        app = app.with_resources(res);
+    }
+    if let Some(rt) = ray_tracer {
+        app = app.with_ray_tracer(rt);
+    }
+    if let Some(pt) = path_tracer {
+        app = app.with_path_tracer(pt);
+    }
+    if arg.debug_server {
+        let service = DebugService::new(&arg.debug_host);
+        app = app.with_debug_service(service.handle());
+        service.run();
+    }
+
     inner_rt.block_on(async move {
-        let result = TuiApp::new(raster).run(None).await;
+        let result = app.run(None).await;
         if let Err(e) = result {
             error!("tui return an error, {}", e.to_string());
         };
@@ -69,3 +195,34 @@ fn main() {
 
     info!("tui app end");
 }
+
+fn render_picture(
+    res: &TriangleResources,
+    raster: &RasterRunner,
+    ray_tracer: &Option<RayTracer>,
+    path_tracer: &mut Option<PathTracer>,
+) -> OutputBuffer<'static> {
+    let mut out = OutputBuffer::new(constant::WIDTH, constant::HEIGHT, false);
+    if let Some(pt) = path_tracer {
+        // headless, so spend every sample before saving
+        pt.set_samples_per_frame(ARG.samples);
+        pt.render_frame(res, &mut out);
+    } else if let Some(rt) = ray_tracer {
+        rt.render_frame(res, &mut out);
+    } else {
+        raster.render_frame(res, &mut out);
+    }
+    out
+}
+
+// a broken model is a user error, so no panic and backtrace for it
+fn load_resources(path: &str) -> TriangleResources {
+    match ObjectLoader::load_triangle_resources(path) {
+        Ok(res) => res,
+        Err(e) => {
+            error!("failed to load {}: {:?}", path, e);
+            eprintln!("dognut: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 pub const HOST: &str = "0.0.0.0";
 pub const PORT: u32 = 9527;
 pub const UDP_PORT: u32 = 19527;
+pub const DEBUG_PORT: u32 = 9627;
+// the debug service can move the camera and change render state, loopback unless asked otherwise
+pub const DEBUG_HOST: &str = "127.0.0.1";
 pub const PORT_RANGE: u32 = 10;
 
 pub const WIDTH: u32 = 640;
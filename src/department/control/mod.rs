@@ -1,14 +1,20 @@
 use std::f32::consts::PI;
+use std::sync::Arc;
 
 use cgmath::{InnerSpace, Rotation3};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
 
+use crate::department::model::animation::{AnimationPlayer, Rig};
+use crate::department::model::skeleton::Mat4;
 use crate::wgpu::instance::Instance;
 
 pub mod camera_controller;
 use log::info;
 
+// crossfade when switching clips from the keyboard
+const CLIP_BLEND_SECONDS: f32 = 0.3;
+
 #[derive(Debug)]
 pub struct ModelController {
     position: cgmath::Vector3<f32>,
@@ -26,16 +32,22 @@ pub struct ModelController {
     scroll: f32,
     speed: f32,
     tui: bool,
+    // skeleton and clips of a rigged model, posed by `player`
+    rig: Option<Arc<Rig>>,
+    player: AnimationPlayer,
+    // seconds of vertex animation played, the sequence wraps it into a loop itself
+    sequence_time: f32,
 }
 
 impl ModelController {
     pub fn new(speed: f32, tui: bool) -> Self {
+        // loaded models are centred on the origin and the camera frames them there
         let p = cgmath::Vector3 {
-            x: -1.46,
+            x: 0.0,
             y: 0.0,
-            z: 4.20,
+            z: 0.0,
         };
-        let q = cgmath::Quaternion::from_axis_angle(p.normalize(), cgmath::Deg(0.0));
+        let q = cgmath::Quaternion::from_angle_y(cgmath::Deg(0.0));
         Self {
             position: p,
             theta: 0.,
@@ -52,9 +64,73 @@ impl ModelController {
             scroll: 0.,
             speed,
             tui,
+            rig: None,
+            player: AnimationPlayer::default(),
+            sequence_time: 0.,
+        }
+    }
+
+    // rigged models loop their first clip until told otherwise
+    pub fn set_rig(&mut self, rig: Arc<Rig>) {
+        self.player = AnimationPlayer::default();
+        if !rig.clips.is_empty() {
+            self.player.play(0, true);
+        }
+        self.player.advance(0., &rig);
+        self.rig = Some(rig);
+    }
+
+    // the model was reloaded without a skeleton
+    pub fn clear_rig(&mut self) {
+        self.player = AnimationPlayer::default();
+        self.rig = None;
+    }
+
+    pub fn clip_names(&self) -> Vec<&str> {
+        self.rig
+            .as_ref()
+            .map(|r| r.clips.iter().map(|c| c.name.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn play_clip(&mut self, clip: usize, looping: bool) {
+        if self.has_clip(clip) {
+            self.player.play(clip, looping);
+        }
+    }
+
+    // crossfades from whatever is playing to `clip` over `seconds`
+    pub fn blend_to_clip(&mut self, clip: usize, seconds: f32, looping: bool) {
+        if self.has_clip(clip) {
+            self.player.blend_to(clip, seconds, looping);
         }
     }
 
+    pub fn set_looping(&mut self, looping: bool) {
+        self.player.set_looping(looping);
+    }
+
+    fn has_clip(&self, clip: usize) -> bool {
+        self.rig.as_ref().is_some_and(|r| clip < r.clips.len())
+    }
+
+    // moves the clips and sequences on by `dt` seconds, `update_model` already does this for the gpu
+    pub fn animate(&mut self, dt: f32) {
+        if let Some(rig) = &self.rig {
+            self.player.advance(dt, rig);
+        }
+        self.sequence_time += dt;
+    }
+
+    pub fn sequence_time(&self) -> f32 {
+        self.sequence_time
+    }
+
+    // skinning matrices of the current pose, none without a rig
+    pub fn joint_matrices(&self) -> Option<&[Mat4]> {
+        self.rig.as_ref().map(|_| self.player.matrices())
+    }
+
     pub fn process_keyboard_tui(&mut self, key: &KeyEvent) -> bool {
         let amount = if key.kind == KeyEventKind::Press {
             1.0
@@ -93,6 +169,17 @@ impl ModelController {
                 self.rotation =
                     cgmath::Quaternion::from_axis_angle(p.normalize(), cgmath::Deg(0.0));
             }
+            KeyCode::Char('n') => {
+                let count = self.rig.as_ref().map_or(0, |r| r.clips.len());
+                if count > 0 {
+                    let next = self.player.clip().map_or(0, |c| (c + 1) % count);
+                    self.blend_to_clip(next, CLIP_BLEND_SECONDS, self.player.looping());
+                }
+            }
+            KeyCode::Char('l') => {
+                let looping = !self.player.looping();
+                self.set_looping(looping);
+            }
             KeyCode::Modifier(_) => {}
             _ => {}
         }
@@ -154,6 +241,7 @@ impl ModelController {
         dt: std::time::Duration,
     ) -> Vec<crate::wgpu::instance::InstanceRaw> {
         let dt = dt.as_secs_f32();
+        self.animate(dt);
 
         self.position.z += (self.amount_forward - self.amount_backward) * self.speed * dt;
         self.position.x += (self.amount_right - self.amount_left) * self.speed * dt;
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use crate::department::model::auto_fit::UnitFit;
+use crate::department::model::skeleton::{normalize4, slerp, JointPose, Mat4, Skeleton, SkinWeights};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Translation,
+    Rotation,
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // every key is in tangent, value, out tangent in that order
+    CubicSpline,
+}
+
+// keyframes of one property of one joint, translation and scale leave the 4th component at 0
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub joint: usize,
+    pub channel: Channel,
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub values: Vec<[f32; 4]>,
+}
+
+impl Track {
+    pub fn sample(&self, time: f32) -> Option<[f32; 4]> {
+        let key = |k: usize| match self.interpolation {
+            Interpolation::CubicSpline => self.values.get(k * 3 + 1).copied(),
+            _ => self.values.get(k).copied(),
+        };
+        let last = self.times.len().checked_sub(1)?;
+        // keys hold still before the first and after the last one
+        if time <= self.times[0] || last == 0 {
+            return key(0);
+        }
+        if time >= self.times[last] {
+            return key(last);
+        }
+        let k = self.times.partition_point(|&t| t <= time) - 1;
+        let (t0, t1) = (self.times[k], self.times[k + 1]);
+        let u = if t1 > t0 { (time - t0) / (t1 - t0) } else { 0. };
+        let (a, b) = (key(k)?, key(k + 1)?);
+
+        let value = match self.interpolation {
+            Interpolation::Step => a,
+            Interpolation::Linear if self.channel == Channel::Rotation => slerp(a, b, u),
+            Interpolation::Linear => [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * u),
+            Interpolation::CubicSpline => {
+                let out_a = *self.values.get(k * 3 + 2)?;
+                let in_b = *self.values.get((k + 1) * 3)?;
+                let dt = t1 - t0;
+                let (u2, u3) = (u * u, u * u * u);
+                let h = [2. * u3 - 3. * u2 + 1., u3 - 2. * u2 + u, -2. * u3 + 3. * u2, u3 - u2];
+                [0, 1, 2, 3].map(|i| h[0] * a[i] + h[1] * dt * out_a[i] + h[2] * b[i] + h[3] * dt * in_b[i])
+            }
+        };
+        Some(match self.channel {
+            Channel::Rotation => normalize4(value),
+            _ => value,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: String,
+    // seconds, the time of the last key of any track
+    pub duration: f32,
+    pub tracks: Vec<Track>,
+}
+
+impl AnimationClip {
+    pub fn new(name: String, tracks: Vec<Track>) -> Self {
+        let duration = tracks
+            .iter()
+            .filter_map(|t| t.times.last())
+            .fold(0f32, |d, &t| d.max(t));
+        Self { name, duration, tracks }
+    }
+
+    // overwrites the joints this clip drives, the rest of `pose` stays as it was
+    pub fn sample(&self, time: f32, pose: &mut [JointPose]) {
+        for track in &self.tracks {
+            let (Some(joint), Some(v)) = (pose.get_mut(track.joint), track.sample(time)) else {
+                continue;
+            };
+            match track.channel {
+                Channel::Translation => joint.translation = [v[0], v[1], v[2]],
+                Channel::Rotation => joint.rotation = v,
+                Channel::Scale => joint.scale = [v[0], v[1], v[2]],
+            }
+        }
+    }
+}
+
+// a skeleton together with every clip that animates it
+#[derive(Debug, Clone)]
+pub struct Rig {
+    pub skeleton: Skeleton,
+    pub clips: Vec<AnimationClip>,
+}
+
+// what a skinned model carries next to its mesh
+#[derive(Debug, Clone)]
+pub struct Skin {
+    pub rig: Arc<Rig>,
+    // indexed by mesh position
+    pub weights: SkinWeights,
+    // set once the positions were normalized, the joint matrices have to follow them
+    pub fit: Option<UnitFit>,
+}
+
+impl Skin {
+    // matrices of an `AnimationPlayer` for this rig, in the space the positions are in now
+    pub fn fitted(&self, matrices: &[Mat4]) -> Vec<Mat4> {
+        match &self.fit {
+            Some(fit) => matrices.iter().map(|m| fit.conjugate(m)).collect(),
+            None => matrices.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Playback {
+    clip: usize,
+    time: f32,
+    looping: bool,
+}
+
+impl Playback {
+    fn advance(&mut self, dt: f32, duration: f32) {
+        self.time += dt;
+        if duration <= 0. {
+            self.time = 0.;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.min(duration);
+        }
+    }
+}
+
+// plays one clip of a rig at a time, crossfading from the previous one when asked to
+#[derive(Debug, Clone, Default)]
+pub struct AnimationPlayer {
+    current: Option<Playback>,
+    // the clip fading out, with the seconds the fade lasts and has run
+    fading: Option<(Playback, f32, f32)>,
+    matrices: Vec<Mat4>,
+}
+
+impl AnimationPlayer {
+    pub fn play(&mut self, clip: usize, looping: bool) {
+        self.current = Some(Playback {
+            clip,
+            time: 0.,
+            looping,
+        });
+        self.fading = None;
+    }
+
+    // starts `clip` from the beginning while the running one fades out over `seconds`
+    pub fn blend_to(&mut self, clip: usize, seconds: f32, looping: bool) {
+        let previous = self.current.take();
+        self.play(clip, looping);
+        if seconds > 0. {
+            self.fading = previous.map(|p| (p, seconds, 0.));
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.fading = None;
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        if let Some(p) = self.current.as_mut() {
+            p.looping = looping;
+        }
+    }
+
+    pub fn looping(&self) -> bool {
+        self.current.is_some_and(|p| p.looping)
+    }
+
+    pub fn clip(&self) -> Option<usize> {
+        self.current.map(|p| p.clip)
+    }
+
+    // moves time on by `dt` seconds and poses the skeleton, the rest pose without a clip
+    pub fn advance(&mut self, dt: f32, rig: &Rig) {
+        let duration = |p: &Playback| rig.clips.get(p.clip).map_or(0., |c| c.duration);
+        let sample = |p: &Playback| {
+            let mut pose = rig.skeleton.rest_pose();
+            if let Some(clip) = rig.clips.get(p.clip) {
+                clip.sample(p.time, &mut pose);
+            }
+            pose
+        };
+
+        if let Some(p) = self.current.as_mut() {
+            p.advance(dt, duration(p));
+        }
+        if let Some((p, seconds, elapsed)) = self.fading.as_mut() {
+            p.advance(dt, duration(p));
+            *elapsed += dt;
+            if *elapsed >= *seconds {
+                self.fading = None;
+            }
+        }
+
+        let mut pose = match self.current.as_ref() {
+            Some(p) => sample(p),
+            None => rig.skeleton.rest_pose(),
+        };
+        if let Some((p, seconds, elapsed)) = self.fading.as_ref() {
+            let t = elapsed / seconds;
+            pose = sample(p)
+                .iter()
+                .zip(&pose)
+                .map(|(from, to)| from.blend(to, t))
+                .collect();
+        }
+        self.matrices = rig.skeleton.skinning_matrices(&pose);
+    }
+
+    // skinning matrices of the last `advance`, one per joint
+    pub fn matrices(&self) -> &[Mat4] {
+        &self.matrices
+    }
+}
@@ -0,0 +1,157 @@
+use tobj::Model;
+
+use crate::department::model::bvh::Aabb;
+use crate::department::model::skeleton::{mul, Mat4};
+use crate::department::model::vertex_buffer::VertexBuffer;
+use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::vector::Vector3;
+
+// how much room is left around the bounding sphere when the camera frames it
+const FRAMING_MARGIN: f32 = 1.1;
+
+#[derive(Debug, Clone)]
+pub struct BoundingSphere {
+    pub center: Vector3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    // ritter's approximation, at most a few percent larger than the minimal sphere
+    pub fn from_points(points: &[Vector3]) -> Self {
+        let Some(first) = points.first() else {
+            return Self {
+                center: Vector3::from_xyz(0., 0., 0.),
+                radius: 0.,
+            };
+        };
+        let farthest = |from: &Vector3| {
+            points
+                .iter()
+                .max_by(|a, b| distance(from, a).total_cmp(&distance(from, b)))
+                .unwrap()
+                .clone()
+        };
+        let a = farthest(first);
+        let b = farthest(&a);
+
+        let mut center = Vector3::from_xyz(
+            (a.x() + b.x()) * 0.5,
+            (a.y() + b.y()) * 0.5,
+            (a.z() + b.z()) * 0.5,
+        );
+        let mut radius = distance(&a, &b) * 0.5;
+
+        // grow towards every point still outside
+        for p in points {
+            let d = distance(&center, p);
+            if d > radius {
+                let grown = (radius + d) * 0.5;
+                let k = (grown - radius) / d;
+                center = Vector3::from_xyz(
+                    center.x() + (p.x() - center.x()) * k,
+                    center.y() + (p.y() - center.y()) * k,
+                    center.z() + (p.z() - center.z()) * k,
+                );
+                radius = grown;
+            }
+        }
+        Self { center, radius }
+    }
+}
+
+// what the loaders know about the extent of a model, in model space
+#[derive(Debug, Clone)]
+pub struct ModelBounds {
+    pub aabb: Aabb,
+    pub sphere: BoundingSphere,
+}
+
+impl ModelBounds {
+    pub fn from_models<'a>(models: impl IntoIterator<Item = &'a Model>) -> Self {
+        let points: Vec<Vector3> = models
+            .into_iter()
+            .flat_map(|m| m.mesh.positions.chunks_exact(3))
+            .map(|p| Vector3::from_xyz(p[0], p[1], p[2]))
+            .collect();
+        Self {
+            aabb: Aabb::from_points(&points),
+            sphere: BoundingSphere::from_points(&points),
+        }
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        let c = &self.sphere.center;
+        [c.x(), c.y(), c.z()]
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.sphere.radius
+    }
+}
+
+// moves the aabb centre to the origin and scales everything into the unit sphere,
+// normals survive a uniform scale untouched
+#[derive(Debug, Clone, Copy)]
+pub struct UnitFit {
+    pub center: [f32; 3],
+    pub scale: f32,
+}
+
+impl UnitFit {
+    pub fn new<'a>(models: impl IntoIterator<Item = &'a Model>, bounds: &ModelBounds) -> Self {
+        if bounds.aabb.is_empty() {
+            return Self {
+                center: [0.; 3],
+                scale: 1.,
+            };
+        }
+        let c = bounds.aabb.centroid();
+        let center = [c.x(), c.y(), c.z()];
+        let reach = models
+            .into_iter()
+            .flat_map(|m| m.mesh.positions.chunks_exact(3))
+            .map(|p| (0..3).map(|k| (p[k] - center[k]).powi(2)).sum::<f32>().sqrt())
+            .fold(0f32, f32::max);
+        // a single point can't be scaled up to anything
+        let scale = if reach > 0. { 1. / reach } else { 1. };
+        Self { center, scale }
+    }
+
+    pub fn apply(&self, p: &mut [f32]) {
+        for (v, c) in p.iter_mut().zip(self.center) {
+            *v = (*v - c) * self.scale;
+        }
+    }
+
+    pub fn apply_to_model(&self, model: &mut Model) {
+        model.mesh.positions.chunks_exact_mut(3).for_each(|p| self.apply(p));
+    }
+
+    pub fn apply_to_buffer(&self, buffer: &mut VertexBuffer) {
+        buffer.vertices.iter_mut().for_each(|v| self.apply(&mut v.position));
+    }
+
+    // `apply` as a transform, for writers that take one
+    pub fn to_transform(&self) -> HomoTransform {
+        let [x, y, z] = self.center;
+        &HomoTransform::translation((-x, -y, -z)) * &HomoTransform::scale((self.scale, self.scale, self.scale))
+    }
+
+    // a skinning matrix made for the original positions, rewritten to act on fitted ones
+    pub fn conjugate(&self, m: &Mat4) -> Mat4 {
+        let ([x, y, z], s) = (self.center, self.scale);
+        let unfit = [[1. / s, 0., 0., 0.], [0., 1. / s, 0., 0.], [0., 0., 1. / s, 0.], [x, y, z, 1.]];
+        let fit = [[s, 0., 0., 0.], [0., s, 0., 0.], [0., 0., s, 0.], [-x * s, -y * s, -z * s, 1.]];
+        mul(&mul(&unfit, m), &fit)
+    }
+}
+
+// distance from the centre at which a camera with vertical field of view `fov_y`
+// (radians) sees the whole sphere
+pub fn framing_distance(radius: f32, fov_y: f32) -> f32 {
+    radius / (fov_y * 0.5).sin() * FRAMING_MARGIN
+}
+
+fn distance(a: &Vector3, b: &Vector3) -> f32 {
+    (b - a).magnitude()
+}
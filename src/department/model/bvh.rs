@@ -0,0 +1,511 @@
+use crate::department::model::triangle_resources::TriangleResources;
+use crate::department::preview::vector::Vector3;
+
+const SAH_BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f32 = 1.0;
+const INTERSECT_COST: f32 = 1.0;
+const EPSILON: f32 = 1e-7;
+
+#[derive(Debug, Clone)]
+pub struct Ray {
+    pub origin: Vector3,
+    pub direction: Vector3,
+    inv_direction: [f32; 3],
+}
+
+impl Ray {
+    // direction is normalized, so `t` of a hit is the distance along the ray
+    pub fn new(origin: Vector3, direction: Vector3) -> Self {
+        let mut direction = direction;
+        direction.norm();
+        let inv_direction = [
+            1. / direction.x(),
+            1. / direction.y(),
+            1. / direction.z(),
+        ];
+        Self {
+            origin,
+            direction,
+            inv_direction,
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Vector3 {
+        Vector3::from_xyz(
+            self.origin.x() + self.direction.x() * t,
+            self.origin.y() + self.direction.y() * t,
+            self.origin.z() + self.direction.z() * t,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Self {
+            min: Vector3::from_xyz(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vector3::from_xyz(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vector3>) -> Self {
+        let mut aabb = Self::empty();
+        for p in points {
+            aabb.grow(p);
+        }
+        aabb
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    pub fn grow(&mut self, p: &Vector3) {
+        for axis in 0..3 {
+            self.min.set(0, axis, self.min.index(0, axis).min(p.index(0, axis)));
+            self.max.set(0, axis, self.max.index(0, axis).max(p.index(0, axis)));
+        }
+    }
+
+    pub fn merge(&mut self, other: &Aabb) {
+        if other.is_empty() {
+            return;
+        }
+        self.grow(&other.min);
+        self.grow(&other.max);
+    }
+
+    pub fn extent(&self) -> Vector3 {
+        &self.max - &self.min
+    }
+
+    pub fn centroid(&self) -> Vector3 {
+        Vector3::from_xyz(
+            (self.min.x() + self.max.x()) * 0.5,
+            (self.min.y() + self.max.y()) * 0.5,
+            (self.min.z() + self.max.z()) * 0.5,
+        )
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.;
+        }
+        let e = self.extent();
+        2. * (e.x() * e.y() + e.y() * e.z() + e.z() * e.x())
+    }
+
+    pub fn largest_axis(&self) -> usize {
+        let e = self.extent();
+        if e.x() >= e.y() && e.x() >= e.z() {
+            0
+        } else if e.y() >= e.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn contains(&self, p: &Vector3) -> bool {
+        (0..3).all(|axis| {
+            let v = p.index(0, axis);
+            v >= self.min.index(0, axis) && v <= self.max.index(0, axis)
+        })
+    }
+
+    // slab test, returns the entry distance if the ray hits the box before `t_max`
+    pub fn intersect_ray(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+        let (mut t0, mut t1) = (t_min, t_max);
+        for axis in 0..3 {
+            let inv = ray.inv_direction[axis];
+            let origin = ray.origin.index(0, axis);
+            let mut near = (self.min.index(0, axis) - origin) * inv;
+            let mut far = (self.max.index(0, axis) - origin) * inv;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+            // NaN comes from 0 * inf when the ray lies on a slab plane, keep the old bounds then
+            if near > t0 {
+                t0 = near;
+            }
+            if far < t1 {
+                t1 = far;
+            }
+            if t0 > t1 {
+                return None;
+            }
+        }
+        Some(t0)
+    }
+}
+
+// Möller–Trumbore, returns (t, u, v)
+pub fn intersect_triangle(tri: &[Vector3; 3], ray: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32, f32)> {
+    let e1 = &tri[1] - &tri[0];
+    let e2 = &tri[2] - &tri[0];
+    let p = ray.direction.cross(&e2);
+    let det = e1.dot(&p);
+    if det.abs() < EPSILON {
+        return None;
+    }
+    let inv_det = 1. / det;
+    let s = &ray.origin - &tri[0];
+    let u = s.dot(&p) * inv_det;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&e1);
+    let v = ray.direction.dot(&q) * inv_det;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    let t = e2.dot(&q) * inv_det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    Some((t, u, v))
+}
+
+#[derive(Debug, Clone)]
+pub struct RayHit {
+    pub triangle: usize,
+    pub t: f32,
+    // weights of v0, v1, v2, same order as `Triangle::barycentric_2d`
+    pub barycentric: Vector3,
+    pub position: Vector3,
+}
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    bounds: Aabb,
+    // for a leaf: first index into `tri_indices`, otherwise: index of the left child,
+    // the right child always follows the left one.
+    left_or_first: usize,
+    count: usize,
+}
+
+impl BvhNode {
+    fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    triangles: Vec<[Vector3; 3]>,
+    tri_indices: Vec<usize>,
+    nodes: Vec<BvhNode>,
+}
+
+struct Bin {
+    bounds: Aabb,
+    count: usize,
+}
+
+impl Bvh {
+    pub fn build(res: &TriangleResources) -> Self {
+        let m = &res.model.mesh;
+        let position = |i: u32| {
+            let i = i as usize;
+            Vector3::from_xyz(m.positions[i * 3], m.positions[i * 3 + 1], m.positions[i * 3 + 2])
+        };
+        let triangles = m
+            .indices
+            .chunks_exact(3)
+            .map(|idx| [position(idx[0]), position(idx[1]), position(idx[2])])
+            .collect();
+        Self::from_triangles(triangles)
+    }
+
+    pub fn from_triangles(triangles: Vec<[Vector3; 3]>) -> Self {
+        let tri_bounds: Vec<Aabb> = triangles.iter().map(|t| Aabb::from_points(t.iter())).collect();
+        let centroids: Vec<Vector3> = tri_bounds.iter().map(|b| b.centroid()).collect();
+
+        let mut bvh = Self {
+            tri_indices: (0..triangles.len()).collect(),
+            triangles,
+            nodes: Vec::new(),
+        };
+
+        if bvh.triangles.is_empty() {
+            return bvh;
+        }
+
+        bvh.nodes.push(BvhNode {
+            bounds: Aabb::empty(),
+            left_or_first: 0,
+            count: bvh.triangles.len(),
+        });
+        bvh.subdivide(0, &tri_bounds, &centroids);
+        bvh
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn triangle(&self, idx: usize) -> &[Vector3; 3] {
+        &self.triangles[idx]
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes
+            .first()
+            .map(|n| n.bounds.clone())
+            .unwrap_or_else(Aabb::empty)
+    }
+
+    fn subdivide(&mut self, node_idx: usize, tri_bounds: &[Aabb], centroids: &[Vector3]) {
+        let (first, count) = (self.nodes[node_idx].left_or_first, self.nodes[node_idx].count);
+
+        let mut bounds = Aabb::empty();
+        let mut centroid_bounds = Aabb::empty();
+        for &t in &self.tri_indices[first..first + count] {
+            bounds.merge(&tri_bounds[t]);
+            centroid_bounds.grow(&centroids[t]);
+        }
+        self.nodes[node_idx].bounds = bounds;
+
+        if count <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        let parent_area = self.nodes[node_idx].bounds.surface_area();
+        let Some((axis, split_pos, split_cost)) =
+            self.find_best_split(first, count, parent_area, &centroid_bounds, tri_bounds, centroids)
+        else {
+            return;
+        };
+
+        let leaf_cost = INTERSECT_COST * count as f32;
+        if split_cost >= leaf_cost {
+            return;
+        }
+
+        // partition triangles in place around the split plane
+        let (mut i, mut j) = (first, first + count);
+        while i < j {
+            if centroids[self.tri_indices[i]].index(0, axis) < split_pos {
+                i += 1;
+            } else {
+                j -= 1;
+                self.tri_indices.swap(i, j);
+            }
+        }
+
+        let left_count = i - first;
+        if left_count == 0 || left_count == count {
+            return;
+        }
+
+        let left = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds: Aabb::empty(),
+            left_or_first: first,
+            count: left_count,
+        });
+        self.nodes.push(BvhNode {
+            bounds: Aabb::empty(),
+            left_or_first: i,
+            count: count - left_count,
+        });
+        self.nodes[node_idx].left_or_first = left;
+        self.nodes[node_idx].count = 0;
+
+        self.subdivide(left, tri_bounds, centroids);
+        self.subdivide(left + 1, tri_bounds, centroids);
+    }
+
+    // binned SAH, returns (axis, split position, cost)
+    fn find_best_split(
+        &self,
+        first: usize,
+        count: usize,
+        parent_area: f32,
+        centroid_bounds: &Aabb,
+        tri_bounds: &[Aabb],
+        centroids: &[Vector3],
+    ) -> Option<(usize, f32, f32)> {
+        let mut best: Option<(usize, f32, f32)> = None;
+        if parent_area <= 0. {
+            return None;
+        }
+
+        for axis in 0..3 {
+            let lo = centroid_bounds.min.index(0, axis);
+            let hi = centroid_bounds.max.index(0, axis);
+            if hi - lo <= f32::EPSILON {
+                continue;
+            }
+
+            let mut bins: Vec<Bin> = (0..SAH_BINS)
+                .map(|_| Bin {
+                    bounds: Aabb::empty(),
+                    count: 0,
+                })
+                .collect();
+            let scale = SAH_BINS as f32 / (hi - lo);
+            for &t in &self.tri_indices[first..first + count] {
+                let b = (((centroids[t].index(0, axis) - lo) * scale) as usize).min(SAH_BINS - 1);
+                bins[b].count += 1;
+                bins[b].bounds.merge(&tri_bounds[t]);
+            }
+
+            // sweep from both sides to get the area and count of every split candidate
+            let mut left_area = [0f32; SAH_BINS - 1];
+            let mut left_count = [0usize; SAH_BINS - 1];
+            let mut right_area = [0f32; SAH_BINS - 1];
+            let mut right_count = [0usize; SAH_BINS - 1];
+            let (mut left_box, mut right_box) = (Aabb::empty(), Aabb::empty());
+            let (mut left_sum, mut right_sum) = (0, 0);
+            for i in 0..SAH_BINS - 1 {
+                left_sum += bins[i].count;
+                left_box.merge(&bins[i].bounds);
+                left_count[i] = left_sum;
+                left_area[i] = left_box.surface_area();
+
+                right_sum += bins[SAH_BINS - 1 - i].count;
+                right_box.merge(&bins[SAH_BINS - 1 - i].bounds);
+                right_count[SAH_BINS - 2 - i] = right_sum;
+                right_area[SAH_BINS - 2 - i] = right_box.surface_area();
+            }
+
+            let bin_width = (hi - lo) / SAH_BINS as f32;
+            for i in 0..SAH_BINS - 1 {
+                if left_count[i] == 0 || right_count[i] == 0 {
+                    continue;
+                }
+                let cost = TRAVERSAL_COST
+                    + INTERSECT_COST
+                        * (left_area[i] * left_count[i] as f32 + right_area[i] * right_count[i] as f32)
+                        / parent_area;
+                if best.as_ref().map_or(true, |b| cost < b.2) {
+                    best = Some((axis, lo + bin_width * (i + 1) as f32, cost));
+                }
+            }
+        }
+
+        best
+    }
+
+    // visits every leaf whose box the ray enters before `t_max`, `on_leaf` returns
+    // the new `t_max` and whether traversal should stop.
+    fn traverse<F>(&self, ray: &Ray, t_min: f32, t_max: f32, mut on_leaf: F)
+    where
+        F: FnMut(&[usize], f32) -> (f32, bool),
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut t_max = t_max;
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0usize);
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if node.bounds.intersect_ray(ray, t_min, t_max).is_none() {
+                continue;
+            }
+            if node.is_leaf() {
+                let tris = &self.tri_indices[node.left_or_first..node.left_or_first + node.count];
+                let (new_max, stop) = on_leaf(tris, t_max);
+                t_max = new_max;
+                if stop {
+                    return;
+                }
+                continue;
+            }
+
+            // push the farther child first so the nearer one is visited first
+            let (l, r) = (node.left_or_first, node.left_or_first + 1);
+            let tl = self.nodes[l].bounds.intersect_ray(ray, t_min, t_max);
+            let tr = self.nodes[r].bounds.intersect_ray(ray, t_min, t_max);
+            match (tl, tr) {
+                (Some(a), Some(b)) => {
+                    if a <= b {
+                        stack.push(r);
+                        stack.push(l);
+                    } else {
+                        stack.push(l);
+                        stack.push(r);
+                    }
+                }
+                (Some(_), None) => stack.push(l),
+                (None, Some(_)) => stack.push(r),
+                (None, None) => {}
+            }
+        }
+    }
+
+    pub fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<RayHit> {
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+        self.traverse(ray, t_min, t_max, |tris, t_max| {
+            let mut t_max = t_max;
+            for &t in tris {
+                if let Some((dist, u, v)) = intersect_triangle(&self.triangles[t], ray, t_min, t_max) {
+                    t_max = dist;
+                    closest = Some((t, dist, u, v));
+                }
+            }
+            (t_max, false)
+        });
+
+        closest.map(|(triangle, t, u, v)| RayHit {
+            triangle,
+            t,
+            barycentric: Vector3::from_xyz(1. - u - v, u, v),
+            position: ray.at(t),
+        })
+    }
+
+    pub fn closest_hit(&self, ray: &Ray) -> Option<RayHit> {
+        self.intersect(ray, 0., f32::INFINITY)
+    }
+
+    // any hit between `t_min` and `t_max`, for shadow rays
+    pub fn occluded(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let mut hit = false;
+        self.traverse(ray, t_min, t_max, |tris, t_max| {
+            hit = tris
+                .iter()
+                .any(|&t| intersect_triangle(&self.triangles[t], ray, t_min, t_max).is_some());
+            (t_max, hit)
+        });
+        hit
+    }
+
+    pub fn count_hits(&self, ray: &Ray) -> usize {
+        let mut hits = 0;
+        self.traverse(ray, 0., f32::INFINITY, |tris, t_max| {
+            hits += tris
+                .iter()
+                .filter(|&&t| intersect_triangle(&self.triangles[t], ray, 0., t_max).is_some())
+                .count();
+            (t_max, false)
+        });
+        hits
+    }
+
+    // parity test against a closed mesh, voted over three axes so a ray grazing an edge
+    // does not flip the answer.
+    pub fn contains(&self, point: &Vector3) -> bool {
+        if !self.bounds().contains(point) {
+            return false;
+        }
+        let dirs = [
+            Vector3::from_xyz(1., 0.000_123, 0.000_457),
+            Vector3::from_xyz(0.000_311, 1., 0.000_173),
+            Vector3::from_xyz(0.000_229, 0.000_389, 1.),
+        ];
+        let inside = dirs
+            .into_iter()
+            .filter(|d| self.count_hits(&Ray::new(point.clone(), d.clone())) % 2 == 1)
+            .count();
+        inside >= 2
+    }
+}
@@ -0,0 +1,490 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use gltf::animation::util::ReadOutputs;
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, RgbImage, RgbaImage};
+use log::warn;
+use tobj::{Material, Mesh, Model};
+
+use crate::department::model::animation::{AnimationClip, Channel, Interpolation, Rig, Skin, Track};
+use crate::department::model::load_error::LoadError;
+use crate::department::model::mesh_repair::{repair_model, LoadWarning};
+use crate::department::model::skeleton::{mul, Joint, JointPose, Mat4, Skeleton, SkinWeights, IDENTITY};
+use crate::department::model::texture::{MipTexture, TextureRole};
+use crate::department::model::triangle_resources::{MaterialRange, TriangleResources};
+use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::vector::{HVec4, Vector3};
+
+// a gltf file flattened into what the obj path produces: one model per primitive with
+// the node transforms baked in, and the pbr materials expressed as mtl parameters.
+// primitives skinned by the first skin stay in bind pose and are moved by `rig` instead
+pub struct GltfScene {
+    pub models: Vec<Model>,
+    pub materials: Vec<Material>,
+    // decoded base colour texture of every material
+    pub textures: Vec<Option<DynamicImage>>,
+    // joint weights per mesh position of every model, for the skinned ones
+    pub skins: Vec<Option<SkinWeights>>,
+    pub rig: Option<Arc<Rig>>,
+    pub warnings: Vec<LoadWarning>,
+}
+
+impl GltfScene {
+    // the cpu pipeline draws a single model, so every primitive is merged into one. every
+    // primitive keeps its material in `material_ranges`, but only the first texture is kept
+    pub fn into_triangle_resources(self) -> TriangleResources {
+        let mut mesh = Mesh::default();
+        let mut weights = SkinWeights::default();
+        let mut material_ranges = Vec::new();
+        let used: Vec<usize> = self.models.iter().filter_map(|m| m.mesh.material_id).collect();
+        let material = used
+            .iter()
+            .copied()
+            .find(|&i| self.textures[i].is_some())
+            .or_else(|| used.first().copied());
+        let has_colors = self.models.iter().all(|m| !m.mesh.vertex_color.is_empty());
+        for (model, skin) in self.models.iter().zip(&self.skins) {
+            let m = &model.mesh;
+            let (p, n, t) = (
+                (mesh.positions.len() / 3) as u32,
+                (mesh.normals.len() / 3) as u32,
+                (mesh.texcoords.len() / 2) as u32,
+            );
+            mesh.positions.extend(&m.positions);
+            mesh.normals.extend(&m.normals);
+            mesh.texcoords.extend(&m.texcoords);
+            if has_colors {
+                mesh.vertex_color.extend(&m.vertex_color);
+            }
+            let start = (mesh.indices.len() / 3) as u32;
+            mesh.indices.extend(m.indices.iter().map(|i| i + p));
+            if let Some(i) = m.material_id.filter(|&i| i < self.materials.len()) {
+                material_ranges.push(MaterialRange {
+                    triangles: start..(mesh.indices.len() / 3) as u32,
+                    material: self.materials[i].clone(),
+                    textured: Some(i) == material,
+                });
+            }
+            mesh.normal_indices.extend(m.normal_indices.iter().map(|i| i + n));
+            mesh.texcoord_indices.extend(m.texcoord_indices.iter().map(|i| i + t));
+
+            // static models get no weights at all and stay where they are
+            for i in 0..(m.positions.len() / 3) as u32 {
+                let (j, w) = skin.as_ref().map_or(([0; 4], [0.; 4]), |s| s.influence(i));
+                weights.joints.push(j);
+                weights.weights.push(w);
+            }
+        }
+
+        mesh.material_id = material;
+
+        let mut warnings = self.warnings;
+        let mut dropped: Vec<usize> = used
+            .iter()
+            .copied()
+            .filter(|&i| Some(i) != material && self.textures[i].is_some())
+            .collect();
+        dropped.sort_unstable();
+        dropped.dedup();
+        if let (Some(i), false) = (material, dropped.is_empty()) {
+            let w = LoadWarning::DroppedTextures {
+                kept: self.materials[i].name.clone(),
+                dropped: dropped.len(),
+            };
+            warn!("{}", w);
+            warnings.push(w);
+        }
+
+        let name = self.models.first().map(|m| m.name.clone()).unwrap_or_default();
+        let mut resources = TriangleResources::new(Model::new(mesh, name));
+        if let Some(i) = material {
+            resources.material = self.materials.get(i).cloned();
+            resources.texture = self
+                .textures
+                .get(i)
+                .cloned()
+                .flatten()
+                .map(|img| Arc::new(MipTexture::new(img, TextureRole::Diffuse)));
+        }
+        resources.material_ranges = material_ranges;
+        resources.warnings = warnings;
+        if self.skins.iter().any(Option::is_some) {
+            resources.skin = self.rig.map(|rig| Skin {
+                rig,
+                weights,
+                fit: None,
+            });
+        }
+        resources.build_vertex_buffer();
+        resources.build_lods();
+        resources
+    }
+}
+
+// reads .gltf with its buffers and images, or a self contained .glb
+pub fn load_gltf(path: &Path) -> Result<GltfScene, LoadError> {
+    let (document, buffers, images) = gltf::import(path).map_err(|source| LoadError::Gltf {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let mut warnings = Vec::new();
+    let images: Vec<Option<DynamicImage>> = images
+        .into_iter()
+        .enumerate()
+        .map(|(i, data)| {
+            let image = decode_image(data);
+            if image.is_none() {
+                warnings.push(LoadWarning::UnsupportedTexture { texture: i });
+            }
+            image
+        })
+        .collect();
+
+    let mut materials = Vec::new();
+    let mut textures = Vec::new();
+    for material in document.materials() {
+        let texture = material
+            .pbr_metallic_roughness()
+            .base_color_texture()
+            .and_then(|info| images.get(info.texture().source().index()).cloned().flatten());
+        materials.push(convert_material(&material));
+        textures.push(texture);
+    }
+    // primitives without a material use the spec's default one, appended at the end
+    let default_material = materials.len();
+    materials.push(pbr_material("default", [1., 1., 1., 1.], 1., 1., [0.; 3], false));
+    textures.push(None);
+
+    let rig = document
+        .skins()
+        .next()
+        .map(|skin| Arc::new(load_rig(&document, &skin, &buffers)));
+
+    let mut scene = GltfScene {
+        models: Vec::new(),
+        materials,
+        textures,
+        skins: Vec::new(),
+        rig,
+        warnings,
+    };
+
+    let root = document.default_scene().or_else(|| document.scenes().next());
+    match root {
+        Some(root) => {
+            for node in root.nodes() {
+                visit(&node, &HomoTransform::identity_matrix(), &buffers, default_material, &mut scene);
+            }
+        }
+        // no scene at all, show every mesh untransformed
+        None => {
+            for node in document.nodes().filter(|n| n.mesh().is_some()) {
+                add_mesh(&node, &HomoTransform::identity_matrix(), &buffers, default_material, &mut scene);
+            }
+        }
+    }
+
+    if scene.models.is_empty() {
+        return Err(LoadError::Empty {
+            path: path.to_path_buf(),
+        });
+    }
+    for w in &scene.warnings {
+        warn!("{}: {}", path.display(), w);
+    }
+    Ok(scene)
+}
+
+// gltf matrices are column major for column vectors, which is exactly the row major
+// layout our row vector convention needs
+fn visit(
+    node: &gltf::Node,
+    parent: &HomoTransform,
+    buffers: &[gltf::buffer::Data],
+    default_material: usize,
+    scene: &mut GltfScene,
+) {
+    let world = &HomoTransform::from_array(node.transform().matrix()) * parent;
+    add_mesh(node, &world, buffers, default_material, scene);
+    for child in node.children() {
+        visit(&child, &world, buffers, default_material, scene);
+    }
+}
+
+fn add_mesh(
+    node: &gltf::Node,
+    world: &HomoTransform,
+    buffers: &[gltf::buffer::Data],
+    default_material: usize,
+    scene: &mut GltfScene,
+) {
+    let Some(mesh) = node.mesh() else {
+        return;
+    };
+    // gltf ignores the transform of a skinned mesh node, its joints place it
+    let skinned = node.skin().map(|skin| skin.index() == 0);
+    let identity = HomoTransform::identity_matrix();
+    let world = if skinned == Some(true) { &identity } else { world };
+    let normal_mat = world
+        .inverse_matrix()
+        .map(|m| m.t())
+        .unwrap_or_else(HomoTransform::identity_matrix);
+
+    for (i, primitive) in mesh.primitives().enumerate() {
+        let name = format!(
+            "{}#{}",
+            mesh.name().or(node.name()).unwrap_or("mesh"),
+            i
+        );
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            scene.warnings.push(LoadWarning::UnsupportedPrimitive { model: name });
+            continue;
+        }
+        if skinned == Some(false) {
+            scene.warnings.push(LoadWarning::ExtraSkin { model: name.clone() });
+        }
+
+        let reader = primitive.reader(|b| Some(&buffers[b.index()].0[..]));
+        let Some(positions) = reader.read_positions() else {
+            continue;
+        };
+
+        let mut m = Mesh::default();
+        for p in positions {
+            let v = &HVec4::from_v3(Vector3::from_xyz(p[0], p[1], p[2])) * world;
+            m.positions.extend([v.x() / v.w(), v.y() / v.w(), v.z() / v.w()]);
+        }
+        let vertex_count = (m.positions.len() / 3) as u32;
+
+        if let Some(normals) = reader.read_normals() {
+            for n in normals {
+                let v = &Vector3::from_xyz(n[0], n[1], n[2]).to_linear_matrix() * &normal_mat;
+                let mut v = Vector3::from_xyz(v.x(), v.y(), v.z());
+                v.norm();
+                m.normals.extend([v.x(), v.y(), v.z()]);
+            }
+        }
+        if let Some(uvs) = reader.read_tex_coords(0) {
+            // gltf puts v = 0 at the top of the image, obj at the bottom
+            for uv in uvs.into_f32() {
+                m.texcoords.extend([uv[0], 1. - uv[1]]);
+            }
+        }
+        if let Some(colors) = reader.read_colors(0) {
+            for c in colors.into_rgb_f32() {
+                m.vertex_color.extend(c);
+            }
+        }
+
+        m.indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertex_count).collect(),
+        };
+        if !m.normals.is_empty() {
+            m.normal_indices = m.indices.clone();
+        }
+        if !m.texcoords.is_empty() {
+            m.texcoord_indices = m.indices.clone();
+        }
+        m.material_id = Some(primitive.material().index().unwrap_or(default_material));
+
+        let weights = match (skinned, reader.read_joints(0), reader.read_weights(0)) {
+            (Some(true), Some(joints), Some(weights)) => {
+                let mut skin = SkinWeights::default();
+                for (j, w) in joints.into_u16().zip(weights.into_f32()) {
+                    // exporters don't always normalize, a vertex must not grow or shrink
+                    let total: f32 = w.iter().sum();
+                    skin.joints.push(j);
+                    skin.weights.push(if total > 0. { w.map(|c| c / total) } else { w });
+                }
+                Some(skin)
+            }
+            _ => None,
+        };
+
+        let smoothing = vec![1; m.indices.len() / 3];
+        let mut model = Model::new(m, name);
+        repair_model(&mut model, &smoothing, &mut scene.warnings);
+        scene.models.push(model);
+        scene.skins.push(weights);
+    }
+}
+
+// the joints of `skin` with their rest pose, and every animation channel that moves one of them
+fn load_rig(document: &gltf::Document, skin: &gltf::Skin, buffers: &[gltf::buffer::Data]) -> Rig {
+    let (parents, worlds) = node_hierarchy(document);
+    let nodes: Vec<usize> = skin.joints().map(|j| j.index()).collect();
+    let joint_of = |node: usize| nodes.iter().position(|&n| n == node);
+
+    let inverse_binds: Vec<Mat4> = skin
+        .reader(|b| Some(&buffers[b.index()].0[..]))
+        .read_inverse_bind_matrices()
+        .map(|m| m.collect())
+        .unwrap_or_default();
+    let joints = skin
+        .joints()
+        .enumerate()
+        .map(|(i, node)| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            let parent = parents[node.index()];
+            let joint_parent = parent.and_then(joint_of);
+            Joint {
+                name: node.name().unwrap_or("joint").to_string(),
+                parent: joint_parent,
+                rest: JointPose {
+                    translation,
+                    rotation,
+                    scale,
+                },
+                inverse_bind: inverse_binds.get(i).copied().unwrap_or(IDENTITY),
+                base: match (joint_parent, parent) {
+                    (None, Some(p)) => worlds[p],
+                    _ => IDENTITY,
+                },
+            }
+        })
+        .collect();
+
+    let clips = document
+        .animations()
+        .enumerate()
+        .filter_map(|(i, animation)| {
+            let tracks: Vec<Track> = animation
+                .channels()
+                .filter_map(|channel| {
+                    let joint = joint_of(channel.target().node().index())?;
+                    let reader = channel.reader(|b| Some(&buffers[b.index()].0[..]));
+                    let times: Vec<f32> = reader.read_inputs()?.collect();
+                    let (property, values) = match reader.read_outputs()? {
+                        ReadOutputs::Translations(t) => (Channel::Translation, t.map(|[x, y, z]| [x, y, z, 0.]).collect()),
+                        ReadOutputs::Rotations(r) => (Channel::Rotation, r.into_f32().collect()),
+                        ReadOutputs::Scales(s) => (Channel::Scale, s.map(|[x, y, z]| [x, y, z, 0.]).collect()),
+                        // morph targets aren't supported
+                        ReadOutputs::MorphTargetWeights(_) => return None,
+                    };
+                    let interpolation = match channel.sampler().interpolation() {
+                        gltf::animation::Interpolation::Step => Interpolation::Step,
+                        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+                    };
+                    Some(Track {
+                        joint,
+                        channel: property,
+                        interpolation,
+                        times,
+                        values,
+                    })
+                })
+                .collect();
+            let name = animation.name().map_or_else(|| format!("clip {}", i), str::to_string);
+            (!tracks.is_empty()).then(|| AnimationClip::new(name, tracks))
+        })
+        .collect();
+
+    Rig {
+        skeleton: Skeleton::new(joints),
+        clips,
+    }
+}
+
+// parent of every node and its world transform without any animation applied
+fn node_hierarchy(document: &gltf::Document) -> (Vec<Option<usize>>, Vec<Mat4>) {
+    let locals: Vec<Mat4> = document.nodes().map(|n| n.transform().matrix()).collect();
+    let mut parents = vec![None; locals.len()];
+    for node in document.nodes() {
+        for child in node.children() {
+            parents[child.index()] = Some(node.index());
+        }
+    }
+    let worlds = (0..locals.len())
+        .map(|n| {
+            let mut world = locals[n];
+            let mut up = parents[n];
+            // the depth bound only matters for a broken file with a cycle
+            for _ in 0..locals.len() {
+                let Some(p) = up else {
+                    break;
+                };
+                world = mul(&world, &locals[p]);
+                up = parents[p];
+            }
+            world
+        })
+        .collect();
+    (parents, worlds)
+}
+
+fn convert_material(material: &gltf::Material) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    pbr_material(
+        material.name().unwrap_or("material"),
+        pbr.base_color_factor(),
+        pbr.metallic_factor(),
+        pbr.roughness_factor(),
+        material.emissive_factor(),
+        pbr.base_color_texture().is_some(),
+    )
+}
+
+// metallic/roughness mapped onto the phong-ish parameters the ray and path tracers read,
+// the raw factors are kept as the `Pm`/`Pr` pbr extension of mtl
+fn pbr_material(
+    name: &str,
+    base_color: [f32; 4],
+    metallic: f32,
+    roughness: f32,
+    emissive: [f32; 3],
+    textured: bool,
+) -> Material {
+    let [r, g, b, a] = base_color;
+    let roughness = roughness.max(0.02);
+
+    let mut m = Material::default();
+    m.name = name.to_string();
+    m.diffuse = Some([r, g, b].map(|c| c * (1. - metallic)));
+    m.specular = Some([r, g, b].map(|c| 0.04 + (c - 0.04) * metallic));
+    // inverse of the phong exponent to roughness mapping of the path tracer
+    m.shininess = Some(2. / (roughness * roughness) - 2.);
+    m.dissolve = Some(a);
+    m.unknown_param.insert("Pm".to_string(), metallic.to_string());
+    m.unknown_param.insert("Pr".to_string(), roughness.to_string());
+
+    if emissive.iter().any(|e| *e > 0.) {
+        m.unknown_param.insert("Ke".to_string(), format!("{} {} {}", emissive[0], emissive[1], emissive[2]));
+    }
+    if textured {
+        m.diffuse_texture = Some(format!("{}.base_color", name));
+    }
+    m
+}
+
+fn decode_image(data: gltf::image::Data) -> Option<DynamicImage> {
+    use gltf::image::Format;
+    let (w, h) = (data.width, data.height);
+    match data.format {
+        Format::R8 => GrayImage::from_raw(w, h, data.pixels).map(DynamicImage::ImageLuma8),
+        Format::R8G8 => GrayAlphaImage::from_raw(w, h, data.pixels).map(DynamicImage::ImageLumaA8),
+        Format::R8G8B8 => RgbImage::from_raw(w, h, data.pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => RgbaImage::from_raw(w, h, data.pixels).map(DynamicImage::ImageRgba8),
+        // wider channels come as native endian bytes
+        Format::R16 => {
+            ImageBuffer::from_raw(w, h, bytemuck::pod_collect_to_vec(&data.pixels)).map(DynamicImage::ImageLuma16)
+        }
+        Format::R16G16 => {
+            ImageBuffer::from_raw(w, h, bytemuck::pod_collect_to_vec(&data.pixels)).map(DynamicImage::ImageLumaA16)
+        }
+        Format::R16G16B16 => {
+            ImageBuffer::from_raw(w, h, bytemuck::pod_collect_to_vec(&data.pixels)).map(DynamicImage::ImageRgb16)
+        }
+        Format::R16G16B16A16 => {
+            ImageBuffer::from_raw(w, h, bytemuck::pod_collect_to_vec(&data.pixels)).map(DynamicImage::ImageRgba16)
+        }
+        Format::R32G32B32FLOAT => {
+            ImageBuffer::from_raw(w, h, bytemuck::pod_collect_to_vec(&data.pixels)).map(DynamicImage::ImageRgb32F)
+        }
+        Format::R32G32B32A32FLOAT => {
+            ImageBuffer::from_raw(w, h, bytemuck::pod_collect_to_vec(&data.pixels)).map(DynamicImage::ImageRgba32F)
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+// everything that can stop a model from loading, warnings that could be worked
+// around are reported through `LoadWarning` instead
+#[derive(Debug)]
+pub enum LoadError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, source: tobj::LoadError },
+    Gltf { path: PathBuf, source: gltf::Error },
+    // stl and ply, which are parsed here rather than by a crate
+    Format { path: PathBuf, message: String },
+    // the file had no geometry at all
+    Empty { path: PathBuf },
+    MissingMaterial { path: PathBuf, material: usize },
+    TextureDecode { path: PathBuf, source: image::ImageError },
+    // a frame of an obj sequence whose triangles differ from the first frame's
+    SequenceMismatch { path: PathBuf, first: PathBuf },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io { path, source } => write!(f, "can't read {}: {}", path.display(), source),
+            LoadError::Parse { path, source } => write!(f, "{} is not a valid model: {}", path.display(), source),
+            LoadError::Gltf { path, source } => write!(f, "{} is not a valid gltf: {}", path.display(), source),
+            LoadError::Format { path, message } => write!(f, "{} is not a valid model: {}", path.display(), message),
+            LoadError::Empty { path } => write!(f, "{} contains no meshes", path.display()),
+            LoadError::MissingMaterial { path, material } => {
+                write!(f, "{} uses material #{} which could not be loaded", path.display(), material)
+            }
+            LoadError::TextureDecode { path, source } => {
+                write!(f, "can't decode texture {}: {}", path.display(), source)
+            }
+            LoadError::SequenceMismatch { path, first } => {
+                write!(f, "{} doesn't have the same triangles as {}", path.display(), first.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            LoadError::Parse { source, .. } => Some(source),
+            LoadError::Gltf { source, .. } => Some(source),
+            LoadError::TextureDecode { source, .. } => Some(source),
+            LoadError::Format { .. }
+            | LoadError::Empty { .. }
+            | LoadError::MissingMaterial { .. }
+            | LoadError::SequenceMismatch { .. } => None,
+        }
+    }
+}
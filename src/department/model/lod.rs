@@ -0,0 +1,90 @@
+use std::f32::consts::PI;
+
+use tobj::Model;
+
+use crate::department::model::simplify::{simplify, Simplified};
+use crate::department::model::skeleton::SkinWeights;
+use crate::department::model::vertex_buffer::VertexBuffer;
+
+// below this a model is already cheap, coarser levels would only look broken
+const MIN_TRIANGLES: usize = 64;
+const MAX_LEVELS: usize = 6;
+// covered pixels per triangle, anything denser is detail the frame can't show
+const PIXELS_PER_TRIANGLE: f32 = 4.;
+
+// coarser versions of one vertex buffer, each about half of the one before
+#[derive(Debug, Clone, Default)]
+pub struct LodChain {
+    pub levels: Vec<Simplified>,
+}
+
+impl LodChain {
+    pub fn build(full: &VertexBuffer) -> Self {
+        let mut levels: Vec<Simplified> = Vec::new();
+        let mut count = full.triangle_count();
+        while levels.len() < MAX_LEVELS && count / 2 >= MIN_TRIANGLES {
+            // each level starts from the previous one, source triangles still point at `full`
+            let next = match levels.last() {
+                Some(previous) => {
+                    let mut next = simplify(&previous.buffer, count / 2);
+                    for t in next.source_triangles.iter_mut() {
+                        *t = previous.source_triangles[*t as usize];
+                    }
+                    next
+                }
+                None => simplify(full, count / 2),
+            };
+            // borders and flips stopped the collapses early, the mesh won't get any cheaper
+            let next_count = next.buffer.triangle_count();
+            if next_count * 10 > count * 9 {
+                break;
+            }
+            count = next_count;
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    // 0 is `full` itself, `i` is `levels[i - 1]`
+    pub fn level(&self, full_triangles: usize, projected_radius: f32) -> usize {
+        let counts: Vec<usize> = std::iter::once(full_triangles)
+            .chain(self.levels.iter().map(|l| l.buffer.triangle_count()))
+            .collect();
+        level_for(&counts, projected_radius)
+    }
+}
+
+// a model with the detail levels of its raw uv vertex buffer, what caches and uploads carry around
+#[derive(Debug, Clone)]
+pub struct MeshLods {
+    pub model: Model,
+    pub lods: LodChain,
+    // joint weights per mesh position of a skinned model
+    pub skin: Option<SkinWeights>,
+}
+
+impl MeshLods {
+    pub fn build(model: Model) -> Self {
+        let lods = LodChain::build(&VertexBuffer::from_mesh(&model.mesh, None));
+        Self { model, lods, skin: None }
+    }
+}
+
+// finest level that fits the pixels the bounding sphere covers, `triangle_counts`
+// starts at full detail and gets coarser
+pub fn level_for(triangle_counts: &[usize], projected_radius: f32) -> usize {
+    let budget = PI * projected_radius * projected_radius / PIXELS_PER_TRIANGLE;
+    triangle_counts
+        .iter()
+        .position(|&c| c as f32 <= budget)
+        .unwrap_or(triangle_counts.len().saturating_sub(1))
+}
+
+// radius in pixels of a sphere `distance` away from a camera with vertical field of
+// view `fov_y` (radians), infinite once the eye is inside it
+pub fn projected_radius(radius: f32, distance: f32, fov_y: f32, viewport_height: f32) -> f32 {
+    if distance <= radius {
+        return f32::INFINITY;
+    }
+    radius / (distance * (fov_y * 0.5).tan()) * viewport_height * 0.5
+}
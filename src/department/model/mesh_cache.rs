@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use memmap2::Mmap;
+use tobj::{Material, Mesh, Model};
+
+use crate::department::model::auto_fit::{BoundingSphere, ModelBounds};
+use crate::department::model::bvh::Aabb;
+use crate::department::model::lod::{LodChain, MeshLods};
+use crate::department::model::simplify::Simplified;
+use crate::department::model::vertex_buffer::{PackedVertex, VertexBuffer};
+use crate::department::preview::vector::Vector3;
+
+const MAGIC: [u8; 8] = *b"DNMESH\0\0";
+// bump whenever the layout below or what the loader does to a mesh changes
+const FORMAT_VERSION: u32 = 1;
+// stands in for a missing material index or illumination model
+const ABSENT: u32 = u32::MAX;
+
+// everything a loaded obj turns into, as written next to it. little endian, every
+// section 4 byte aligned so vertex and index arrays can be read straight from the map:
+//
+//   magic, version u32, source hash u64, bounds (10 f32)
+//   materials: count, then name and every mtl field with a presence flag
+//   models: count, then name, material, colour flag, vertex buffer, lod levels
+pub struct MeshCache {
+    pub meshes: Vec<MeshLods>,
+    pub materials: Vec<Material>,
+    pub bounds: ModelBounds,
+}
+
+impl MeshCache {
+    pub fn build(models: Vec<Model>, materials: Vec<Material>) -> Self {
+        let bounds = ModelBounds::from_models(&models);
+        Self {
+            meshes: models.into_iter().map(MeshLods::build).collect(),
+            materials,
+            bounds,
+        }
+    }
+
+    pub fn into_models(self) -> (Vec<Model>, Vec<Material>) {
+        (self.meshes.into_iter().map(|m| m.model).collect(), self.materials)
+    }
+}
+
+// `ctree3.obj` is cached as `ctree3.dnmesh` in the same directory
+pub fn cache_path(source: &Path) -> PathBuf {
+    source.with_extension("dnmesh")
+}
+
+// fnv-1a over the obj and every mtl library it names, a missing library hashes as absent
+pub fn source_hash(obj_path: &Path, obj: &[u8]) -> u64 {
+    let mut hash = Fnv::new();
+    hash.write(obj);
+    let dir = obj_path.parent().unwrap_or(Path::new("."));
+    for name in mtl_libraries(obj) {
+        hash.write(name.as_bytes());
+        match fs::read(dir.join(&name)) {
+            Ok(mtl) => hash.write(&mtl),
+            Err(_) => hash.write(&[0]),
+        }
+    }
+    hash.0
+}
+
+// the `mtllib` names of an obj, relative to its directory
+pub fn mtl_libraries(obj: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(obj)
+        .lines()
+        .filter_map(|line| match line.trim().split_once(' ') {
+            Some(("mtllib", name)) => Some(name.trim().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+// `None` for a missing, stale or damaged cache, the caller reloads the source then
+pub fn read_cache(path: &Path, hash: u64) -> Option<MeshCache> {
+    let file = fs::File::open(path).ok()?;
+    // the cache is only ever replaced by rename, never written in place
+    let map = unsafe { Mmap::map(&file) }.ok()?;
+    let cache = decode(&map, hash);
+    if cache.is_none() {
+        debug!("{}: mesh cache is stale or damaged", path.display());
+    }
+    cache
+}
+
+pub fn write_cache(path: &Path, hash: u64, cache: &MeshCache) -> io::Result<()> {
+    let mut w = Writer::default();
+    w.data.extend(MAGIC);
+    w.u32(FORMAT_VERSION);
+    w.data.extend(hash.to_le_bytes());
+    let b = &cache.bounds;
+    for v in [&b.aabb.min, &b.aabb.max, &b.sphere.center] {
+        w.f32s(&[v.x(), v.y(), v.z()]);
+    }
+    w.f32s(&[b.sphere.radius]);
+
+    w.u32(cache.materials.len() as u32);
+    for m in &cache.materials {
+        write_material(&mut w, m);
+    }
+
+    w.u32(cache.meshes.len() as u32);
+    for mesh in &cache.meshes {
+        let m = &mesh.model;
+        w.str(&m.name);
+        w.u32(m.mesh.material_id.map_or(ABSENT, |i| i as u32));
+        w.u32(!m.mesh.vertex_color.is_empty() as u32);
+        write_buffer(&mut w, &VertexBuffer::from_mesh(&m.mesh, None));
+        w.u32(mesh.lods.levels.len() as u32);
+        for level in &mesh.lods.levels {
+            write_buffer(&mut w, &level.buffer);
+            w.pod(&level.source_triangles);
+        }
+    }
+
+    // written aside and renamed over, a reader never maps a half written file
+    let partial = path.with_extension("dnmesh.partial");
+    fs::write(&partial, &w.data)?;
+    fs::rename(&partial, path)
+}
+
+fn decode(data: &[u8], hash: u64) -> Option<MeshCache> {
+    let mut r = Reader { data, at: 0 };
+    if r.take(MAGIC.len())? != MAGIC || r.u32()? != FORMAT_VERSION {
+        return None;
+    }
+    if u64::from_le_bytes(r.take(8)?.try_into().ok()?) != hash {
+        return None;
+    }
+    let mut vector = || r.f32s(3).map(|v| Vector3::from_xyz(v[0], v[1], v[2]));
+    let (min, max, center) = (vector()?, vector()?, vector()?);
+    let radius = r.f32s(1)?[0];
+    let bounds = ModelBounds {
+        aabb: Aabb { min, max },
+        sphere: BoundingSphere { center, radius },
+    };
+
+    let materials = (0..r.u32()?).map(|_| read_material(&mut r)).collect::<Option<Vec<_>>>()?;
+
+    let mut meshes = Vec::new();
+    for _ in 0..r.u32()? {
+        let name = r.str()?;
+        let material_id = r.u32()?;
+        let has_colors = r.u32()? != 0;
+        let buffer = read_buffer(&mut r)?;
+        let mut lods = LodChain::default();
+        for _ in 0..r.u32()? {
+            let buffer = read_buffer(&mut r)?;
+            let source_triangles = r.pod::<u32>()?.to_vec();
+            lods.levels.push(Simplified {
+                buffer,
+                source_triangles,
+            });
+        }
+        let mesh = mesh_from_buffer(&buffer, (material_id != ABSENT).then_some(material_id as usize), has_colors);
+        meshes.push(MeshLods {
+            model: Model::new(mesh, name),
+            lods,
+            skin: None,
+        });
+    }
+    Some(MeshCache {
+        meshes,
+        materials,
+        bounds,
+    })
+}
+
+// one index for all attributes, `VertexBuffer::from_mesh` gives back the same buffer
+// so the cached lod source triangles still line up
+fn mesh_from_buffer(buffer: &VertexBuffer, material_id: Option<usize>, has_colors: bool) -> Mesh {
+    let mut mesh = Mesh::default();
+    for v in &buffer.vertices {
+        mesh.positions.extend(v.position);
+        mesh.normals.extend(v.normal);
+        mesh.texcoords.extend(v.uv);
+        if has_colors {
+            mesh.vertex_color.extend(v.color);
+        }
+    }
+    mesh.indices = buffer.indices.clone();
+    mesh.normal_indices = buffer.indices.clone();
+    mesh.texcoord_indices = buffer.indices.clone();
+    mesh.material_id = material_id;
+    mesh
+}
+
+fn write_buffer(w: &mut Writer, buffer: &VertexBuffer) {
+    w.pod(&buffer.vertices);
+    w.pod(&buffer.indices);
+}
+
+fn read_buffer(r: &mut Reader) -> Option<VertexBuffer> {
+    Some(VertexBuffer {
+        vertices: r.pod::<PackedVertex>()?.to_vec(),
+        indices: r.pod::<u32>()?.to_vec(),
+        position_indices: Vec::new(),
+    })
+}
+
+fn write_material(w: &mut Writer, m: &Material) {
+    w.str(&m.name);
+    for color in [&m.ambient, &m.diffuse, &m.specular] {
+        w.opt_f32s(color.as_ref().map(|c| &c[..]));
+    }
+    for scalar in [&m.shininess, &m.dissolve, &m.optical_density] {
+        w.opt_f32s(scalar.as_ref().map(std::slice::from_ref));
+    }
+    for texture in [
+        &m.ambient_texture,
+        &m.diffuse_texture,
+        &m.specular_texture,
+        &m.normal_texture,
+        &m.shininess_texture,
+        &m.dissolve_texture,
+    ] {
+        w.opt_str(texture.as_deref());
+    }
+    w.u32(m.illumination_model.map_or(ABSENT, u32::from));
+    // sorted, the same source always writes the same bytes
+    let mut params: Vec<_> = m.unknown_param.iter().collect();
+    params.sort();
+    w.u32(params.len() as u32);
+    for (k, v) in params {
+        w.str(k);
+        w.str(v);
+    }
+}
+
+fn read_material(r: &mut Reader) -> Option<Material> {
+    let mut m = Material::default();
+    m.name = r.str()?;
+    let mut color = || r.opt_f32s(3).map(|c| c.map(|c| [c[0], c[1], c[2]]));
+    m.ambient = color()?;
+    m.diffuse = color()?;
+    m.specular = color()?;
+    let mut scalar = || r.opt_f32s(1).map(|c| c.map(|c| c[0]));
+    m.shininess = scalar()?;
+    m.dissolve = scalar()?;
+    m.optical_density = scalar()?;
+    m.ambient_texture = r.opt_str()?;
+    m.diffuse_texture = r.opt_str()?;
+    m.specular_texture = r.opt_str()?;
+    m.normal_texture = r.opt_str()?;
+    m.shininess_texture = r.opt_str()?;
+    m.dissolve_texture = r.opt_str()?;
+    let illumination = r.u32()?;
+    m.illumination_model = (illumination != ABSENT).then_some(illumination as u8);
+    let mut params = HashMap::new();
+    for _ in 0..r.u32()? {
+        params.insert(r.str()?, r.str()?);
+    }
+    m.unknown_param = params;
+    Some(m)
+}
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn u32(&mut self, v: u32) {
+        self.data.extend(v.to_le_bytes());
+    }
+
+    fn f32s(&mut self, v: &[f32]) {
+        v.iter().for_each(|f| self.data.extend(f.to_le_bytes()));
+    }
+
+    // element count, then the raw elements padded to 4 bytes
+    fn pod<T: bytemuck::Pod>(&mut self, v: &[T]) {
+        self.u32(v.len() as u32);
+        self.data.extend_from_slice(bytemuck::cast_slice(v));
+        self.pad();
+    }
+
+    fn str(&mut self, s: &str) {
+        self.pod(s.as_bytes());
+    }
+
+    fn opt_f32s(&mut self, v: Option<&[f32]>) {
+        self.u32(v.is_some() as u32);
+        if let Some(v) = v {
+            self.f32s(v);
+        }
+    }
+
+    fn opt_str(&mut self, s: Option<&str>) {
+        self.u32(s.is_some() as u32);
+        if let Some(s) = s {
+            self.str(s);
+        }
+    }
+
+    fn pad(&mut self) {
+        while self.data.len() % 4 != 0 {
+            self.data.push(0);
+        }
+    }
+}
+
+// every read is bounds checked, a truncated file just ends up as `None`
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.at..self.at.checked_add(n)?)?;
+        self.at += n;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn f32s(&mut self, n: usize) -> Option<Vec<f32>> {
+        let bytes = self.take(n * 4)?;
+        Some(bytes.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect())
+    }
+
+    // borrowed straight from the map, the file is 4 byte aligned throughout
+    fn pod<T: bytemuck::Pod>(&mut self) -> Option<&'a [T]> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len.checked_mul(std::mem::size_of::<T>())?)?;
+        self.at = self.at.next_multiple_of(4);
+        bytemuck::try_cast_slice(bytes).ok()
+    }
+
+    fn str(&mut self) -> Option<String> {
+        String::from_utf8(self.pod::<u8>()?.to_vec()).ok()
+    }
+
+    fn opt_f32s(&mut self, n: usize) -> Option<Option<Vec<f32>>> {
+        match self.u32()? {
+            0 => Some(None),
+            _ => self.f32s(n).map(Some),
+        }
+    }
+
+    fn opt_str(&mut self) -> Option<Option<String>> {
+        match self.u32()? {
+            0 => Some(None),
+            _ => self.str().map(Some),
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use tobj::{LoadOptions, Mesh, Model};
+
+// faces are triangulated by `repair_model` instead of tobj, which only fans them
+// and forgets the original face boundaries that smoothing groups are attached to
+pub fn obj_load_options() -> LoadOptions {
+    LoadOptions {
+        triangulate: false,
+        ignore_points: true,
+        ignore_lines: true,
+        ..Default::default()
+    }
+}
+
+// something about the source file was off, but it could still be turned into triangles
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadWarning {
+    MissingNormals { model: String },
+    MissingTexcoords { model: String },
+    Triangulated { model: String, faces: usize },
+    DegenerateFace { model: String, face: usize },
+    IndexOutOfRange { model: String, face: usize },
+    SmoothingGroupMismatch { expected: usize, found: usize },
+    UnsupportedPrimitive { model: String },
+    UnsupportedTexture { texture: usize },
+    ExtraSkin { model: String },
+    DroppedTextures { kept: String, dropped: usize },
+}
+
+impl Display for LoadWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadWarning::MissingNormals { model } => {
+                write!(f, "model '{}' has no normals, generated them from smoothing groups", model)
+            }
+            LoadWarning::MissingTexcoords { model } => {
+                write!(f, "model '{}' has no texture coordinates, using (0, 0)", model)
+            }
+            LoadWarning::Triangulated { model, faces } => {
+                write!(f, "model '{}' had {} polygons with more than 3 corners, triangulated", model, faces)
+            }
+            LoadWarning::DegenerateFace { model, face } => {
+                write!(f, "model '{}' face {} has no area, dropped", model, face)
+            }
+            LoadWarning::IndexOutOfRange { model, face } => {
+                write!(f, "model '{}' face {} references a missing vertex, dropped", model, face)
+            }
+            LoadWarning::SmoothingGroupMismatch { expected, found } => {
+                write!(
+                    f,
+                    "found {} smoothing group entries for {} faces, smoothing everything",
+                    found, expected
+                )
+            }
+            LoadWarning::UnsupportedPrimitive { model } => {
+                write!(f, "model '{}' is made of points or lines, skipped", model)
+            }
+            LoadWarning::UnsupportedTexture { texture } => {
+                write!(f, "texture {} has a pixel format that can't be shown, ignored", texture)
+            }
+            LoadWarning::ExtraSkin { model } => {
+                write!(f, "model '{}' uses a second skin, only the first one is animated", model)
+            }
+            LoadWarning::DroppedTextures { kept, dropped } => {
+                write!(
+                    f,
+                    "the cpu renderers show one texture, kept the one of '{}' and drew {} more untextured",
+                    kept, dropped
+                )
+            }
+        }
+    }
+}
+
+// smoothing group of every `f` line in file order, 0 means flat shaded
+pub fn scan_smoothing_groups(text: &str) -> Vec<u32> {
+    let mut current = 0;
+    let mut groups = Vec::new();
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("s") => {
+                current = match words.next() {
+                    Some("off") | None => 0,
+                    Some(g) => g.parse().unwrap_or(0),
+                };
+            }
+            Some("f") => groups.push(current),
+            _ => {}
+        }
+    }
+    groups
+}
+
+// splits the per-file smoothing groups between the models, tobj emits them in file order
+pub fn split_smoothing_groups(models: &[Model], groups: &[u32], warnings: &mut Vec<LoadWarning>) -> Vec<Vec<u32>> {
+    let counts: Vec<usize> = models.iter().map(|m| face_arities(&m.mesh).len()).collect();
+    let expected: usize = counts.iter().sum();
+    if expected != groups.len() {
+        warnings.push(LoadWarning::SmoothingGroupMismatch {
+            expected,
+            found: groups.len(),
+        });
+        return counts.iter().map(|&c| vec![1; c]).collect();
+    }
+
+    let mut start = 0;
+    counts
+        .iter()
+        .map(|&c| {
+            let g = groups[start..start + c].to_vec();
+            start += c;
+            g
+        })
+        .collect()
+}
+
+// leaves the mesh as plain triangles with complete normal and texcoord indices
+pub fn repair_model(model: &mut Model, smoothing: &[u32], warnings: &mut Vec<LoadWarning>) {
+    let name = model.name.clone();
+    let mesh = &mut model.mesh;
+    let arities = face_arities(mesh);
+    let vertex_count = (mesh.positions.len() / 3) as u32;
+    let has_normals = !mesh.normals.is_empty() && mesh.normal_indices.len() == mesh.indices.len();
+    let has_texcoords = !mesh.texcoords.is_empty() && mesh.texcoord_indices.len() == mesh.indices.len();
+
+    let mut indices = Vec::with_capacity(mesh.indices.len());
+    let mut normal_indices = Vec::with_capacity(mesh.indices.len());
+    let mut texcoord_indices = Vec::with_capacity(mesh.indices.len());
+    let mut triangle_groups = Vec::with_capacity(mesh.indices.len() / 3);
+    let mut polygons = 0;
+
+    let mut next = 0;
+    for (face, &arity) in arities.iter().enumerate() {
+        let corners: Vec<usize> = (next..next + arity as usize).collect();
+        next += arity as usize;
+
+        if corners.iter().any(|&c| mesh.indices[c] >= vertex_count) {
+            warnings.push(LoadWarning::IndexOutOfRange {
+                model: name.clone(),
+                face,
+            });
+            continue;
+        }
+        if arity > 3 {
+            polygons += 1;
+        }
+
+        let positions: Vec<[f32; 3]> = corners.iter().map(|&c| position(mesh, mesh.indices[c])).collect();
+        let triangles = triangulate(&positions);
+        if triangles.is_empty() {
+            warnings.push(LoadWarning::DegenerateFace {
+                model: name.clone(),
+                face,
+            });
+            continue;
+        }
+
+        for [a, b, c] in triangles {
+            for k in [a, b, c] {
+                let corner = corners[k];
+                indices.push(mesh.indices[corner]);
+                if has_normals {
+                    normal_indices.push(mesh.normal_indices[corner]);
+                }
+                if has_texcoords {
+                    texcoord_indices.push(mesh.texcoord_indices[corner]);
+                }
+            }
+            triangle_groups.push(smoothing.get(face).copied().unwrap_or(1));
+        }
+    }
+
+    if polygons > 0 {
+        warnings.push(LoadWarning::Triangulated {
+            model: name.clone(),
+            faces: polygons,
+        });
+    }
+
+    mesh.indices = indices;
+    mesh.face_arities.clear();
+
+    if has_normals {
+        mesh.normal_indices = normal_indices;
+    } else {
+        warnings.push(LoadWarning::MissingNormals { model: name.clone() });
+        generate_normals(mesh, &triangle_groups);
+    }
+
+    if has_texcoords {
+        mesh.texcoord_indices = texcoord_indices;
+    } else {
+        warnings.push(LoadWarning::MissingTexcoords { model: name });
+        mesh.texcoords = vec![0., 0.];
+        mesh.texcoord_indices = vec![0; mesh.indices.len()];
+    }
+}
+
+// an empty arity list means every face already is a triangle
+fn face_arities(mesh: &Mesh) -> Vec<u32> {
+    if mesh.face_arities.is_empty() {
+        vec![3; mesh.indices.len() / 3]
+    } else {
+        mesh.face_arities.clone()
+    }
+}
+
+fn position(mesh: &Mesh, i: u32) -> [f32; 3] {
+    let i = i as usize * 3;
+    [mesh.positions[i], mesh.positions[i + 1], mesh.positions[i + 2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len > f32::EPSILON {
+        [v[0] / len, v[1] / len, v[2] / len]
+    } else {
+        [0., 0., 1.]
+    }
+}
+
+// ear clipping in the plane the polygon faces the most, corner indices keep the winding
+fn triangulate(positions: &[[f32; 3]]) -> Vec<[usize; 3]> {
+    let n = positions.len();
+    if n < 3 {
+        return Vec::new();
+    }
+
+    // newell normal, robust for slightly non planar faces
+    let mut normal = [0f32; 3];
+    for i in 0..n {
+        let (a, b) = (positions[i], positions[(i + 1) % n]);
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+    let abs = normal.map(f32::abs);
+    if abs[0] + abs[1] + abs[2] <= f32::EPSILON {
+        return Vec::new();
+    }
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // drop the dominant axis and keep the projection counter clockwise
+    let (u, v, flip) = if abs[0] >= abs[1] && abs[0] >= abs[2] {
+        (1, 2, normal[0] < 0.)
+    } else if abs[1] >= abs[2] {
+        (2, 0, normal[1] < 0.)
+    } else {
+        (0, 1, normal[2] < 0.)
+    };
+    let points: Vec<(f32, f32)> = positions
+        .iter()
+        .map(|p| if flip { (p[v], p[u]) } else { (p[u], p[v]) })
+        .collect();
+
+    let area = |a: usize, b: usize, c: usize| {
+        let (pa, pb, pc) = (points[a], points[b], points[c]);
+        (pb.0 - pa.0) * (pc.1 - pa.1) - (pb.1 - pa.1) * (pc.0 - pa.0)
+    };
+    let inside = |p: usize, a: usize, b: usize, c: usize| {
+        let d = [
+            area(a, b, p),
+            area(b, c, p),
+            area(c, a, p),
+        ];
+        d.iter().all(|&x| x >= 0.)
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = (remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]);
+            area(a, b, c) > 0.
+                && !remaining
+                    .iter()
+                    .any(|&p| p != a && p != b && p != c && inside(p, a, b, c))
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]]);
+                remaining.remove(i);
+            }
+            // self intersecting or collinear leftovers, fan the rest like tobj would
+            None => {
+                for i in 1..m - 1 {
+                    triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+                }
+                return triangles;
+            }
+        }
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+// group 0 gets face normals, corners sharing a position and a nonzero group
+// share the area weighted average of their faces
+fn generate_normals(mesh: &mut Mesh, triangle_groups: &[u32]) {
+    let face_normals: Vec<[f32; 3]> = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|t| {
+            let (a, b, c) = (position(mesh, t[0]), position(mesh, t[1]), position(mesh, t[2]));
+            cross(sub(b, a), sub(c, a))
+        })
+        .collect();
+
+    let mut smooth: HashMap<(u32, u32), [f32; 3]> = HashMap::new();
+    for (t, tri) in mesh.indices.chunks_exact(3).enumerate() {
+        let group = triangle_groups[t];
+        if group == 0 {
+            continue;
+        }
+        for &p in tri {
+            let acc = smooth.entry((p, group)).or_insert([0.; 3]);
+            for k in 0..3 {
+                acc[k] += face_normals[t][k];
+            }
+        }
+    }
+
+    let mut normals = Vec::new();
+    let mut normal_indices = Vec::with_capacity(mesh.indices.len());
+    let mut slots: HashMap<(u32, u32), u32> = HashMap::new();
+    for (t, tri) in mesh.indices.chunks_exact(3).enumerate() {
+        let group = triangle_groups[t];
+        if group == 0 {
+            normals.extend(normalize(face_normals[t]));
+        }
+        let flat = (normals.len() / 3).saturating_sub(1) as u32;
+        for &p in tri {
+            let index = if group == 0 {
+                flat
+            } else {
+                *slots.entry((p, group)).or_insert_with(|| {
+                    normals.extend(normalize(smooth[&(p, group)]));
+                    (normals.len() / 3 - 1) as u32
+                })
+            };
+            normal_indices.push(index);
+        }
+    }
+
+    mesh.normals = normals;
+    mesh.normal_indices = normal_indices;
+}
@@ -3,5 +3,21 @@ pub mod object_buffer;
 pub mod object_loader;
 pub mod render_object;
 pub mod triangle_resources;
+pub mod bvh;
+pub mod vertex_buffer;
+pub mod mesh_repair;
+pub mod load_error;
+pub mod gltf_loader;
+pub mod stl;
+pub mod ply;
+pub mod object_writer;
+pub mod auto_fit;
+pub mod simplify;
+pub mod lod;
+pub mod mesh_cache;
+pub mod texture;
 
 
+pub mod skeleton;
+pub mod animation;
+pub mod obj_sequence;
@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+
+use crate::department::model::auto_fit::UnitFit;
+use crate::department::model::load_error::LoadError;
+use crate::department::model::object_loader::{ModelFormat, ObjectLoader};
+use crate::department::model::vertex_buffer::VertexBuffer;
+
+pub const DEFAULT_FPS: f32 = 24.;
+// a frame every ten seconds, anything slower is a typo
+const MIN_FPS: f32 = 0.1;
+
+// `tree_0001.obj` and its siblings `tree_0002.obj` ... ordered by frame number. none unless
+// the stem ends in a frame number and at least one more frame sits next to it
+pub fn sequence_frames(path: &Path) -> Option<Vec<PathBuf>> {
+    let stem = path.file_stem()?.to_str()?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    let prefix = &stem[..stem.len() - digits];
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+    let mut frames: Vec<(u64, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let p = entry.ok()?.path();
+            let number = p.file_stem()?.to_str()?.strip_prefix(prefix)?;
+            // same padding too, so `tree_1.obj` next to `tree_0001.obj` isn't a frame of it
+            let matches = p.extension().and_then(|e| e.to_str()).unwrap_or("") == extension
+                && number.len() == digits
+                && number.bytes().all(|b| b.is_ascii_digit());
+            if !matches {
+                return None;
+            }
+            Some((number.parse().ok()?, p))
+        })
+        .collect();
+    if frames.len() < 2 {
+        return None;
+    }
+    frames.sort_by_key(|(n, _)| *n);
+    Some(frames.into_iter().map(|(_, p)| p).collect())
+}
+
+// the sequence `path` belongs to, if it has numbered siblings. files that are merely named
+// alike, `part_01.obj` next to `part_02.obj`, don't share a topology and load as a static model
+pub fn load_sequence(path: &Path) -> Option<VertexAnimation> {
+    let frames = sequence_frames(path)?;
+    match VertexAnimation::load(&frames) {
+        Ok(animation) => {
+            info!("{}: obj sequence of {} frames", path.display(), frames.len());
+            Some(animation)
+        }
+        Err(e) => {
+            warn!("{}: numbered siblings aren't frames of it, loading it alone, {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// positions and normals of every model, one per vertex in `VertexBuffer::from_mesh` order
+#[derive(Debug, Clone)]
+pub struct MorphFrame {
+    pub positions: Vec<Vec<[f32; 3]>>,
+    pub normals: Vec<Vec<[f32; 3]>>,
+}
+
+// an obj sequence sharing one topology, played at `fps` and blended linearly between
+// neighbouring frames. the last frame blends back into the first, so it loops
+#[derive(Debug, Clone)]
+pub struct VertexAnimation {
+    pub fps: f32,
+    pub frames: Vec<MorphFrame>,
+}
+
+impl VertexAnimation {
+    // every frame has to split into the same triangles over the same vertices as the first
+    pub fn load(paths: &[PathBuf]) -> Result<Self, LoadError> {
+        let mut frames = Vec::with_capacity(paths.len());
+        let mut topology: Option<Vec<Vec<u32>>> = None;
+        for path in paths {
+            // frames are read once, they don't need a mesh cache or detail levels
+            let (models, _, _) = match ModelFormat::from_path(path) {
+                ModelFormat::Obj => ObjectLoader::load_obj_file(path)?,
+                _ => ObjectLoader::load_models(path)?,
+            };
+            let buffers: Vec<VertexBuffer> = models.iter().map(|m| VertexBuffer::from_mesh(&m.mesh, None)).collect();
+            let indices: Vec<Vec<u32>> = buffers.iter().map(|b| b.indices.clone()).collect();
+            match &topology {
+                Some(first) if *first != indices => {
+                    return Err(LoadError::SequenceMismatch {
+                        path: path.clone(),
+                        first: paths[0].clone(),
+                    });
+                }
+                Some(_) => {}
+                None => topology = Some(indices),
+            }
+            frames.push(MorphFrame {
+                positions: buffers.iter().map(|b| b.vertices.iter().map(|v| v.position).collect()).collect(),
+                normals: buffers.iter().map(|b| b.vertices.iter().map(|v| v.normal).collect()).collect(),
+            });
+        }
+        Ok(Self {
+            fps: DEFAULT_FPS,
+            frames,
+        })
+    }
+
+    pub fn set_fps(&mut self, fps: f32) {
+        self.fps = fps.max(MIN_FPS);
+    }
+
+    // one loop in seconds
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 / self.fps
+    }
+
+    // the cpu path draws a single model, the other ones don't need their frames kept
+    pub fn only_model(mut self, model: usize) -> Self {
+        for frame in self.frames.iter_mut() {
+            frame.positions = vec![std::mem::take(&mut frame.positions[model])];
+            frame.normals = vec![std::mem::take(&mut frame.normals[model])];
+        }
+        self
+    }
+
+    // the model was normalized, every frame moves the same way
+    pub fn apply_fit(&mut self, fit: &UnitFit) {
+        for frame in self.frames.iter_mut() {
+            frame.positions.iter_mut().flatten().for_each(|p| fit.apply(p));
+        }
+    }
+
+    // positions and normals of `model` at `time` seconds
+    pub fn sample(&self, model: usize, time: f32) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+        let (a, b, t) = self.frames_at(time);
+        let (a, b) = (&self.frames[a], &self.frames[b]);
+        let lerp = |x: &[[f32; 3]], y: &[[f32; 3]]| -> Vec<[f32; 3]> {
+            x.iter()
+                .zip(y)
+                .map(|(p, q)| [0, 1, 2].map(|k| p[k] + (q[k] - p[k]) * t))
+                .collect()
+        };
+        let positions = lerp(&a.positions[model], &b.positions[model]);
+        let normals = lerp(&a.normals[model], &b.normals[model])
+            .into_iter()
+            .map(|n| {
+                let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                if len > 0. {
+                    n.map(|c| c / len)
+                } else {
+                    n
+                }
+            })
+            .collect();
+        (positions, normals)
+    }
+
+    // the frame `time` falls in, the one after it and how far along between the two
+    fn frames_at(&self, time: f32) -> (usize, usize, f32) {
+        let n = self.frames.len();
+        let t = (time * self.fps).rem_euclid(n as f32);
+        let a = (t.floor() as usize).min(n - 1);
+        (a, (a + 1) % n, t - a as f32)
+    }
+}
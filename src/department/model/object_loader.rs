@@ -1,174 +1,237 @@
+use std::fs;
+use std::io::{self, Cursor};
 use std::path::Path;
 
-use tobj;
-
-use crate::department::model::render_object::RenderObject;
+use log::{info, warn};
+use tobj::{self, MTLLoadResult, Material, Model};
+
+use crate::department::model::gltf_loader::load_gltf;
+use crate::department::model::load_error::LoadError;
+use crate::department::model::lod::{LodChain, MeshLods};
+use crate::department::model::mesh_cache::{cache_path, read_cache, source_hash, write_cache, MeshCache};
+use crate::department::model::mesh_repair::{
+    obj_load_options, repair_model, scan_smoothing_groups, split_smoothing_groups, LoadWarning,
+};
+use crate::department::model::obj_sequence::load_sequence;
+use crate::department::model::ply::read_ply;
+use crate::department::model::stl::read_stl;
+use crate::department::model::texture::{load_texture, TextureRole};
 use crate::department::model::triangle_resources::TriangleResources;
-use crate::department::preview::position::Pos3;
 
 pub struct ObjectLoader {}
 
 impl ObjectLoader {
-    pub fn load_render_obj(path: &str) -> Vec<RenderObject> {
-        let _model_path = Path::new(path);
-        let (models, materials) =
-            tobj::load_obj(
-                path,
-                &tobj::LoadOptions::default(),
-            )
-                .expect("Failed to OBJ load file");
-
-        // Note: If you don't mind missing the materials, you can generate a default.
-
-        let materials = materials.unwrap_or_default();
-
-        println!("Number of models          = {}", models.len());
-        println!("Number of materials       = {}", materials.len());
-        let mut render_objects: Vec<RenderObject> = Vec::new();
-
-        for (i, m) in models.iter().enumerate() {
-            let mut vertexes: Vec<Pos3> = Vec::new();
-            let mut indexes: Vec<usize> = Vec::new();
-
-            let mesh = &m.mesh;
-            println!("model[{}].name             = \'{}\'", i, m.name);
-            println!("model[{}].mesh.material_id = {:?}", i, mesh.material_id);
-
-            println!(
-                "model[{}].face_count       = {}",
-                i,
-                mesh.face_arities.len()
-            );
-            println!("vertex_color:{:?}", mesh.vertex_color.len());
-            println!("normals:{:?}", mesh.normals.len());
-            println!("texcoords:{:?}", mesh.texcoords.len());
-            println!("indices:{:?}", mesh.indices.len());
-            // println!("vertex_color_indices:{:?}", mesh.vertex_color_indices.len());
-            println!("texcoord_indices:{:?}", mesh.texcoord_indices.len());
-            println!("normal_indices:{:?}", mesh.normal_indices.len());
-
-            let mut next_face = 0;
-            indexes.extend(mesh.indices.iter().map(|x| *x as usize));
-            for face in 0..mesh.face_arities.len() {
-                let end = next_face + mesh.face_arities[face] as usize;
-
-                let face_indices = &mesh.indices[next_face..end];
-                if face_indices.len() != 3 {
-                    // println!(" face[{}].indices          = {:?}", face, face_indices);
-                } else {
-                    for i in face_indices {
-                        indexes.push(*i as usize);
-                    }
-                }
-                // println!(" face[{}].indices          = {:?}", face, face_indices);
-
-                if !mesh.texcoord_indices.is_empty() {
-                    let texcoord_face_indices = &mesh.texcoord_indices[next_face..end];
-                    println!(
-                        " face[{}].texcoord_indices = {:?}",
-                        face, texcoord_face_indices
-                    );
-                }
-                if !mesh.normal_indices.is_empty() {
-                    let normal_face_indices = &mesh.normal_indices[next_face..end];
-                    println!(
-                        " face[{}].normal_indices   = {:?}",
-                        face, normal_face_indices
-                    );
-                }
-
-                next_face = end;
-            }
+    // only load one resources for now
+    pub fn load_triangle_resources(path: &str) -> Result<TriangleResources, LoadError> {
+        let model_path = Path::new(path);
+        if ModelFormat::from_path(model_path) == ModelFormat::Gltf {
+            let resources = load_gltf(model_path)?.into_triangle_resources();
+            info!("we've got {} triangles in total.", resources.triangle_count());
+            return Ok(resources);
+        }
 
-            // Normals and texture coordinates are also loaded, but not printed in
-            // this example.
-            println!(
-                "model[{}].positions        = {}",
-                i,
-                mesh.positions.len() / 3
-            );
-            assert!(mesh.positions.len() % 3 == 0);
-
-            for vtx in 0..mesh.positions.len() / 3 {
-                vertexes.push(Pos3::from_xyz(
-                    mesh.positions[3 * vtx],
-                    mesh.positions[3 * vtx + 1],
-                    mesh.positions[3 * vtx + 2],
-                ));
+        // obj comes with its detail levels from the mesh cache, everything else builds them below
+        let (mut meshes, materials, warnings) = match ModelFormat::from_path(model_path) {
+            ModelFormat::Obj => {
+                let (cache, warnings) = Self::load_obj_cached(model_path)?;
+                (cache.meshes, cache.materials, warnings)
             }
+            _ => {
+                let (models, materials, warnings) = Self::load_models(model_path)?;
+                let meshes = models
+                    .into_iter()
+                    .map(|model| MeshLods {
+                        model,
+                        lods: LodChain::default(),
+                        skin: None,
+                    })
+                    .collect();
+                (meshes, materials, warnings)
+            }
+        };
 
-            let render_o = RenderObject::from_vec(vertexes, indexes);
+        let index = meshes.len() - 1;
+        let MeshLods { model, lods, .. } = meshes.pop().unwrap();
+        let mut triangle_resources = TriangleResources::new(model);
+        triangle_resources.warnings = warnings;
 
+        let model = &triangle_resources.model;
+        info!("we've got {} triangles in total.", model.mesh.indices.len() / 3);
 
-            render_objects.push(render_o);
+        if let Some(i) = model.mesh.material_id {
+            let material = materials
+                .get(i)
+                .cloned()
+                .ok_or_else(|| LoadError::MissingMaterial {
+                    path: model_path.to_path_buf(),
+                    material: i,
+                })?;
+
+            if let Some(diffuse_texture) = &material.diffuse_texture {
+                let texture_path = model_path.parent().unwrap_or(Path::new(".")).join(diffuse_texture);
+                triangle_resources.texture = Some(load_texture(&texture_path, TextureRole::Diffuse)?);
+            } else {
+                warn!("material '{}' has no diffuse texture", material.name);
+            }
+            triangle_resources.material = Some(material);
+        }
+        triangle_resources.build_vertex_buffer();
+        let sequence = if ModelFormat::from_path(model_path) == ModelFormat::Obj {
+            load_sequence(model_path)
+        } else {
+            None
+        };
+        if let Some(sequence) = sequence {
+            triangle_resources.sequence = Some(sequence.only_model(index));
+        } else if lods.levels.is_empty() {
+            triangle_resources.build_lods();
+        } else {
+            triangle_resources.set_lods(lods);
         }
 
-        // for (i, m) in materials.iter().enumerate() {
-        //     println!("material[{}].name = \'{}\'", i, m.name);
-        //     println!(
-        //         "    material.Ka = ({}, {}, {})",
-        //         m.ambient.unwrap()[0], m.ambient.unwrap()[1], m.ambient.unwrap()[2]
-        //     );
-        //     println!(
-        //         "    material.Kd = ({}, {}, {})",
-        //         m.diffuse[0], m.diffuse[1], m.diffuse[2]
-        //     );
-        //     println!(
-        //         "    material.Ks = ({}, {}, {})",
-        //         m.specular[0], m.specular[1], m.specular[2]
-        //     );
-        //     println!("    material.Ns = {}", m.shininess);
-        //     println!("    material.d = {}", m.dissolve);
-        //     println!("    material.map_Ka = {}", m.ambient_texture);
-        //     println!("    material.map_Kd = {}", m.diffuse_texture);
-        //     println!("    material.map_Ks = {}", m.specular_texture);
-        //     println!("    material.map_Ns = {}", m.shininess_texture);
-        //     println!("    material.map_Bump = {}", m.normal_texture);
-        //     println!("    material.map_d = {}", m.dissolve_texture);
-
-        //     for (k, v) in &m.unknown_param {
-        //         println!("    material.{} = {}", k, v);
-        //     }
-        // }
-
-        render_objects
+        Ok(triangle_resources)
     }
 
-    // only load one resources for now
-    pub fn load_triangle_resources(path: &str) -> TriangleResources {
+    // geometry of every model in the file and nothing else, what an export writes out
+    pub fn load_all_triangle_resources(path: &str) -> Result<Vec<TriangleResources>, LoadError> {
         let model_path = Path::new(path);
-        let (mut models, materials) =
-            tobj::load_obj(
-                path,
-                &tobj::LoadOptions::default(),
-            )
-                .expect("Failed to OBJ load file");
-        assert!(models.len() > 0);
+        if ModelFormat::from_path(model_path) == ModelFormat::Gltf {
+            return Ok(vec![load_gltf(model_path)?.into_triangle_resources()]);
+        }
+        let (models, _, _) = Self::load_models(model_path)?;
+        Ok(models.into_iter().map(TriangleResources::new).collect())
+    }
 
-        let mut mat = materials.unwrap_or_default();
+    // every model in the file, already triangulated and with normals and uv
+    pub fn load_models(path: &Path) -> Result<(Vec<Model>, Vec<Material>, Vec<LoadWarning>), LoadError> {
+        match ModelFormat::from_path(path) {
+            ModelFormat::Obj => {
+                let (cache, warnings) = Self::load_obj_cached(path)?;
+                let (models, materials) = cache.into_models();
+                Ok((models, materials, warnings))
+            }
+            ModelFormat::Gltf => {
+                let scene = load_gltf(path)?;
+                Ok((scene.models, scene.materials, scene.warnings))
+            }
+            ModelFormat::Stl => {
+                let (model, warnings) = read_stl(path)?;
+                Ok((vec![model], Vec::new(), warnings))
+            }
+            ModelFormat::Ply => {
+                let (model, warnings) = read_ply(path)?;
+                Ok((vec![model], Vec::new(), warnings))
+            }
+        }
+    }
 
-        let mut triangle_resources = TriangleResources::new(models.pop().unwrap());
+    // the mesh cache next to `path` while it matches the obj and mtl contents, otherwise
+    // parses and repairs the obj and writes a new cache. warnings only come from a parse
+    pub fn load_obj_cached(path: &Path) -> Result<(MeshCache, Vec<LoadWarning>), LoadError> {
+        let io_error = |source| LoadError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let bytes = fs::read(path).map_err(io_error)?;
+        let hash = source_hash(path, &bytes);
+        let cached = cache_path(path);
+        if let Some(cache) = read_cache(&cached, hash) {
+            info!("{}: loaded from mesh cache {}", path.display(), cached.display());
+            return Ok((cache, Vec::new()));
+        }
 
-        let model = &triangle_resources.model;
+        let text = String::from_utf8(bytes).map_err(|e| io_error(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        let (models, materials, warnings) = Self::load_obj(path, &text)?;
+        let cache = MeshCache::build(models, materials);
+        // a read only model directory just means parsing again next time
+        if let Err(e) = write_cache(&cached, hash, &cache) {
+            warn!("{}: mesh cache not written, {}", cached.display(), e);
+        }
+        Ok((cache, warnings))
+    }
 
-        println!("we've got {} triangles in total.", model.mesh.indices.len() / 3);
+    // `load_obj` straight from the file, leaves the mesh cache alone
+    pub fn load_obj_file(path: &Path) -> Result<(Vec<Model>, Vec<Material>, Vec<LoadWarning>), LoadError> {
+        let text = fs::read_to_string(path).map_err(|source| LoadError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::load_obj(path, &text)
+    }
 
-        if let Some(i) = model.mesh.material_id {
-            let diffuse_texture = mat[i].diffuse_texture.clone();
-            if model_path.is_relative() {
-                let texture_path = model_path.parent().unwrap().join(Path::new(&diffuse_texture.unwrap()));
-                let texture = image::open(texture_path);
-                if texture.is_err() {
-                    triangle_resources.image = None;
-                    triangle_resources.material = None;
-                }else {
-                    triangle_resources.image = Some(texture.unwrap());
-                    triangle_resources.material = mat.pop();
-                }
+    // parses the obj and its mtl libraries next to it, then repairs every model
+    fn load_obj(path: &Path, text: &str) -> Result<(Vec<Model>, Vec<Material>, Vec<LoadWarning>), LoadError> {
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Self::parse_obj(path, text, |mtl| tobj::load_mtl(dir.join(mtl)))
+    }
 
-            }
+    // same as `load_obj` for text that is already in memory, `load_mtl` resolves `mtllib` names
+    pub fn parse_obj<F>(
+        path: &Path,
+        text: &str,
+        load_mtl: F,
+    ) -> Result<(Vec<Model>, Vec<Material>, Vec<LoadWarning>), LoadError>
+    where
+        F: Fn(&Path) -> MTLLoadResult,
+    {
+        let (mut models, materials) = tobj::load_obj_buf(&mut Cursor::new(text.as_bytes()), &obj_load_options(), load_mtl)
+            .map_err(|source| LoadError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        if models.is_empty() {
+            return Err(LoadError::Empty {
+                path: path.to_path_buf(),
+            });
+        }
+
+        // a broken mtl only matters once a mesh actually refers to it
+        let materials = materials.unwrap_or_else(|e| {
+            warn!("{}: material library not loaded, {}", path.display(), e);
+            Vec::new()
+        });
+
+        let warnings = Self::repair_models(text, &mut models);
+        for w in &warnings {
+            warn!("{}: {}", path.display(), w);
         }
 
-        triangle_resources
+        Ok((models, materials, warnings))
+    }
+
+    // triangulates and fills in whatever the file left out, see `mesh_repair`
+    fn repair_models(text: &str, models: &mut [Model]) -> Vec<LoadWarning> {
+        let mut warnings = Vec::new();
+        let groups = scan_smoothing_groups(text);
+        let groups = split_smoothing_groups(models, &groups, &mut warnings);
+        for (model, smoothing) in models.iter_mut().zip(groups.iter()) {
+            repair_model(model, smoothing, &mut warnings);
+        }
+        warnings
+    }
+}
+
+// picked by extension, anything unknown is treated as obj
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModelFormat {
+    Obj,
+    Gltf,
+    Stl,
+    Ply,
+}
+
+impl ModelFormat {
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "gltf" | "glb" => ModelFormat::Gltf,
+            "stl" => ModelFormat::Stl,
+            "ply" => ModelFormat::Ply,
+            _ => ModelFormat::Obj,
+        }
     }
 }
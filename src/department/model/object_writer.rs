@@ -0,0 +1,37 @@
+use std::io;
+use std::path::Path;
+
+use log::info;
+
+use crate::department::model::object_loader::ModelFormat;
+use crate::department::model::ply::write_ply;
+use crate::department::model::stl::write_stl;
+use crate::department::model::triangle_resources::TriangleResources;
+use crate::department::preview::homo_transformation::HomoTransform;
+
+pub struct ObjectWriter {}
+
+impl ObjectWriter {
+    // format from the extension, `transform` is baked into the written geometry
+    pub fn save_triangle_resources(
+        path: &str,
+        models: &[TriangleResources],
+        transform: &HomoTransform,
+        binary: bool,
+    ) -> io::Result<()> {
+        let out_path = Path::new(path);
+        match ModelFormat::from_path(out_path) {
+            ModelFormat::Stl => write_stl(out_path, models, transform, binary)?,
+            ModelFormat::Ply => write_ply(out_path, models, transform, binary)?,
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("can't export {:?}, use .stl or .ply", other),
+                ))
+            }
+        }
+        let triangles: usize = models.iter().map(TriangleResources::triangle_count).sum();
+        info!("exported {} triangles of {} models to {}", triangles, models.len(), path);
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use tobj::{Mesh, Model};
+
+use crate::department::model::load_error::LoadError;
+use crate::department::model::mesh_repair::{repair_model, LoadWarning};
+use crate::department::model::triangle_resources::TriangleResources;
+use crate::department::model::vertex_buffer::VertexBuffer;
+use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::vector::{HVec4, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    // integer colours are 0-255, float ones already 0-1
+    fn color_scale(&self) -> f64 {
+        match self {
+            Scalar::F32 | Scalar::F64 => 1.,
+            _ => 1. / 255.,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Property {
+    Scalar { name: String, ty: Scalar },
+    List { name: String, count: Scalar, item: Scalar },
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// walks the body of the file one value at a time, whatever the encoding
+struct Cursor<'a> {
+    data: &'a [u8],
+    at: usize,
+    encoding: Encoding,
+}
+
+impl<'a> Cursor<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.at
+    }
+
+    fn read(&mut self, ty: Scalar) -> Option<f64> {
+        if self.encoding == Encoding::Ascii {
+            while self.at < self.data.len() && self.data[self.at].is_ascii_whitespace() {
+                self.at += 1;
+            }
+            let start = self.at;
+            while self.at < self.data.len() && !self.data[self.at].is_ascii_whitespace() {
+                self.at += 1;
+            }
+            return std::str::from_utf8(&self.data[start..self.at]).ok()?.parse().ok();
+        }
+
+        let bytes = self.data.get(self.at..self.at + ty.size())?;
+        self.at += ty.size();
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        if self.encoding == Encoding::BigEndian {
+            buf[..bytes.len()].reverse();
+        }
+        Some(match ty {
+            Scalar::I8 => buf[0] as i8 as f64,
+            Scalar::U8 => buf[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(buf),
+        })
+    }
+}
+
+// ascii and binary ply with per-vertex normals, colours and uv; polygon faces
+// are triangulated like obj ones
+pub fn read_ply(path: &Path) -> Result<(Model, Vec<LoadWarning>), LoadError> {
+    let data = fs::read(path).map_err(|source| LoadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let format_error = |message: String| LoadError::Format {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let (encoding, elements, body) = read_header(&data).map_err(format_error)?;
+    let mut cursor = Cursor {
+        data: &data[body..],
+        at: 0,
+        encoding,
+    };
+
+    let mut mesh = Mesh::default();
+    let mut normals = Vec::new();
+    let mut texcoords = Vec::new();
+    let mut colors = Vec::new();
+    for element in &elements {
+        for i in 0..element.count {
+            let truncated = || format_error(format!("{} {} is truncated", element.name, i));
+            let mut values: Vec<(&str, f64, Scalar)> = Vec::new();
+            for property in &element.properties {
+                match property {
+                    Property::Scalar { name, ty } => {
+                        values.push((name.as_str(), cursor.read(*ty).ok_or_else(truncated)?, *ty));
+                    }
+                    Property::List { name, count, item } => {
+                        let n = cursor.read(*count).ok_or_else(truncated)? as usize;
+                        // the count comes from the file, every item takes at least a byte
+                        let mut list = Vec::with_capacity(n.min(cursor.remaining()));
+                        for _ in 0..n {
+                            list.push(cursor.read(*item).ok_or_else(truncated)? as u32);
+                        }
+                        if element.name == "face" && (name == "vertex_indices" || name == "vertex_index") {
+                            mesh.face_arities.push(n as u32);
+                            mesh.indices.extend(list);
+                        }
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                let get = |keys: &[&str]| values.iter().find(|(n, _, _)| keys.contains(n)).map(|(_, v, ty)| (*v, *ty));
+                let position = [get(&["x"]), get(&["y"]), get(&["z"])];
+                mesh.positions.extend(position.map(|p| p.map_or(0., |(v, _)| v as f32)));
+                if let [Some(x), Some(y), Some(z)] = [get(&["nx"]), get(&["ny"]), get(&["nz"])] {
+                    normals.extend([x.0, y.0, z.0].map(|v| v as f32));
+                }
+                if let [Some(u), Some(v)] = [get(&["s", "u", "texture_u"]), get(&["t", "v", "texture_v"])] {
+                    texcoords.extend([u.0 as f32, v.0 as f32]);
+                }
+                if let [Some(r), Some(g), Some(b)] = [get(&["red", "r"]), get(&["green", "g"]), get(&["blue", "b"])] {
+                    colors.extend([r, g, b].map(|(v, ty)| (v * ty.color_scale()) as f32));
+                }
+            }
+        }
+    }
+
+    let vertex_count = mesh.positions.len() / 3;
+    if mesh.indices.is_empty() {
+        return Err(LoadError::Empty {
+            path: path.to_path_buf(),
+        });
+    }
+    // attributes only count when every vertex has them
+    if normals.len() == vertex_count * 3 {
+        mesh.normals = normals;
+        mesh.normal_indices = mesh.indices.clone();
+    }
+    if texcoords.len() == vertex_count * 2 {
+        mesh.texcoords = texcoords;
+        mesh.texcoord_indices = mesh.indices.clone();
+    }
+    if colors.len() == vertex_count * 3 {
+        mesh.vertex_color = colors;
+    }
+
+    let faces = mesh.face_arities.len();
+    let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut model = Model::new(mesh, name);
+    let mut warnings = Vec::new();
+    repair_model(&mut model, &vec![1; faces], &mut warnings);
+    Ok((model, warnings))
+}
+
+// the header is always ascii, returns where the body starts
+fn read_header(data: &[u8]) -> Result<(Encoding, Vec<Element>, usize), String> {
+    let end = data
+        .windows(10)
+        .position(|w| w == b"end_header")
+        .ok_or("no end_header")?;
+    let mut body = end + 10;
+    // the header ends with a single newline, possibly \r\n
+    if data.get(body) == Some(&b'\r') {
+        body += 1;
+    }
+    if data.get(body) == Some(&b'\n') {
+        body += 1;
+    }
+
+    let header = std::str::from_utf8(&data[..end]).map_err(|_| "header is not ascii")?;
+    let mut lines = header.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err("not a ply file".to_string());
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", format, ..] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    other => return Err(format!("unknown format {}", other)),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| format!("bad element count {}", count))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let (count, item) = match (Scalar::parse(count), Scalar::parse(item)) {
+                    (Some(c), Some(i)) => (c, i),
+                    _ => return Err(format!("bad list property {}", name)),
+                };
+                elements.last_mut().ok_or("property before element")?.properties.push(Property::List {
+                    name: name.to_string(),
+                    count,
+                    item,
+                });
+            }
+            ["property", ty, name] => {
+                let ty = Scalar::parse(ty).ok_or(format!("bad property type {}", ty))?;
+                elements.last_mut().ok_or("property before element")?.properties.push(Property::Scalar {
+                    name: name.to_string(),
+                    ty,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok((encoding.ok_or("no format line")?, elements, body))
+}
+
+// writes every model after `transform` into one vertex and face list, with normals, uv and
+// vertex colours, as binary little endian or ascii
+pub fn write_ply(path: &Path, models: &[TriangleResources], transform: &HomoTransform, binary: bool) -> io::Result<()> {
+    let buffers: Vec<VertexBuffer> = models.iter().map(|res| VertexBuffer::from_mesh(&res.model.mesh, None)).collect();
+    let has_colors = models.iter().any(|res| !res.model.mesh.vertex_color.is_empty());
+    let vertex_count: usize = buffers.iter().map(|b| b.vertices.len()).sum();
+    let face_count: usize = buffers.iter().map(|b| b.triangle_count()).sum();
+    let name = models.first().map_or("", |res| res.model.name.as_str());
+    let normal_mat = transform
+        .inverse_matrix()
+        .map(|m| m.t())
+        .unwrap_or_else(HomoTransform::identity_matrix);
+
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    writeln!(out, "ply")?;
+    writeln!(out, "format {} 1.0", if binary { "binary_little_endian" } else { "ascii" })?;
+    writeln!(out, "comment dognut export {}", name)?;
+    writeln!(out, "element vertex {}", vertex_count)?;
+    for p in ["x", "y", "z", "nx", "ny", "nz", "s", "t"] {
+        writeln!(out, "property float {}", p)?;
+    }
+    if has_colors {
+        for p in ["red", "green", "blue"] {
+            writeln!(out, "property uchar {}", p)?;
+        }
+    }
+    writeln!(out, "element face {}", face_count)?;
+    writeln!(out, "property list uchar uint vertex_indices")?;
+    writeln!(out, "end_header")?;
+
+    for v in buffers.iter().flat_map(|b| &b.vertices) {
+        let [x, y, z] = v.position;
+        let p = &HVec4::from_v3(Vector3::from_xyz(x, y, z)) * transform;
+        let [nx, ny, nz] = v.normal;
+        let n = &Vector3::from_xyz(nx, ny, nz).to_linear_matrix() * &normal_mat;
+        let mut n = Vector3::from_xyz(n.x(), n.y(), n.z());
+        if n.magnitude() > 0. {
+            n.norm();
+        }
+        let floats = [
+            p.x() / p.w(),
+            p.y() / p.w(),
+            p.z() / p.w(),
+            n.x(),
+            n.y(),
+            n.z(),
+            v.uv[0],
+            v.uv[1],
+        ];
+        let color = v.color.map(|c| (c.clamp(0., 1.) * 255.).round() as u8);
+
+        if binary {
+            for f in floats {
+                out.write_all(&f.to_le_bytes())?;
+            }
+            if has_colors {
+                out.write_all(&color)?;
+            }
+        } else {
+            let line: Vec<String> = floats.iter().map(|f| f.to_string()).collect();
+            write!(out, "{}", line.join(" "))?;
+            if has_colors {
+                write!(out, " {} {} {}", color[0], color[1], color[2])?;
+            }
+            writeln!(out)?;
+        }
+    }
+
+    // each model's indices start after the vertices of the ones before it
+    let offsets = buffers.iter().scan(0u32, |start, b| {
+        let offset = *start;
+        *start += b.vertices.len() as u32;
+        Some(offset)
+    });
+    for (buffer, offset) in buffers.iter().zip(offsets) {
+        for t in 0..buffer.triangle_count() {
+            let corners = buffer.triangle(t).map(|c| c + offset);
+            if binary {
+                out.write_all(&[3u8])?;
+                for c in corners {
+                    out.write_all(&c.to_le_bytes())?;
+                }
+            } else {
+                writeln!(out, "3 {} {} {}", corners[0], corners[1], corners[2])?;
+            }
+        }
+    }
+    out.flush()
+}
@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::department::model::vertex_buffer::VertexBuffer;
+
+// open borders get a plane through them perpendicular to their face, weighted
+// heavily so the silhouette of the hole doesn't shrink
+const BOUNDARY_WEIGHT: f64 = 1000.;
+
+#[derive(Debug, Clone, Default)]
+pub struct Simplified {
+    pub buffer: VertexBuffer,
+    // triangle of the input buffer every output triangle came from
+    pub source_triangles: Vec<u32>,
+}
+
+// symmetric 4x4 error quadric of garland and heckbert, upper triangle row by row
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane([a, b, c, d]: [f64; 4], weight: f64) -> Self {
+        Quadric([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d].map(|q| q * weight))
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (q, o) in self.0.iter_mut().zip(other.0) {
+            *q += o;
+        }
+    }
+
+    // squared distance of `p` to every plane that was folded in
+    fn error(&self, [x, y, z]: [f64; 3]) -> f64 {
+        let q = &self.0;
+        q[0] * x * x + 2. * q[1] * x * y + 2. * q[2] * x * z + 2. * q[3] * x
+            + q[4] * y * y + 2. * q[5] * y * z + 2. * q[6] * y
+            + q[7] * z * z + 2. * q[8] * z
+            + q[9]
+    }
+}
+
+// moving `remove` onto `keep`, stamped with the versions it was costed against
+struct Collapse {
+    cost: f64,
+    keep: u32,
+    remove: u32,
+    stamp: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// reversed, the heap pops the cheapest collapse first
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+// quadric error edge collapse down to about `target_triangles`. corners are welded by
+// position first, so uv and normal seams move together and never tear; every vertex
+// keeps its own attributes and only takes the position it collapsed onto
+pub fn simplify(input: &VertexBuffer, target_triangles: usize) -> Simplified {
+    let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+    let mut positions: Vec<[f64; 3]> = Vec::new();
+    let corner_position: Vec<u32> = input
+        .vertices
+        .iter()
+        .map(|v| {
+            *welded.entry(v.position.map(f32::to_bits)).or_insert_with(|| {
+                positions.push(v.position.map(f64::from));
+                (positions.len() - 1) as u32
+            })
+        })
+        .collect();
+
+    let triangles: Vec<[u32; 3]> = (0..input.triangle_count()).map(|t| input.triangle(t)).collect();
+    let mut alive: Vec<bool> = triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|v| corner_position[v as usize]);
+            a != b && b != c && c != a
+        })
+        .collect();
+    let mut alive_count = alive.iter().filter(|a| **a).count();
+
+    // what every welded position was merged into, itself while it still exists
+    let mut merged: Vec<u32> = (0..positions.len() as u32).collect();
+    let mut version = vec![0u32; positions.len()];
+    let mut around: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut edge_use: HashMap<(u32, u32), (u32, usize)> = HashMap::new();
+
+    for (t, tri) in triangles.iter().enumerate() {
+        if !alive[t] {
+            continue;
+        }
+        let ids = tri.map(|v| corner_position[v as usize]);
+        for (k, &p) in ids.iter().enumerate() {
+            around[p as usize].push(t);
+            let q = ids[(k + 1) % 3];
+            edge_use.entry((p.min(q), p.max(q))).or_insert((0, t)).0 += 1;
+        }
+        let corners = ids.map(|p| positions[p as usize]);
+        let n = cross(sub(corners[1], corners[0]), sub(corners[2], corners[0]));
+        let area2 = length(n);
+        if area2 == 0. {
+            continue;
+        }
+        let n = scale(n, 1. / area2);
+        let plane = Quadric::from_plane([n[0], n[1], n[2], -dot(n, corners[0])], area2 * 0.5);
+        for p in ids {
+            quadrics[p as usize].add(&plane);
+        }
+    }
+
+    for (&(a, b), &(count, t)) in &edge_use {
+        if count != 1 {
+            continue;
+        }
+        let ids = triangles[t].map(|v| corner_position[v as usize]);
+        let corners = ids.map(|p| positions[p as usize]);
+        let face = cross(sub(corners[1], corners[0]), sub(corners[2], corners[0]));
+        let (pa, pb) = (positions[a as usize], positions[b as usize]);
+        let edge = sub(pb, pa);
+        let m = cross(edge, face);
+        let len = length(m);
+        if len == 0. {
+            continue;
+        }
+        let m = scale(m, 1. / len);
+        let plane = Quadric::from_plane([m[0], m[1], m[2], -dot(m, pa)], BOUNDARY_WEIGHT * dot(edge, edge));
+        quadrics[a as usize].add(&plane);
+        quadrics[b as usize].add(&plane);
+    }
+
+    let candidate = |a: u32, b: u32, quadrics: &[Quadric], version: &[u32], positions: &[[f64; 3]]| {
+        let mut q = quadrics[a as usize];
+        q.add(&quadrics[b as usize]);
+        let (cost_a, cost_b) = (q.error(positions[a as usize]), q.error(positions[b as usize]));
+        let (keep, remove, cost) = if cost_a <= cost_b { (a, b, cost_a) } else { (b, a, cost_b) };
+        Collapse {
+            cost,
+            keep,
+            remove,
+            stamp: (version[keep as usize], version[remove as usize]),
+        }
+    };
+
+    let mut heap: BinaryHeap<Collapse> = edge_use
+        .keys()
+        .map(|&(a, b)| candidate(a, b, &quadrics, &version, &positions))
+        .collect();
+
+    while alive_count > target_triangles {
+        let Some(c) = heap.pop() else {
+            break;
+        };
+        let (keep, remove) = (c.keep as usize, c.remove as usize);
+        if merged[keep] != c.keep
+            || merged[remove] != c.remove
+            || (version[keep], version[remove]) != c.stamp
+        {
+            continue;
+        }
+
+        // faces that survive the collapse must not turn over
+        let target = positions[keep];
+        let flips = around[remove].iter().filter(|&&t| alive[t]).any(|&t| {
+            let ids = triangles[t].map(|v| resolve(&merged, corner_position[v as usize]));
+            if ids.contains(&c.keep) {
+                return false;
+            }
+            let before = ids.map(|p| positions[p as usize]);
+            let after = ids.map(|p| if p == c.remove { target } else { positions[p as usize] });
+            let n0 = cross(sub(before[1], before[0]), sub(before[2], before[0]));
+            let n1 = cross(sub(after[1], after[0]), sub(after[2], after[0]));
+            dot(n0, n1) <= 0.
+        });
+        if flips {
+            continue;
+        }
+
+        merged[remove] = c.keep;
+        let q = quadrics[remove];
+        quadrics[keep].add(&q);
+        version[keep] += 1;
+        for t in std::mem::take(&mut around[remove]) {
+            if !alive[t] {
+                continue;
+            }
+            let ids = triangles[t].map(|v| resolve(&merged, corner_position[v as usize]));
+            if ids[0] == ids[1] || ids[1] == ids[2] || ids[2] == ids[0] {
+                alive[t] = false;
+                alive_count -= 1;
+            } else {
+                around[keep].push(t);
+            }
+        }
+        around[keep].retain(|&t| alive[t]);
+        around[keep].sort_unstable();
+        around[keep].dedup();
+
+        let neighbours: HashSet<u32> = around[keep]
+            .iter()
+            .flat_map(|&t| triangles[t].map(|v| resolve(&merged, corner_position[v as usize])))
+            .filter(|&p| p != c.keep)
+            .collect();
+        for n in neighbours {
+            heap.push(candidate(c.keep, n, &quadrics, &version, &positions));
+        }
+    }
+
+    // compact what is left, only referenced vertices survive
+    let mut simplified = Simplified::default();
+    let mut remap: HashMap<u32, u32> = HashMap::new();
+    for (t, tri) in triangles.iter().enumerate() {
+        if !alive[t] {
+            continue;
+        }
+        for &v in tri {
+            let index = *remap.entry(v).or_insert_with(|| {
+                let mut vertex = input.vertices[v as usize];
+                let p = positions[resolve(&merged, corner_position[v as usize]) as usize];
+                vertex.position = p.map(|c| c as f32);
+                simplified.buffer.vertices.push(vertex);
+                if let Some(&p) = input.position_indices.get(v as usize) {
+                    simplified.buffer.position_indices.push(p);
+                }
+                (simplified.buffer.vertices.len() - 1) as u32
+            });
+            simplified.buffer.indices.push(index);
+        }
+        simplified.source_triangles.push(t as u32);
+    }
+    simplified
+}
+
+fn resolve(merged: &[u32], mut p: u32) -> u32 {
+    while merged[p as usize] != p {
+        p = merged[p as usize];
+    }
+    p
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f64; 3], k: f64) -> [f64; 3] {
+    a.map(|c| c * k)
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn length(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
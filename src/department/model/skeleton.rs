@@ -0,0 +1,200 @@
+// 4x4 matrix in the row vector layout, `v * m`. that is also exactly the column major
+// array gltf stores and wgsl reads for column vectors, so it passes through untouched
+pub type Mat4 = [[f32; 4]; 4];
+
+pub const IDENTITY: Mat4 = [[1., 0., 0., 0.], [0., 1., 0., 0.], [0., 0., 1., 0.], [0., 0., 0., 1.]];
+
+// rotation is a unit quaternion, x y z and then the scalar w
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointPose {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl Default for JointPose {
+    fn default() -> Self {
+        Self {
+            translation: [0.; 3],
+            rotation: [0., 0., 0., 1.],
+            scale: [1.; 3],
+        }
+    }
+}
+
+impl JointPose {
+    // scale, then rotate, then translate
+    pub fn to_matrix(&self) -> Mat4 {
+        let [x, y, z, w] = self.rotation;
+        let [sx, sy, sz] = self.scale;
+        let [tx, ty, tz] = self.translation;
+        [
+            [(1. - 2. * (y * y + z * z)) * sx, 2. * (x * y + z * w) * sx, 2. * (x * z - y * w) * sx, 0.],
+            [2. * (x * y - z * w) * sy, (1. - 2. * (x * x + z * z)) * sy, 2. * (y * z + x * w) * sy, 0.],
+            [2. * (x * z + y * w) * sz, 2. * (y * z - x * w) * sz, (1. - 2. * (x * x + y * y)) * sz, 0.],
+            [tx, ty, tz, 1.],
+        ]
+    }
+
+    // `t` = 0 is `self`, 1 is `other`
+    pub fn blend(&self, other: &JointPose, t: f32) -> JointPose {
+        JointPose {
+            translation: lerp3(self.translation, other.translation, t),
+            rotation: slerp(self.rotation, other.rotation, t),
+            scale: lerp3(self.scale, other.scale, t),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    // local transform while no clip drives the joint
+    pub rest: JointPose,
+    // model space to the joint's space at bind time
+    pub inverse_bind: Mat4,
+    // fixed world transform of whatever sits above a root joint, identity below another joint
+    pub base: Mat4,
+}
+
+// joints in the order the skin's vertex joint indices refer to
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    // parents before children
+    order: Vec<usize>,
+}
+
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>) -> Self {
+        let mut order = Vec::with_capacity(joints.len());
+        let mut placed = vec![false; joints.len()];
+        // a parent index that loops back is treated as a root
+        fn place(j: usize, joints: &[Joint], placed: &mut [bool], order: &mut Vec<usize>, depth: usize) {
+            if placed[j] {
+                return;
+            }
+            if let Some(p) = joints[j].parent.filter(|_| depth < joints.len()) {
+                place(p, joints, placed, order, depth + 1);
+            }
+            if !placed[j] {
+                placed[j] = true;
+                order.push(j);
+            }
+        }
+        for j in 0..joints.len() {
+            place(j, &joints, &mut placed, &mut order, 0);
+        }
+        Self { joints, order }
+    }
+
+    pub fn rest_pose(&self) -> Vec<JointPose> {
+        self.joints.iter().map(|j| j.rest).collect()
+    }
+
+    // one matrix per joint taking a bind pose vertex to where `pose` puts it
+    pub fn skinning_matrices(&self, pose: &[JointPose]) -> Vec<Mat4> {
+        let mut world = vec![IDENTITY; self.joints.len()];
+        for &j in &self.order {
+            let joint = &self.joints[j];
+            let local = pose.get(j).unwrap_or(&joint.rest).to_matrix();
+            let parent = joint.parent.map_or(&joint.base, |p| &world[p]);
+            world[j] = mul(&local, parent);
+        }
+        self.joints
+            .iter()
+            .zip(&world)
+            .map(|(joint, world)| mul(&joint.inverse_bind, world))
+            .collect()
+    }
+}
+
+// up to four joints per vertex, indexed like the positions of the mesh they belong to
+#[derive(Debug, Clone, Default)]
+pub struct SkinWeights {
+    pub joints: Vec<[u16; 4]>,
+    pub weights: Vec<[f32; 4]>,
+}
+
+impl SkinWeights {
+    // zero weights for positions without influences, those are left where they are
+    pub fn influence(&self, position: u32) -> ([u16; 4], [f32; 4]) {
+        let i = position as usize;
+        match (self.joints.get(i), self.weights.get(i)) {
+            (Some(j), Some(w)) => (*j, *w),
+            _ => ([0; 4], [0.; 4]),
+        }
+    }
+}
+
+// linear blend skinning of one vertex, the normal goes through the same matrices without translation
+pub fn skin_vertex(
+    position: [f32; 3],
+    normal: [f32; 3],
+    (joints, weights): ([u16; 4], [f32; 4]),
+    matrices: &[Mat4],
+) -> ([f32; 3], [f32; 3]) {
+    let total: f32 = weights.iter().sum();
+    if total <= 0. {
+        return (position, normal);
+    }
+    let (mut p, mut n) = ([0f32; 3], [0f32; 3]);
+    for (&j, &w) in joints.iter().zip(&weights) {
+        let Some(m) = matrices.get(j as usize).filter(|_| w > 0.) else {
+            continue;
+        };
+        let w = w / total;
+        for k in 0..3 {
+            p[k] += w * (position[0] * m[0][k] + position[1] * m[1][k] + position[2] * m[2][k] + m[3][k]);
+            n[k] += w * (normal[0] * m[0][k] + normal[1] * m[1][k] + normal[2] * m[2][k]);
+        }
+    }
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if len > 0. {
+        n = n.map(|c| c / len);
+    }
+    (p, n)
+}
+
+pub fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut m = [[0.; 4]; 4];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+fn lerp3(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|k| a[k] + (b[k] - a[k]) * t)
+}
+
+// shortest arc, nlerp once the two are close enough for slerp to lose precision
+pub fn slerp(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let mut cos = a.iter().zip(&b).map(|(x, y)| x * y).sum::<f32>();
+    let b = if cos < 0. {
+        cos = -cos;
+        b.map(|c| -c)
+    } else {
+        b
+    };
+    let (wa, wb) = if cos > 0.9995 {
+        (1. - t, t)
+    } else {
+        let theta = cos.acos();
+        let sin = theta.sin();
+        (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+    };
+    normalize4([0, 1, 2, 3].map(|k| a[k] * wa + b[k] * wb))
+}
+
+pub fn normalize4(q: [f32; 4]) -> [f32; 4] {
+    let len = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if len > 0. {
+        q.map(|c| c / len)
+    } else {
+        [0., 0., 0., 1.]
+    }
+}
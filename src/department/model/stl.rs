@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use tobj::{Mesh, Model};
+
+use crate::department::model::load_error::LoadError;
+use crate::department::model::mesh_repair::{repair_model, LoadWarning};
+use crate::department::model::triangle_resources::TriangleResources;
+use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::vector::{HVec4, Vector3};
+
+const HEADER_LEN: usize = 80;
+const TRIANGLE_LEN: usize = 50;
+
+// binary or ascii stl, identical corners are welded so the vertex cache has something to share
+pub fn read_stl(path: &Path) -> Result<(Model, Vec<LoadWarning>), LoadError> {
+    let data = fs::read(path).map_err(|source| LoadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let format_error = |message: &str| LoadError::Format {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    };
+
+    // ascii files start with `solid` too, but only binary ones match the size from the header
+    let triangles = if is_binary(&data) {
+        read_binary(&data)
+    } else {
+        let text = std::str::from_utf8(&data).map_err(|_| format_error("neither binary nor ascii stl"))?;
+        read_ascii(text).ok_or_else(|| format_error("malformed ascii stl"))?
+    };
+    if triangles.is_empty() {
+        return Err(LoadError::Empty {
+            path: path.to_path_buf(),
+        });
+    }
+
+    let mut mesh = Mesh::default();
+    let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+    for corners in &triangles {
+        for p in corners {
+            let index = *welded.entry(p.map(f32::to_bits)).or_insert_with(|| {
+                mesh.positions.extend(p);
+                (mesh.positions.len() / 3 - 1) as u32
+            });
+            mesh.indices.push(index);
+        }
+    }
+
+    // facet normals are frequently zero or stale, flat ones are regenerated from the geometry
+    let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut model = Model::new(mesh, name);
+    let mut warnings = Vec::new();
+    repair_model(&mut model, &vec![0; triangles.len()], &mut warnings);
+    Ok((model, warnings))
+}
+
+fn is_binary(data: &[u8]) -> bool {
+    if data.len() < HEADER_LEN + 4 {
+        return false;
+    }
+    let count = u32::from_le_bytes(data[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap()) as usize;
+    data.len() == HEADER_LEN + 4 + count * TRIANGLE_LEN
+}
+
+fn read_binary(data: &[u8]) -> Vec<[[f32; 3]; 3]> {
+    data[HEADER_LEN + 4..]
+        .chunks_exact(TRIANGLE_LEN)
+        .map(|t| {
+            let f = |at: usize| f32::from_le_bytes(t[at..at + 4].try_into().unwrap());
+            // the 12 byte facet normal comes first
+            [0, 1, 2].map(|v| [0, 1, 2].map(|k| f(12 + v * 12 + k * 4)))
+        })
+        .collect()
+}
+
+fn read_ascii(text: &str) -> Option<Vec<[[f32; 3]; 3]>> {
+    let mut triangles = Vec::new();
+    let mut corners = Vec::with_capacity(3);
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("vertex") => {
+                let mut p = [0f32; 3];
+                for v in p.iter_mut() {
+                    *v = words.next()?.parse().ok()?;
+                }
+                corners.push(p);
+            }
+            Some("endfacet") => {
+                // polygons with more corners are fanned
+                for i in 1..corners.len().saturating_sub(1) {
+                    triangles.push([corners[0], corners[i], corners[i + 1]]);
+                }
+                corners.clear();
+            }
+            _ => {}
+        }
+    }
+    Some(triangles)
+}
+
+// writes every triangle of every model after `transform` as one solid, stl has no room for
+// uv or colours
+pub fn write_stl(path: &Path, models: &[TriangleResources], transform: &HomoTransform, binary: bool) -> io::Result<()> {
+    let triangles: Vec<([f32; 3], [[f32; 3]; 3])> = models
+        .iter()
+        .flat_map(|res| (0..res.triangle_count()).map(move |t| (res, t)))
+        .map(|(res, t)| {
+            let (positions, _, _) = res.triangle_attributes(t);
+            let p = positions.map(|p| {
+                let v = &HVec4::from_v3(p) * transform;
+                [v.x() / v.w(), v.y() / v.w(), v.z() / v.w()]
+            });
+            let a = Vector3::from_xyz(p[1][0] - p[0][0], p[1][1] - p[0][1], p[1][2] - p[0][2]);
+            let b = Vector3::from_xyz(p[2][0] - p[0][0], p[2][1] - p[0][1], p[2][2] - p[0][2]);
+            let mut n = a.cross(&b);
+            if n.magnitude() > 0. {
+                n.norm();
+            }
+            ([n.x(), n.y(), n.z()], p)
+        })
+        .collect();
+
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    let name = models.first().map_or("", |res| res.model.name.as_str());
+    if binary {
+        let mut header = [0u8; HEADER_LEN];
+        let title = format!("dognut export {}", name);
+        let len = title.len().min(HEADER_LEN);
+        header[..len].copy_from_slice(&title.as_bytes()[..len]);
+        out.write_all(&header)?;
+        out.write_all(&(triangles.len() as u32).to_le_bytes())?;
+        for (normal, corners) in &triangles {
+            for v in std::iter::once(normal).chain(corners.iter()) {
+                for c in v {
+                    out.write_all(&c.to_le_bytes())?;
+                }
+            }
+            out.write_all(&0u16.to_le_bytes())?;
+        }
+    } else {
+        writeln!(out, "solid {}", name)?;
+        for (n, corners) in &triangles {
+            writeln!(out, "  facet normal {} {} {}", n[0], n[1], n[2])?;
+            writeln!(out, "    outer loop")?;
+            for p in corners {
+                writeln!(out, "      vertex {} {} {}", p[0], p[1], p[2])?;
+            }
+            writeln!(out, "    endloop")?;
+            writeln!(out, "  endfacet")?;
+        }
+        writeln!(out, "endsolid {}", name)?;
+    }
+    out.flush()
+}
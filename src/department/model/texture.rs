@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use lazy_static::lazy_static;
+use log::debug;
+
+use crate::department::model::load_error::LoadError;
+
+// what a texture is sampled for, which decides how its texels are stored and filtered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureRole {
+    Diffuse,
+    Normal,
+    Roughness,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+impl TextureRole {
+    // colour is painted in srgb, normals and roughness are plain numbers
+    pub fn color_space(self) -> ColorSpace {
+        match self {
+            TextureRole::Diffuse => ColorSpace::Srgb,
+            TextureRole::Normal | TextureRole::Roughness => ColorSpace::Linear,
+        }
+    }
+}
+
+// a decoded texture and its mip chain. level 0 is the full image, every level after it
+// is half the size of the one before, down to a single texel
+#[derive(Debug, Clone)]
+pub struct MipTexture {
+    pub role: TextureRole,
+    pub levels: Vec<RgbaImage>,
+}
+
+impl MipTexture {
+    pub fn new(image: DynamicImage, role: TextureRole) -> Self {
+        let color_space = role.color_space();
+        // float images (hdr) hold linear values, a colour texture stores them srgb encoded like
+        // any 8 bit image. whatever is brighter than white is clamped
+        let is_float = matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_));
+        let base = if is_float && color_space == ColorSpace::Srgb {
+            let linear = image.to_rgba32f();
+            RgbaImage::from_fn(linear.width(), linear.height(), |x, y| {
+                let [r, g, b, a] = linear.get_pixel(x, y).0;
+                Rgba([linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a].map(to_u8))
+            })
+        } else {
+            image.to_rgba8()
+        };
+
+        let decode: [f32; 256] = std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.));
+        let mut levels = vec![base];
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width() <= 1 && last.height() <= 1 {
+                break;
+            }
+            let next = downsample(last, role, &decode);
+            levels.push(next);
+        }
+        Self { role, levels }
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.role.color_space()
+    }
+
+    pub fn base(&self) -> &RgbaImage {
+        &self.levels[0]
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.base().dimensions()
+    }
+
+    // the level whose texels are about `texels_per_pixel` level 0 texels wide
+    pub fn level_for(&self, texels_per_pixel: f32) -> usize {
+        if texels_per_pixel.is_nan() || texels_per_pixel <= 1. {
+            return 0;
+        }
+        (texels_per_pixel.log2().round() as usize).min(self.levels.len() - 1)
+    }
+
+    // nearest texel of `level`, (x, y) in level 0 texel coordinates and clamped to the edge
+    pub fn texel(&self, x: f32, y: f32, level: usize) -> [u8; 4] {
+        let level = level.min(self.levels.len() - 1);
+        let image = &self.levels[level];
+        let scale = (1u32 << level) as f32;
+        let (x, y) = ((x / scale) as u32, (y / scale) as u32);
+        image.get_pixel(x.min(image.width() - 1), y.min(image.height() - 1)).0
+    }
+}
+
+// 2x2 box filter. colour is averaged as light, not as srgb numbers, and normals are
+// averaged as vectors and made unit length again
+fn downsample(image: &RgbaImage, role: TextureRole, decode: &[f32; 256]) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    RgbaImage::from_fn((width / 2).max(1), (height / 2).max(1), |x, y| {
+        let mut sum = [0f32; 4];
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let p = image.get_pixel((x * 2 + dx).min(width - 1), (y * 2 + dy).min(height - 1)).0;
+            for (k, (s, &c)) in sum.iter_mut().zip(&p).enumerate() {
+                *s += match role {
+                    TextureRole::Diffuse if k < 3 => decode[c as usize],
+                    TextureRole::Normal if k < 3 => c as f32 / 255. * 2. - 1.,
+                    _ => c as f32 / 255.,
+                };
+            }
+        }
+        let [r, g, b, a] = sum.map(|s| s / 4.);
+        let rgb = match role {
+            TextureRole::Diffuse => [r, g, b].map(linear_to_srgb),
+            TextureRole::Normal => {
+                let len = (r * r + g * g + b * b).sqrt();
+                let n = if len > 0. { [r / len, g / len, b / len] } else { [0., 0., 1.] };
+                n.map(|c| c * 0.5 + 0.5)
+            }
+            TextureRole::Roughness => [r, g, b],
+        };
+        Rgba([rgb[0], rgb[1], rgb[2], a].map(to_u8))
+    })
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+fn to_u8(c: f32) -> u8 {
+    (c.clamp(0., 1.) * 255. + 0.5) as u8
+}
+
+struct CachedTexture {
+    modified: Option<SystemTime>,
+    texture: Arc<MipTexture>,
+}
+
+// decoded textures by file and role, shared by both renderers. an entry is only used
+// while the file keeps its modification time, so an edited texture is decoded again
+#[derive(Default)]
+pub struct TextureCache {
+    textures: HashMap<(PathBuf, TextureRole), CachedTexture>,
+}
+
+lazy_static! {
+    static ref TEXTURE_CACHE: Mutex<TextureCache> = Mutex::new(TextureCache::default());
+}
+
+impl TextureCache {
+    // the format comes from the extension: png, jpeg, gif, tga, bmp and hdr
+    pub fn load(&mut self, path: &Path, role: TextureRole) -> Result<Arc<MipTexture>, LoadError> {
+        let key = (fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()), role);
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if let Some(cached) = self.textures.get(&key).filter(|c| c.modified.is_some() && c.modified == modified) {
+            debug!("{}: texture cache hit", path.display());
+            return Ok(cached.texture.clone());
+        }
+
+        let image = image::open(path).map_err(|source| LoadError::TextureDecode {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let texture = Arc::new(MipTexture::new(image, role));
+        self.textures.insert(key, CachedTexture {
+            modified,
+            texture: texture.clone(),
+        });
+        Ok(texture)
+    }
+}
+
+// `TextureCache::load` on the cache of the whole process
+pub fn load_texture(path: &Path, role: TextureRole) -> Result<Arc<MipTexture>, LoadError> {
+    TEXTURE_CACHE.lock().unwrap().load(path, role)
+}
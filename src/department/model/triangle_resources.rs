@@ -1,7 +1,16 @@
-use image::{DynamicImage, GenericImageView};
+use std::ops::Range;
+use std::sync::Arc;
+
 use tobj::{Material, Model};
 
+use crate::department::model::animation::Skin;
+use crate::department::model::auto_fit::{ModelBounds, UnitFit};
+use crate::department::model::lod::LodChain;
+use crate::department::model::mesh_repair::LoadWarning;
+use crate::department::model::obj_sequence::VertexAnimation;
+use crate::department::model::texture::MipTexture;
 use crate::department::model::triangle::Triangle;
+use crate::department::model::vertex_buffer::VertexBuffer;
 use crate::department::preview::vector::{Vec2, Vector3};
 
 pub struct TriangleIter<'a> {
@@ -20,7 +29,7 @@ impl<'a> Iterator for TriangleIter<'a> {
         }
 
 
-        let (width ,height) = self.resources.image.as_ref().unwrap().dimensions();
+        let (width ,height) = self.resources.texture.as_ref().map_or((2, 2), |t| t.dimensions());
         let (width, height) = (width - 1, height - 1);
 
         let m = &self.resources.model.mesh;
@@ -69,20 +78,160 @@ impl<'a> Iterator for TriangleIter<'a> {
     }
 }
 
+// the triangles of a merged model that came with their own material
+#[derive(Debug, Clone)]
+pub struct MaterialRange {
+    pub triangles: Range<u32>,
+    pub material: Material,
+    // whether `TriangleResources::texture` is this material's
+    pub textured: bool,
+}
+
 pub struct TriangleResources {
     pub model: Model,
     pub material: Option<Material>,
-    pub image: Option<DynamicImage>,
+    // per primitive materials of a merged model, sorted. `material` covers the triangles outside them
+    pub material_ranges: Vec<MaterialRange>,
+    // diffuse texture with its mip chain, shared with every other user of the file
+    pub texture: Option<Arc<MipTexture>>,
+    // packed once at load, what the cpu rasterizer actually walks
+    pub vertex_buffer: VertexBuffer,
+    // what had to be fixed up while loading
+    pub warnings: Vec<LoadWarning>,
+    // aabb and bounding sphere of the positions, kept up to date by `normalize`
+    pub bounds: ModelBounds,
+    // simplified copies of `vertex_buffer`, empty until `build_lods` or `set_lods`
+    pub lods: LodChain,
+    // joints and weights of a rigged model, positions stay in bind pose
+    pub skin: Option<Skin>,
+    // frames of a numbered obj sequence, see `pose_sequence`
+    pub sequence: Option<VertexAnimation>,
 }
 
 
 impl TriangleResources {
     pub fn new(model: Model) -> Self {
+        let vertex_buffer = VertexBuffer::from_mesh(&model.mesh, None);
+        let bounds = ModelBounds::from_models(std::slice::from_ref(&model));
         Self {
             model,
             material: None,
-            image: None,
+            material_ranges: Vec::new(),
+            texture: None,
+            vertex_buffer,
+            warnings: Vec::new(),
+            bounds,
+            lods: LodChain::default(),
+            skin: None,
+            sequence: None,
+        }
+    }
+
+    // recentre at the origin and scale to unit size, so any model fits the default view.
+    // positions are moved in place, the detail levels stay valid
+    pub fn normalize(&mut self) {
+        let fit = UnitFit::new(std::slice::from_ref(&self.model), &self.bounds);
+        fit.apply_to_model(&mut self.model);
+        fit.apply_to_buffer(&mut self.vertex_buffer);
+        for level in self.lods.levels.iter_mut() {
+            fit.apply_to_buffer(&mut level.buffer);
+        }
+        if let Some(skin) = self.skin.as_mut() {
+            skin.fit = Some(fit);
+        }
+        if let Some(sequence) = self.sequence.as_mut() {
+            sequence.apply_fit(&fit);
+        }
+        self.bounds = ModelBounds::from_models(std::slice::from_ref(&self.model));
+    }
+
+    // has to run again whenever `texture` changes, uv are baked in texel coordinates
+    pub fn build_vertex_buffer(&mut self) {
+        let size = self.texture.as_ref().map(|t| t.dimensions());
+        self.vertex_buffer = VertexBuffer::from_mesh(&self.model.mesh, size);
+        self.lods = LodChain::default();
+    }
+
+    // last step of loading, after anything that rebuilds the vertex buffer
+    pub fn build_lods(&mut self) {
+        self.set_lods(LodChain::build(&VertexBuffer::from_mesh(&self.model.mesh, None)));
+    }
+
+    // `lods` come with raw uv, as built from or cached for the model alone
+    pub fn set_lods(&mut self, mut lods: LodChain) {
+        if let Some(size) = self.texture.as_ref().map(|t| t.dimensions()) {
+            for level in lods.levels.iter_mut() {
+                level.buffer.scale_to_texels(size);
+            }
         }
+        self.lods = lods;
+    }
+
+    // moves every vertex to where the sequence has it `time` seconds in. sequences are
+    // drawn at full detail, the simplified levels couldn't follow the vertices
+    pub fn pose_sequence(&mut self, time: f32) {
+        let Some(sequence) = &self.sequence else {
+            return;
+        };
+        let (positions, normals) = sequence.sample(0, time);
+        for (v, (p, n)) in self.vertex_buffer.vertices.iter_mut().zip(positions.iter().zip(&normals)) {
+            v.position = *p;
+            v.normal = *n;
+        }
+        // picking and export read the mesh itself
+        for (&i, p) in self.vertex_buffer.position_indices.iter().zip(&positions) {
+            let i = i as usize * 3;
+            self.model.mesh.positions[i..i + 3].copy_from_slice(p);
+        }
+    }
+
+    // the buffer of one level and, below full detail, the full triangle each of its triangles came from
+    pub fn lod(&self, level: usize) -> (&VertexBuffer, Option<&[u32]>) {
+        match level.checked_sub(1).and_then(|i| self.lods.levels.get(i)) {
+            Some(l) => (&l.buffer, Some(&l.source_triangles)),
+            None => (&self.vertex_buffer, None),
+        }
+    }
+
+    // model space positions, normals and raw (unscaled) uv of one triangle
+    pub fn triangle_attributes(&self, triangle: usize) -> ([Vector3; 3], [Vector3; 3], [Vec2; 3]) {
+        let m = &self.model.mesh;
+        let corner = |k: usize| {
+            let i = triangle * 3 + k;
+            let (pi, ni, ti) = (
+                m.indices[i] as usize,
+                m.normal_indices[i] as usize,
+                m.texcoord_indices[i] as usize,
+            );
+            (
+                Vector3::from_xyz(m.positions[pi * 3], m.positions[pi * 3 + 1], m.positions[pi * 3 + 2]),
+                Vector3::from_xyz(m.normals[ni * 3], m.normals[ni * 3 + 1], m.normals[ni * 3 + 2]),
+                Vec2::from_xy(m.texcoords[ti * 2], m.texcoords[ti * 2 + 1]),
+            )
+        };
+        let (p0, n0, t0) = corner(0);
+        let (p1, n1, t1) = corner(1);
+        let (p2, n2, t2) = corner(2);
+        ([p0, p1, p2], [n0, n1, n2], [t0, t1, t2])
+    }
+
+    // index into `material_ranges` of the range holding `triangle`
+    pub fn material_range(&self, triangle: usize) -> Option<usize> {
+        let i = self.material_ranges.partition_point(|r| r.triangles.end as usize <= triangle);
+        self.material_ranges
+            .get(i)
+            .filter(|r| r.triangles.contains(&(triangle as u32)))
+            .map(|_| i)
+    }
+
+    // `texture` where it belongs to the material of `triangle`
+    pub fn texture_of(&self, triangle: usize) -> Option<&MipTexture> {
+        let textured = self.material_range(triangle).map_or(true, |i| self.material_ranges[i].textured);
+        self.texture.as_deref().filter(|_| textured)
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.model.mesh.indices.len() / 3
     }
 
     pub fn iter(&self) -> TriangleIter {
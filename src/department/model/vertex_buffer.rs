@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use tobj::Mesh;
+
+// one unique corner of the mesh, uv already in texel coordinates of the diffuse texture
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PackedVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    // white unless the mesh carries vertex colours
+    pub color: [f32; 3],
+}
+
+// obj keeps separate position/normal/texcoord indices, here every distinct triple
+// becomes one vertex and `indices` refers to those, three per triangle
+#[derive(Debug, Clone, Default)]
+pub struct VertexBuffer {
+    pub vertices: Vec<PackedVertex>,
+    pub indices: Vec<u32>,
+    // mesh position every vertex was made from, what per position data like skin weights
+    // is looked up by. empty for buffers that didn't keep track, like cached levels
+    pub position_indices: Vec<u32>,
+}
+
+impl VertexBuffer {
+    // `texture_size` scales and flips uv the way the rasterizer samples, raw uv are kept without it
+    pub fn from_mesh(mesh: &Mesh, texture_size: Option<(u32, u32)>) -> Self {
+        let mut unique: HashMap<(u32, u32, u32), u32> = HashMap::with_capacity(mesh.indices.len());
+        let mut buffer = VertexBuffer {
+            vertices: Vec::new(),
+            indices: Vec::with_capacity(mesh.indices.len()),
+            position_indices: Vec::new(),
+        };
+
+        for (i, &pi) in mesh.indices.iter().enumerate() {
+            let ni = mesh.normal_indices.get(i).copied().unwrap_or(pi);
+            let ti = mesh.texcoord_indices.get(i).copied().unwrap_or(pi);
+
+            let index = *unique.entry((pi, ni, ti)).or_insert_with(|| {
+                let (p, n, t) = (pi as usize * 3, ni as usize * 3, ti as usize * 2);
+                let position = [mesh.positions[p], mesh.positions[p + 1], mesh.positions[p + 2]];
+                let normal = if n + 2 < mesh.normals.len() {
+                    [mesh.normals[n], mesh.normals[n + 1], mesh.normals[n + 2]]
+                } else {
+                    [0., 0., 0.]
+                };
+                let uv = if t + 1 < mesh.texcoords.len() {
+                    [mesh.texcoords[t], mesh.texcoords[t + 1]]
+                } else {
+                    [0., 0.]
+                };
+
+                let color = if p + 2 < mesh.vertex_color.len() {
+                    [mesh.vertex_color[p], mesh.vertex_color[p + 1], mesh.vertex_color[p + 2]]
+                } else {
+                    [1., 1., 1.]
+                };
+
+                buffer.vertices.push(PackedVertex {
+                    position,
+                    normal,
+                    uv,
+                    color,
+                });
+                buffer.position_indices.push(pi);
+                (buffer.vertices.len() - 1) as u32
+            });
+            buffer.indices.push(index);
+        }
+
+        if let Some(size) = texture_size {
+            buffer.scale_to_texels(size);
+        }
+        buffer
+    }
+
+    // raw uv to the scaled and flipped texel coordinates the rasterizer samples with
+    pub fn scale_to_texels(&mut self, (width, height): (u32, u32)) {
+        let (width, height) = ((width.max(1) - 1) as f32, (height.max(1) - 1) as f32);
+        for v in self.vertices.iter_mut() {
+            let [u, t] = v.uv;
+            v.uv = [u * width, height - t * height];
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangle(&self, triangle: usize) -> [u32; 3] {
+        let i = triangle * 3;
+        [self.indices[i], self.indices[i + 1], self.indices[i + 2]]
+    }
+}
@@ -0,0 +1,195 @@
+use std::io::{Read, Write};
+use std::net::TcpStream as StdTcpStream;
+use std::sync::{Arc, Mutex};
+
+use log::{debug, error, info, warn};
+use protobuf::{EnumOrUnknown, Message};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::department::common::constant;
+use crate::pb::debugger::{
+    DebugInfo, DebugMethod, DebugPacket, RenderState, RenderStateUpdate, Vec3, Void,
+};
+
+// the renderer publishes `render_state` once per frame and drains `pending` before drawing
+#[derive(Default)]
+pub struct DebugState {
+    pub render_state: RenderState,
+    pub pending: Vec<RenderStateUpdate>,
+}
+
+pub type DebugHandle = Arc<Mutex<DebugState>>;
+
+// render state is a few hundred bytes, a longer frame is garbage or hostile
+const MAX_FRAME: u32 = 1 << 20;
+
+pub fn to_vec3(v: [f32; 3]) -> Vec3 {
+    let mut out = Vec3::new();
+    out.x = v[0];
+    out.y = v[1];
+    out.z = v[2];
+    out
+}
+
+pub fn from_vec3(v: &Vec3) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+pub struct DebugService {
+    state: DebugHandle,
+    host: String,
+}
+
+impl DebugService {
+    // `host` is the interface to listen on, `constant::DEBUG_HOST` keeps it to this machine
+    pub fn new(host: &str) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(DebugState::default())),
+            host: host.to_string(),
+        }
+    }
+
+    pub fn handle(&self) -> DebugHandle {
+        self.state.clone()
+    }
+
+    pub fn run(self) {
+        std::thread::Builder::new().name("dognut_debug_service".into()).spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            rt.block_on(async {
+                for i in 0..(constant::PORT_RANGE) {
+                    let host_str = format!("{}:{}", self.host, constant::DEBUG_PORT + i);
+                    if let Ok(lis) = TcpListener::bind(&host_str).await {
+                        info!("debug service listen on {}", host_str);
+                        self.accept(lis).await;
+                        break;
+                    }
+                }
+            });
+        }).unwrap();
+    }
+
+    async fn accept(&self, l: TcpListener) {
+        loop {
+            match l.accept().await {
+                Ok((stream, addr)) => {
+                    debug!("debug client connected from {}", addr);
+                    tokio::spawn(serve_client(stream, self.state.clone()));
+                }
+                Err(e) => {
+                    error!("error when accept debug client {}", e);
+                }
+            }
+        }
+    }
+}
+
+// one request, one reply, both framed like NetPacket: a big endian u32 length then the message
+async fn serve_client(mut stream: TcpStream, state: DebugHandle) {
+    loop {
+        let len = match stream.read_u32().await {
+            Ok(len) => len,
+            Err(_) => break,
+        };
+        if len > MAX_FRAME {
+            warn!("debug packet of {} bytes, dropping the client", len);
+            break;
+        }
+        let mut buf = vec![0u8; len as usize];
+        if stream.read_exact(&mut buf).await.is_err() {
+            break;
+        }
+
+        let reply = DebugPacket::parse_from_bytes(&buf)
+            .and_then(|req| handle_packet(&state, req))
+            .and_then(|reply| reply.write_to_bytes());
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                error!("bad debug packet, {}", e);
+                break;
+            }
+        };
+
+        if stream.write_u32(reply.len() as u32).await.is_err()
+            || stream.write_all(reply.as_slice()).await.is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn handle_packet(state: &DebugHandle, req: DebugPacket) -> protobuf::Result<DebugPacket> {
+    let mut reply = DebugPacket::new();
+    reply.method = req.method;
+    reply.payload = match req.method.enum_value() {
+        Ok(DebugMethod::OnGetDebugInfo) => {
+            let info = DebugInfo::parse_from_bytes(&req.payload)?;
+            let mut update = RenderStateUpdate::new();
+            update.triangleIndex = Some(info.triangleIndex);
+            state.lock().unwrap().pending.push(update);
+            Void::new().write_to_bytes()?
+        }
+        Ok(DebugMethod::GetRenderState) => state.lock().unwrap().render_state.write_to_bytes()?,
+        // the update is applied on the next frame, so the reply is the state it was applied to
+        Ok(DebugMethod::SetRenderState) => {
+            let update = RenderStateUpdate::parse_from_bytes(&req.payload)?;
+            let mut state = state.lock().unwrap();
+            state.pending.push(update);
+            state.render_state.write_to_bytes()?
+        }
+        Err(v) => {
+            warn!("unknown debug method {}", v);
+            Vec::new()
+        }
+    };
+    Ok(reply)
+}
+
+// blocking client for the debug service, used by the `debug_client` binary
+pub struct DebugClient {
+    stream: StdTcpStream,
+}
+
+impl DebugClient {
+    pub fn connect(addr: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            stream: StdTcpStream::connect(addr)?,
+        })
+    }
+
+    fn call(&mut self, method: DebugMethod, request: &impl Message) -> anyhow::Result<Vec<u8>> {
+        let mut packet = DebugPacket::new();
+        packet.method = EnumOrUnknown::new(method);
+        packet.payload = request.write_to_bytes()?;
+        let bytes = packet.write_to_bytes()?;
+        self.stream.write_all(&(bytes.len() as u32).to_be_bytes())?;
+        self.stream.write_all(&bytes)?;
+
+        let mut len = [0u8; 4];
+        self.stream.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len);
+        if len > MAX_FRAME {
+            anyhow::bail!("debug reply of {} bytes, more than {}", len, MAX_FRAME);
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.stream.read_exact(&mut buf)?;
+        Ok(DebugPacket::parse_from_bytes(&buf)?.payload)
+    }
+
+    pub fn on_get_debug_info(&mut self, info: &DebugInfo) -> anyhow::Result<()> {
+        self.call(DebugMethod::OnGetDebugInfo, info)?;
+        Ok(())
+    }
+
+    pub fn get_render_state(&mut self) -> anyhow::Result<RenderState> {
+        let payload = self.call(DebugMethod::GetRenderState, &Void::new())?;
+        Ok(RenderState::parse_from_bytes(&payload)?)
+    }
+
+    pub fn set_render_state(&mut self, update: &RenderStateUpdate) -> anyhow::Result<RenderState> {
+        let payload = self.call(DebugMethod::SetRenderState, update)?;
+        Ok(RenderState::parse_from_bytes(&payload)?)
+    }
+}
@@ -1,4 +1,6 @@
 pub mod shader;
 pub mod rasterizer;
+pub mod ray_tracer;
+pub mod path_tracer;
 
 
@@ -0,0 +1,452 @@
+use std::f32::consts::PI;
+
+use log::error;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tobj::Material;
+
+use crate::department::model::bvh::{Bvh, Ray, RayHit};
+use crate::department::model::triangle_resources::TriangleResources;
+use crate::department::pipeline::ray_tracer::{
+    add, debug_color, highlighted, mul, offset, primary_ray, reflect, refract, scale, schlick, screen_to_model, to_pixel,
+    Color, DirectionalLight, SurfaceHit, SurfaceMaterial,
+};
+use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::output_buffer::OutputBuffer;
+use crate::department::preview::vector::Vector3;
+use crate::department::view::camera::Camera;
+use crate::department::view::camera_trait::CameraTrait;
+use crate::department::pipeline::rasterizer::resolve_pick;
+use crate::department::view::picking::{PickId, PickResult, NO_PICK};
+use crate::pb::debugger::ShaderMode;
+
+// radiance of rays leaving the scene, keeps unlit sides from going pitch black
+const SKY: Color = [0.1, 0.1, 0.12];
+// bounces before russian roulette may stop a path
+const MIN_BOUNCES: u32 = 3;
+const MIN_ROUGHNESS: f32 = 0.02;
+
+// the obj material seen as a diffuse + GGX specular surface that may glow
+#[derive(Debug, Clone)]
+pub struct PathMaterial {
+    pub base: SurfaceMaterial,
+    pub specular: Color,
+    pub roughness: f32,
+    pub emission: Color,
+}
+
+impl PathMaterial {
+    pub fn from_material(material: Option<&Material>) -> Self {
+        let base = SurfaceMaterial::from_material(material);
+        let (specular, roughness, emission) = match material {
+            Some(m) => (
+                m.specular.unwrap_or([0.04, 0.04, 0.04]),
+                // the usual phong exponent to beckmann/ggx roughness mapping
+                m.shininess.map_or(0.5, |ns| (2. / (ns + 2.)).sqrt()),
+                m.unknown_param.get("Ke").and_then(|ke| parse_color(ke)).unwrap_or([0.; 3]),
+            ),
+            None => ([0.04; 3], 0.5, [0.; 3]),
+        };
+        Self {
+            base,
+            specular,
+            roughness: roughness.max(MIN_ROUGHNESS),
+            emission,
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.emission.iter().any(|e| *e > 0.)
+    }
+}
+
+fn parse_color(s: &str) -> Option<Color> {
+    let v: Vec<f32> = s.split_whitespace().filter_map(|c| c.parse().ok()).collect();
+    match v.len() {
+        1 => Some([v[0]; 3]),
+        3 => Some([v[0], v[1], v[2]]),
+        _ => None,
+    }
+}
+
+fn luminance(c: Color) -> f32 {
+    0.299 * c[0] + 0.587 * c[1] + 0.114 * c[2]
+}
+
+// tangent frame around `n`, see Duff et al. "Building an Orthonormal Basis, Revisited"
+fn basis(n: &Vector3) -> (Vector3, Vector3) {
+    let sign = 1f32.copysign(n.z());
+    let a = -1. / (sign + n.z());
+    let b = n.x() * n.y() * a;
+    (
+        Vector3::from_xyz(1. + sign * n.x() * n.x() * a, sign * b, -sign * n.x()),
+        Vector3::from_xyz(b, sign + n.y() * n.y() * a, -n.y()),
+    )
+}
+
+fn to_world(local: [f32; 3], n: &Vector3) -> Vector3 {
+    let (t, b) = basis(n);
+    Vector3::from_xyz(
+        t.x() * local[0] + b.x() * local[1] + n.x() * local[2],
+        t.y() * local[0] + b.y() * local[1] + n.y() * local[2],
+        t.z() * local[0] + b.z() * local[1] + n.z() * local[2],
+    )
+}
+
+fn cosine_hemisphere(rng: &mut StdRng, n: &Vector3) -> Vector3 {
+    let (u1, u2): (f32, f32) = (rng.gen(), rng.gen());
+    let r = u1.sqrt();
+    let phi = 2. * PI * u2;
+    to_world([r * phi.cos(), r * phi.sin(), (1. - u1).max(0.).sqrt()], n)
+}
+
+// GGX distributed half vector
+fn ggx_half_vector(rng: &mut StdRng, n: &Vector3, alpha: f32) -> Vector3 {
+    let (u1, u2): (f32, f32) = (rng.gen(), rng.gen());
+    let cos_theta = ((1. - u1) / (1. + (alpha * alpha - 1.) * u1)).sqrt();
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * u2;
+    to_world([sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta], n)
+}
+
+fn ggx_d(n_h: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let d = n_h * n_h * (a2 - 1.) + 1.;
+    a2 / (PI * d * d)
+}
+
+// separable smith G for GGX
+fn ggx_g(n_v: f32, n_l: f32, alpha: f32) -> f32 {
+    let g1 = |c: f32| 2. * c / (c + (alpha * alpha + (1. - alpha * alpha) * c * c).sqrt());
+    g1(n_v) * g1(n_l)
+}
+
+fn fresnel(f0: Color, cos: f32) -> Color {
+    let k = (1. - cos).powi(5);
+    [f0[0] + (1. - f0[0]) * k, f0[1] + (1. - f0[1]) * k, f0[2] + (1. - f0[2]) * k]
+}
+
+fn half(a: &Vector3, b: &Vector3) -> Vector3 {
+    let mut h = Vector3::from_xyz(a.x() + b.x(), a.y() + b.y(), a.z() + b.z());
+    h.norm();
+    h
+}
+
+struct PathScene {
+    bvh: Bvh,
+    // one per `TriangleResources::material_ranges`, then the one of the whole model
+    materials: Vec<PathMaterial>,
+}
+
+impl PathScene {
+    fn new(triangle_res: &TriangleResources) -> Self {
+        let materials = triangle_res
+            .material_ranges
+            .iter()
+            .map(|r| PathMaterial::from_material(Some(&r.material)))
+            .chain([PathMaterial::from_material(triangle_res.material.as_ref())])
+            .collect();
+        Self {
+            bvh: Bvh::build(triangle_res),
+            materials,
+        }
+    }
+
+    fn material(&self, triangle_res: &TriangleResources, triangle: usize) -> &PathMaterial {
+        let i = triangle_res.material_range(triangle).unwrap_or(self.materials.len() - 1);
+        &self.materials[i]
+    }
+}
+
+// Progressive Monte-Carlo path tracer. Every `render_frame` adds `samples_per_frame` paths
+// per pixel to the accumulation buffer and shows the running mean, until `sample_cap` is reached.
+pub struct PathTracer {
+    model_mat: HomoTransform,
+    camera: Camera,
+    light: DirectionalLight,
+    max_bounces: u32,
+    samples_per_frame: u32,
+    sample_cap: u32,
+    shader_mode: ShaderMode,
+    highlight: Option<PickId>,
+    tui: bool,
+    scene: Option<PathScene>,
+    rng: StdRng,
+    accumulation: Vec<Color>,
+    ids: Vec<PickId>,
+    dimension: (u32, u32),
+    samples: u32,
+}
+
+impl PathTracer {
+    pub fn new(camera: Camera, light_source: Vector3, light_intensity: f32, sample_cap: u32, tui: bool) -> Self {
+        let mut direction = light_source;
+        direction.norm();
+        Self {
+            model_mat: HomoTransform::identity_matrix(),
+            camera,
+            light: DirectionalLight {
+                direction,
+                intensity: light_intensity,
+            },
+            max_bounces: 8,
+            samples_per_frame: 1,
+            sample_cap: sample_cap.max(1),
+            shader_mode: ShaderMode::Lambertian,
+            highlight: None,
+            tui,
+            scene: None,
+            rng: StdRng::from_entropy(),
+            accumulation: Vec::new(),
+            ids: Vec::new(),
+            dimension: (0, 0),
+            samples: 0,
+        }
+    }
+
+    pub fn set_model(&mut self, m: HomoTransform) {
+        self.model_mat = m;
+        self.reset();
+    }
+
+    // same as `RasterRunner::set_viewport`, the samples so far were taken for the old one
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.camera.update_projection(width, height);
+        self.reset();
+    }
+
+    pub fn set_samples_per_frame(&mut self, samples: u32) {
+        self.samples_per_frame = samples.max(1);
+    }
+
+    // drawn over the accumulated mean, the samples stay valid
+    pub fn set_highlight(&mut self, id: Option<PickId>) {
+        self.highlight = id;
+    }
+
+    pub fn shader_mode(&self) -> ShaderMode {
+        self.shader_mode
+    }
+
+    pub fn set_shader_mode(&mut self, mode: ShaderMode) {
+        self.shader_mode = mode;
+        self.reset();
+    }
+
+    pub fn camera_pose(&self) -> ([f32; 3], [f32; 3]) {
+        self.camera.pose()
+    }
+
+    pub fn set_camera_pose(&mut self, position: [f32; 3], forward: [f32; 3]) {
+        self.camera.set_pose(position, forward);
+        self.reset();
+    }
+
+    pub fn set_scene(&mut self, triangle_res: &TriangleResources) {
+        self.scene = Some(PathScene::new(triangle_res));
+        self.reset();
+    }
+
+    // throws away the accumulated samples, needed whenever the view changes
+    pub fn reset(&mut self) {
+        self.accumulation.clear();
+        self.ids.clear();
+        self.samples = 0;
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn is_converged(&self) -> bool {
+        self.samples >= self.sample_cap
+    }
+
+    pub fn render_frame(&mut self, triangle_res: &TriangleResources, out: &mut OutputBuffer) {
+        if self.scene.is_none() {
+            error!("path tracer has no scene, call set_scene first");
+            return;
+        }
+        let dimension = out.dimension();
+        if dimension != self.dimension || self.accumulation.is_empty() {
+            self.dimension = dimension;
+            self.reset();
+            let pixels = (dimension.0 * dimension.1) as usize;
+            self.accumulation = vec![[0.; 3]; pixels];
+            self.ids = vec![NO_PICK; pixels];
+        }
+
+        if !self.is_converged() {
+            self.accumulate(triangle_res, out);
+        }
+
+        let inv = 1. / self.samples.max(1) as f32;
+        let (width, height) = dimension;
+        for j in 0..height {
+            for i in 0..width {
+                let idx = (j * width + i) as usize;
+                let mut color = scale(self.accumulation[idx], inv);
+                if self.highlight == Some(self.ids[idx]) {
+                    color = highlighted(color);
+                }
+                out.set_id(i as usize, j as usize, self.ids[idx]);
+                out.put_pixel(i, j, &to_pixel(color, self.tui));
+            }
+        }
+    }
+
+    // like `RasterRunner::pick`, against the triangles the bvh was built from
+    pub fn pick(&self, triangle_res: &TriangleResources, out: &OutputBuffer, x: u32, y: u32) -> Option<PickResult> {
+        let id = out.get_id(x as usize, y as usize);
+        if id.is_none() {
+            return None;
+        }
+        let screen_to_model = screen_to_model(&self.model_mat, &self.camera, out)?;
+        let attributes = triangle_res.triangle_attributes(id.triangle as usize);
+        resolve_pick(id, attributes, &screen_to_model, &self.model_mat, x, y)
+    }
+
+    fn accumulate(&mut self, triangle_res: &TriangleResources, out: &OutputBuffer) {
+        let screen_to_model = match screen_to_model(&self.model_mat, &self.camera, out) {
+            Some(m) => m,
+            None => return,
+        };
+        let to_light = self.to_light();
+        let scene = self.scene.take().unwrap();
+
+        let (width, height) = self.dimension;
+        let samples = self.samples_per_frame.min(self.sample_cap - self.samples);
+        for j in 0..height {
+            for i in 0..width {
+                let idx = (j * width + i) as usize;
+                for _ in 0..samples {
+                    // jittered inside the pixel, so the mean is also anti aliased
+                    let (dx, dy): (f32, f32) = (self.rng.gen(), self.rng.gen());
+                    let ray = primary_ray(&screen_to_model, i as f32 + dx, j as f32 + dy);
+                    let radiance = self.trace(triangle_res, &scene, &to_light, ray, idx);
+                    self.accumulation[idx] = add(self.accumulation[idx], radiance);
+                }
+            }
+        }
+        self.samples += samples;
+        self.scene = Some(scene);
+    }
+
+    fn to_light(&self) -> Vector3 {
+        let l = &self.light.direction.to_linear_matrix()
+            * &self.model_mat.inverse_matrix().unwrap_or_else(HomoTransform::identity_matrix);
+        let mut l = Vector3::from_xyz(-l.x(), -l.y(), -l.z());
+        l.norm();
+        l
+    }
+
+    fn trace(&mut self, res: &TriangleResources, scene: &PathScene, to_light: &Vector3, ray: Ray, pixel: usize) -> Color {
+        let mut ray = ray;
+        let mut radiance = [0.; 3];
+        let mut throughput = [1.; 3];
+
+        for bounce in 0..self.max_bounces {
+            let hit: RayHit = match scene.bvh.closest_hit(&ray) {
+                Some(hit) => hit,
+                None => {
+                    radiance = add(radiance, mul(throughput, SKY));
+                    break;
+                }
+            };
+            if bounce == 0 {
+                self.ids[pixel] = PickId::new(0, hit.triangle as u32);
+            }
+            let material = scene.material(res, hit.triangle);
+
+            let surface = SurfaceHit::new(res, &material.base, &hit);
+            // the debug modes only look at the primary hit
+            if let Some(color) = debug_color(self.shader_mode, &surface) {
+                return color;
+            }
+            if material.is_emissive() {
+                radiance = add(radiance, mul(throughput, material.emission));
+            }
+
+            let front = ray.direction.dot(&surface.geometric_normal) < 0.;
+            let facing = if front { 1. } else { -1. };
+            let mut n = surface.normal.clone();
+            n *= facing;
+            let mut ng = surface.geometric_normal.clone();
+            ng *= facing;
+            let mut wo = ray.direction.clone();
+            wo *= -1.;
+
+            // glass: pick reflection or refraction by fresnel, the weights cancel out
+            if material.base.transparency > 0. && self.rng.gen::<f32>() < material.base.transparency {
+                let eta = if front { 1. / material.base.ior } else { material.base.ior };
+                let reflect_prob = match refract(&ray.direction, &n, eta) {
+                    Some(_) => schlick(wo.dot(&n).max(0.), eta),
+                    None => 1.,
+                };
+                ray = if self.rng.gen::<f32>() < reflect_prob {
+                    Ray::new(offset(&surface.position, &ng, 1.), reflect(&ray.direction, &n))
+                } else {
+                    throughput = mul(throughput, surface.albedo);
+                    Ray::new(offset(&surface.position, &ng, -1.), refract(&ray.direction, &n, eta).unwrap())
+                };
+                continue;
+            }
+
+            let albedo = surface.albedo;
+            let n_v = wo.dot(&n).max(1e-4);
+            let alpha = material.roughness * material.roughness;
+
+            // next event estimation towards the sun, a delta light can only be reached this way
+            let n_l = n.dot(to_light);
+            if n_l > 0.
+                && !scene
+                    .bvh
+                    .occluded(&Ray::new(offset(&surface.position, &ng, 1.), to_light.clone()), 0., f32::INFINITY)
+            {
+                let h = half(&wo, to_light);
+                let f = fresnel(material.specular, wo.dot(&h).max(0.));
+                let spec = ggx_d(n.dot(&h).max(0.), alpha) * ggx_g(n_v, n_l, alpha) / (4. * n_v * n_l);
+                let brdf = add(scale(albedo, 1. / PI), scale(f, spec));
+                // intensity is irradiance over pi, so a white lambert surface matches the ray tracer
+                let e = self.light.intensity * PI * n_l;
+                radiance = add(radiance, mul(throughput, scale(brdf, e)));
+            }
+
+            // choose a lobe in proportion to how much it reflects
+            let spec_weight = luminance(material.specular);
+            let diff_weight = luminance(albedo);
+            let p_spec = if spec_weight + diff_weight > 0. {
+                (spec_weight / (spec_weight + diff_weight)).clamp(0.1, 0.9)
+            } else {
+                break;
+            };
+
+            let (wi, weight) = if self.rng.gen::<f32>() < p_spec {
+                let h = ggx_half_vector(&mut self.rng, &n, alpha);
+                let wi = reflect(&ray.direction, &h);
+                let n_l = wi.dot(&n);
+                if n_l <= 0. {
+                    break;
+                }
+                let v_h = wo.dot(&h).max(1e-4);
+                // D cancels against the half vector pdf
+                let w = ggx_g(n_v, n_l, alpha) * v_h / (n_v * n.dot(&h).max(1e-4));
+                (wi, scale(fresnel(material.specular, v_h), w / p_spec))
+            } else {
+                // cos / pi cancels against the pdf
+                (cosine_hemisphere(&mut self.rng, &n), scale(albedo, 1. / (1. - p_spec)))
+            };
+            throughput = mul(throughput, weight);
+
+            if bounce >= MIN_BOUNCES {
+                let survive = luminance(throughput).clamp(0.05, 0.95);
+                if self.rng.gen::<f32>() > survive {
+                    break;
+                }
+                throughput = scale(throughput, 1. / survive);
+            }
+            ray = Ray::new(offset(&surface.position, &ng, 1.), wi);
+        }
+        radiance
+    }
+}
@@ -1,14 +1,27 @@
-use image::GenericImageView;
+use std::time::Duration;
 
+use crate::department::control::camera_controller::CameraController;
+use crate::department::model::bvh::intersect_triangle;
+use crate::department::model::lod::projected_radius;
+use crate::department::model::skeleton::{skin_vertex, Mat4};
 use crate::department::model::triangle::Triangle;
 use crate::department::model::triangle_resources::TriangleResources;
-use crate::department::pipeline::shader::Shader;
+use crate::department::model::vertex_buffer::VertexBuffer;
+use crate::department::pipeline::ray_tracer::primary_ray;
+use crate::department::pipeline::shader::{shader_for_mode, Shader};
 use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::matrix::{HMat, Matrix};
 use crate::department::preview::output_buffer::OutputBuffer;
-use crate::department::preview::vector::Vector3;
+use crate::department::preview::position::Pos3;
+use crate::department::preview::vector::{HVec4, Vec2, Vector3};
+use crate::department::view::picking::{PickId, PickResult};
 use crate::department::types::msg::TransferMsg;
 use crate::department::types::multi_sender::MultiSender;
 use crate::department::view::camera::Camera;
+use crate::department::view::camera_trait::CameraTrait;
+use crate::pb::debugger::ShaderMode;
+
+pub(crate) const HIGHLIGHT_COLOR: [u8; 3] = [255, 220, 0];
 
 pub struct RasterRunner {
     pub encoder_tx: MultiSender<TransferMsg>,
@@ -17,7 +30,13 @@ pub struct RasterRunner {
     proj_mat: HomoTransform,
     camera: Camera,
     shader: Box<dyn Shader>,
+    // replaces `shader` while a debug shader mode is active
+    mode_shader: Option<Box<dyn Shader>>,
+    shader_mode: ShaderMode,
     tui: bool,
+    highlight: Option<PickId>,
+    // pose of a rigged model, empty draws it in bind pose
+    joint_matrices: Vec<Mat4>,
 }
 
 impl RasterRunner {
@@ -34,7 +53,11 @@ impl RasterRunner {
             proj_mat: camera.perspective_projection.clone(),
             camera,
             shader,
+            mode_shader: None,
+            shader_mode: ShaderMode::Lambertian,
             tui,
+            highlight: None,
+            joint_matrices: Vec::new(),
         }
     }
 
@@ -42,59 +65,270 @@ impl RasterRunner {
         self.model_mat = m;
     }
 
+    pub fn set_highlight(&mut self, id: Option<PickId>) {
+        self.highlight = id;
+    }
+
+    pub fn highlight(&self) -> Option<PickId> {
+        self.highlight
+    }
+
+    pub fn set_joint_matrices(&mut self, matrices: Vec<Mat4>) {
+        self.joint_matrices = matrices;
+    }
+
+    pub fn shader_mode(&self) -> ShaderMode {
+        self.shader_mode
+    }
+
+    pub fn set_shader_mode(&mut self, mode: ShaderMode) {
+        self.mode_shader = shader_for_mode(mode, self.tui);
+        self.shader_mode = mode;
+    }
+
+    pub fn camera_pose(&self) -> ([f32; 3], [f32; 3]) {
+        self.camera.pose()
+    }
+
+    pub fn set_camera_pose(&mut self, position: [f32; 3], forward: [f32; 3]) {
+        self.camera.set_pose(position, forward);
+        self.view_mat = self.camera.to_view_matrix();
+    }
+
+    // moves the camera by what the controller collected since the last frame, like
+    // `State::update_outside` does on the gpu
+    pub fn update_camera(&mut self, controller: &mut CameraController, dt: Duration) {
+        controller.update_camera(&mut self.camera, dt);
+        self.view_mat = self.camera.to_view_matrix();
+    }
+
+    // `width` x `height` is the frame in square pixels, see `Presenter::projection_size`
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.camera.update_projection(width, height);
+        self.proj_mat = self.camera.perspective_projection.clone();
+    }
+
+    pub fn frame(&mut self, center: [f32; 3], radius: f32) {
+        self.camera.frame(center, radius);
+        self.view_mat = self.camera.to_view_matrix();
+    }
+
     pub fn render_frame(&self, triangle_res: &TriangleResources, out: &mut OutputBuffer) {
+        let shader = self.mode_shader.as_ref().unwrap_or(&self.shader);
         let mv = &self.model_mat * &self.view_mat;
         let mvp = &mv * &self.proj_mat;
         let view_port = out.to_view_port_matrix();
-        let image = triangle_res.image.as_ref().unwrap();
+        let mvp_view_port = &mvp * &view_port;
+        // untextured meshes (stl, ply) are coloured by their vertices instead
+        let texture = triangle_res.texture.as_deref();
+        let (buffer, source_triangles) = triangle_res.lod(self.lod_level(triangle_res, out));
+        let skinned = self.skin_vertices(triangle_res, buffer);
+        let vertex = |c: usize| match &skinned {
+            Some(s) => s[c],
+            None => (buffer.vertices[c].position, buffer.vertices[c].normal),
+        };
+
+        // post-transform cache, every unique vertex goes through the matrices once per frame
+        let screen: Vec<HVec4> = (0..buffer.vertices.len())
+            .map(|c| {
+                let [x, y, z] = vertex(c).0;
+                &HVec4::from_v3(Vector3::from_xyz(x, y, z)) * &mvp_view_port
+            })
+            .collect();
+        let screen_divide: Vec<Vector3> = screen
+            .iter()
+            .map(|v| Vector3::from_xyz(v.x() / v.w(), v.y() / v.w(), v.z() / v.w()))
+            .collect();
 
-        for mut triangle in triangle_res.iter() {
-            let screen = triangle.clip_return_screen_no_divide(&mvp, &view_port);
-            let screen_divide: Vec<Vector3> = screen
+        // input assembly, three indices per triangle into the cache
+        for (tri_idx, corners) in buffer.indices.chunks_exact(3).enumerate() {
+            // ids always name full detail triangles, so picking doesn't depend on the level
+            let triangle = source_triangles.map_or(tri_idx as u32, |s| s[tri_idx]);
+            let id = PickId::new(0, triangle);
+            let highlighted = self.highlight == Some(id);
+            let corners = [corners[0] as usize, corners[1] as usize, corners[2] as usize];
+            let tri_screen: Vec<Vector3> = corners.iter().map(|&c| screen_divide[c].clone()).collect();
+            let w = corners.map(|c| screen[c].w());
+            let normal: Vec<Vector3> = corners
                 .iter()
-                .map(|v| {
-                    let d = v / v.index(0, 3);
-                    Vector3::from_xyz(d.index(0, 0), d.index(0, 1), d.index(0, 2))
+                .map(|&c| {
+                    let [x, y, z] = vertex(c).1;
+                    Vector3::from_xyz(x, y, z)
                 })
                 .collect();
+            let uvs = corners.map(|c| buffer.vertices[c].uv);
+            let colors = corners.map(|c| buffer.vertices[c].color);
+            // one mip level per triangle, from how many texels each covered pixel spans
+            let level = texture.map_or(0, |t| {
+                let area = |p: [[f32; 2]; 3]| {
+                    ((p[1][0] - p[0][0]) * (p[2][1] - p[0][1]) - (p[2][0] - p[0][0]) * (p[1][1] - p[0][1])).abs()
+                };
+                let pixels = area([0, 1, 2].map(|k| [tri_screen[k].x(), tri_screen[k].y()]));
+                if pixels > 0. {
+                    t.level_for((area(uvs) / pixels).sqrt())
+                } else {
+                    0
+                }
+            });
 
-            let (sx, ex, sy, ey) = Triangle::bounding_box(&screen_divide);
+            // nothing is clipped before this, a vertex may be anywhere off screen. negative
+            // coordinates already saturate to 0 in the cast
+            let (sx, ex, sy, ey) = Triangle::bounding_box(&tri_screen);
+            let (width, height) = out.dimension();
+            let (ex, ey) = (ex.min(width), ey.min(height));
 
             for i in sx..ex {
                 for j in sy..ey {
                     let p = Vector3::from_xyz(i as f32 + 0.5, j as f32 + 0.5, 0.);
 
-                    let bar = Triangle::barycentric_2d_out((p.x(), p.y()), &screen_divide);
+                    let bar = Triangle::barycentric_2d_out((p.x(), p.y()), &tri_screen);
 
                     if bar.x() < 0. || bar.y() < 0. || bar.z() < 0. {
                         continue;
                     }
 
-                    let reci = 1.
-                        / (bar.x() / screen[0].w()
-                            + bar.y() / screen[1].w()
-                            + bar.z() / screen[2].w());
+                    let reci = 1. / (bar.x() / w[0] + bar.y() / w[1] + bar.z() / w[2]);
                     let bar_correct = Vector3::from_xyz(
-                        (bar.x() / screen[0].w()) * reci,
-                        (bar.y() / screen[1].w()) * reci,
-                        (bar.z() / screen[2].w()) * reci,
+                        (bar.x() / w[0]) * reci,
+                        (bar.y() / w[1]) * reci,
+                        (bar.z() / w[2]) * reci,
                     );
 
                     let z_current = bar_correct.dot(&Vector3::from_xyz(
-                        screen_divide[0].z(),
-                        screen_divide[1].z(),
-                        screen_divide[2].z(),
+                        tri_screen[0].z(),
+                        tri_screen[1].z(),
+                        tri_screen[2].z(),
                     ));
 
                     if z_current > out.get_depth(p.x() as usize, p.y() as usize) {
                         out.set_depth(p.x() as usize, p.y() as usize, z_current);
-                        let uv = triangle.get_uv(&bar_correct);
-                        let color = image.get_pixel(uv.u() as u32, uv.v() as u32);
-                        let shade = self.shader.shade(&triangle.normal, &color.0, &bar_correct);
+                        out.set_id(p.x() as usize, p.y() as usize, id);
+                        let u = bar_correct.x() * uvs[0][0] + bar_correct.y() * uvs[1][0] + bar_correct.z() * uvs[2][0];
+                        let v = bar_correct.x() * uvs[0][1] + bar_correct.y() * uvs[1][1] + bar_correct.z() * uvs[2][1];
+                        let color = match texture {
+                            Some(texture) => texture.texel(u, v, level),
+                            None => {
+                                let c = [0, 1, 2].map(|k| {
+                                    bar_correct.x() * colors[0][k] + bar_correct.y() * colors[1][k] + bar_correct.z() * colors[2][k]
+                                });
+                                [c[0], c[1], c[2], 1.].map(|v| (v.clamp(0., 1.) * 255.) as u8)
+                            }
+                        };
+                        let mut shade = shader.shade(&normal, &color, &bar_correct);
+                        if highlighted {
+                            for c in 0..3 {
+                                shade[c] = ((shade[c] as u16 + HIGHLIGHT_COLOR[c] as u16) / 2) as u8;
+                            }
+                        }
                         out.put_pixel(i, j, &shade);
                     }
                 }
             }
         }
     }
+
+    // vertex stage skinning, positions and normals of a rigged model in the current pose
+    fn skin_vertices(&self, triangle_res: &TriangleResources, buffer: &VertexBuffer) -> Option<Vec<([f32; 3], [f32; 3])>> {
+        let skin = triangle_res.skin.as_ref().filter(|_| !self.joint_matrices.is_empty())?;
+        let matrices = skin.fitted(&self.joint_matrices);
+        let skinned = buffer
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let influence = buffer
+                    .position_indices
+                    .get(i)
+                    .map_or(([0; 4], [0.; 4]), |&p| skin.weights.influence(p));
+                skin_vertex(v.position, v.normal, influence, &matrices)
+            })
+            .collect();
+        Some(skinned)
+    }
+
+    // the further away the model is, the fewer pixels it covers and the coarser the level
+    fn lod_level(&self, triangle_res: &TriangleResources, out: &OutputBuffer) -> usize {
+        if triangle_res.lods.levels.is_empty() {
+            return 0;
+        }
+        let bounds = &triangle_res.bounds;
+        let center = &HVec4::from_v3(bounds.sphere.center.clone()) * &self.model_mat;
+        let (eye, _) = self.camera.pose();
+        let distance = [center.x() / center.w(), center.y() / center.w(), center.z() / center.w()]
+            .iter()
+            .zip(eye)
+            .map(|(c, e)| (c - e) * (c - e))
+            .sum::<f32>()
+            .sqrt();
+        let radius = projected_radius(bounds.radius(), distance, self.camera.fov_y(), out.dimension().1 as f32);
+        triangle_res.lods.level(triangle_res.vertex_buffer.triangle_count(), radius)
+    }
+
+    // resolves the pixel of the last rendered frame back to the triangle under it,
+    // `out` must still hold the id buffer of that frame.
+    pub fn pick(&self, triangle_res: &TriangleResources, out: &OutputBuffer, x: u32, y: u32) -> Option<PickResult> {
+        let id = out.get_id(x as usize, y as usize);
+        if id.is_none() {
+            return None;
+        }
+
+        let mvp = &(&self.model_mat * &self.view_mat) * &self.proj_mat;
+        let screen_to_model = (&mvp * &out.to_view_port_matrix()).inverse_matrix()?;
+        let attributes = self.posed_attributes(triangle_res, id.triangle as usize);
+        resolve_pick(id, attributes, &screen_to_model, &self.model_mat, x, y)
+    }
+
+    // `TriangleResources::triangle_attributes` in the pose the last frame was skinned with
+    fn posed_attributes(&self, triangle_res: &TriangleResources, triangle: usize) -> ([Vector3; 3], [Vector3; 3], [Vec2; 3]) {
+        let (positions, normals, uvs) = triangle_res.triangle_attributes(triangle);
+        let Some(skin) = triangle_res.skin.as_ref().filter(|_| !self.joint_matrices.is_empty()) else {
+            return (positions, normals, uvs);
+        };
+        let matrices = skin.fitted(&self.joint_matrices);
+        let indices = &triangle_res.model.mesh.indices;
+        let skinned = [0, 1, 2].map(|k| {
+            let (p, n) = (&positions[k], &normals[k]);
+            let influence = skin.weights.influence(indices[triangle * 3 + k]);
+            let (p, n) = skin_vertex([p.x(), p.y(), p.z()], [n.x(), n.y(), n.z()], influence, &matrices);
+            (Vector3::from_xyz(p[0], p[1], p[2]), Vector3::from_xyz(n[0], n[1], n[2]))
+        });
+        let [(p0, n0), (p1, n1), (p2, n2)] = skinned;
+        ([p0, p1, p2], [n0, n1, n2], uvs)
+    }
+}
+
+// where the ray through the center of pixel (x, y) meets the triangle `id` names, the
+// attributes are that triangle in model space as the frame drew it
+pub(crate) fn resolve_pick(
+    id: PickId,
+    (positions, normals, uvs): ([Vector3; 3], [Vector3; 3], [Vec2; 3]),
+    screen_to_model: &HMat,
+    model_mat: &HomoTransform,
+    x: u32,
+    y: u32,
+) -> Option<PickResult> {
+    let ray = primary_ray(screen_to_model, x as f32 + 0.5, y as f32 + 0.5);
+    let (_, u, v) = intersect_triangle(&positions, &ray, f32::NEG_INFINITY, f32::INFINITY)?;
+    let bar = Vector3::from_xyz(1. - u - v, u, v);
+
+    let local = &bar * &Matrix::<3, 3>::from_rows(positions.to_vec());
+    let world_position = Pos3::from_matrix(&(&local.to_homogeneous() * model_mat));
+
+    let local_normal = &bar * &Matrix::<3, 3>::from_rows(normals.to_vec());
+    let model_it = model_mat
+        .inverse_matrix()
+        .map(|m| m.t())
+        .unwrap_or_else(HomoTransform::identity_matrix);
+    let n = &local_normal.to_linear_matrix() * &model_it;
+    let mut normal = Vector3::from_xyz(n.x(), n.y(), n.z());
+    normal.norm();
+
+    let uv = &bar * &Matrix::<3, 2>::from_rows(uvs.to_vec());
+
+    Some(PickResult {
+        id,
+        world_position,
+        normal,
+        uv,
+    })
 }
@@ -0,0 +1,446 @@
+use log::error;
+use tobj::Material;
+
+use crate::department::model::bvh::{Bvh, Ray, RayHit};
+use crate::department::model::texture::MipTexture;
+use crate::department::model::triangle_resources::TriangleResources;
+use crate::department::pipeline::rasterizer::{resolve_pick, HIGHLIGHT_COLOR};
+use crate::department::pipeline::shader::luminance_byte;
+use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::matrix::{HMat, Matrix};
+use crate::department::preview::output_buffer::OutputBuffer;
+use crate::department::preview::position::Pos3;
+use crate::department::preview::vector::{HVec4, Vec2, Vector3};
+use crate::department::view::camera::Camera;
+use crate::department::view::camera_trait::CameraTrait;
+use crate::department::view::picking::{PickId, PickResult};
+use crate::pb::debugger::ShaderMode;
+
+// offset along the geometric normal so secondary rays do not hit their own triangle
+const SURFACE_BIAS: f32 = 1e-3;
+const AMBIENT: f32 = 0.1;
+const DEFAULT_IOR: f32 = 1.5;
+
+pub type Color = [f32; 3];
+
+// what the tracer needs out of the obj material, see the `illum` models of the mtl spec
+#[derive(Debug, Clone)]
+pub struct SurfaceMaterial {
+    pub diffuse: Color,
+    pub reflectivity: f32,
+    pub transparency: f32,
+    pub ior: f32,
+}
+
+impl Default for SurfaceMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: [1., 1., 1.],
+            reflectivity: 0.,
+            transparency: 0.,
+            ior: DEFAULT_IOR,
+        }
+    }
+}
+
+impl SurfaceMaterial {
+    pub fn from_material(material: Option<&Material>) -> Self {
+        let mut out = Self::default();
+        let m = match material {
+            Some(m) => m,
+            None => return out,
+        };
+
+        if let Some(kd) = m.diffuse {
+            out.diffuse = kd;
+        }
+        let specular = m.specular.map_or(0., |ks| (ks[0] + ks[1] + ks[2]) / 3.);
+        let dissolve = m.dissolve.unwrap_or(1.);
+        out.ior = m.optical_density.unwrap_or(DEFAULT_IOR);
+
+        match m.illumination_model {
+            // reflection on, with (5) or without (3) fresnel
+            Some(3) | Some(5) => out.reflectivity = specular.max(0.5),
+            // glass
+            Some(4) | Some(6) | Some(7) => {
+                out.transparency = if dissolve < 1. { 1. - dissolve } else { 1. };
+            }
+            _ => {
+                if dissolve < 1. {
+                    out.transparency = 1. - dissolve;
+                }
+            }
+        }
+        out
+    }
+}
+
+// a directional light, `direction` is where the light travels to, as for `LambertianShader`
+#[derive(Debug, Clone)]
+pub struct DirectionalLight {
+    pub direction: Vector3,
+    pub intensity: f32,
+}
+
+struct TracerScene {
+    bvh: Bvh,
+    // one per `TriangleResources::material_ranges`, then the one of the whole model
+    materials: Vec<SurfaceMaterial>,
+}
+
+impl TracerScene {
+    fn new(triangle_res: &TriangleResources) -> Self {
+        let materials = triangle_res
+            .material_ranges
+            .iter()
+            .map(|r| SurfaceMaterial::from_material(Some(&r.material)))
+            .chain([SurfaceMaterial::from_material(triangle_res.material.as_ref())])
+            .collect();
+        Self {
+            bvh: Bvh::build(triangle_res),
+            materials,
+        }
+    }
+
+    fn material(&self, triangle_res: &TriangleResources, triangle: usize) -> &SurfaceMaterial {
+        let i = triangle_res.material_range(triangle).unwrap_or(self.materials.len() - 1);
+        &self.materials[i]
+    }
+}
+
+// Whitted style: hard shadows towards the light, mirror reflection and refraction,
+// recursing up to `max_depth` bounces.
+pub struct RayTracer {
+    model_mat: HomoTransform,
+    camera: Camera,
+    light: DirectionalLight,
+    max_depth: u32,
+    shader_mode: ShaderMode,
+    highlight: Option<PickId>,
+    tui: bool,
+    scene: Option<TracerScene>,
+}
+
+pub(crate) fn add(a: Color, b: Color) -> Color {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub(crate) fn mul(a: Color, b: Color) -> Color {
+    [a[0] * b[0], a[1] * b[1], a[2] * b[2]]
+}
+
+pub(crate) fn scale(a: Color, s: f32) -> Color {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub(crate) fn lerp(a: Color, b: Color, t: f32) -> Color {
+    add(scale(a, 1. - t), scale(b, t))
+}
+
+pub(crate) fn reflect(d: &Vector3, n: &Vector3) -> Vector3 {
+    let k = 2. * d.dot(n);
+    Vector3::from_xyz(d.x() - k * n.x(), d.y() - k * n.y(), d.z() - k * n.z())
+}
+
+// `eta` is n_from / n_to, None on total internal reflection
+pub(crate) fn refract(d: &Vector3, n: &Vector3, eta: f32) -> Option<Vector3> {
+    let cos_i = -d.dot(n);
+    let sin2_t = eta * eta * (1. - cos_i * cos_i);
+    if sin2_t > 1. {
+        return None;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    let k = eta * cos_i - cos_t;
+    Some(Vector3::from_xyz(
+        eta * d.x() + k * n.x(),
+        eta * d.y() + k * n.y(),
+        eta * d.z() + k * n.z(),
+    ))
+}
+
+pub(crate) fn schlick(cos: f32, eta: f32) -> f32 {
+    let r0 = ((1. - eta) / (1. + eta)).powi(2);
+    r0 + (1. - r0) * (1. - cos).powi(5)
+}
+
+pub(crate) fn offset(p: &Vector3, n: &Vector3, sign: f32) -> Vector3 {
+    Vector3::from_xyz(
+        p.x() + n.x() * SURFACE_BIAS * sign,
+        p.y() + n.y() * SURFACE_BIAS * sign,
+        p.z() + n.z() * SURFACE_BIAS * sign,
+    )
+}
+
+// uv in [0, 1], flipped and scaled the same way `TriangleIter` does for the rasterizer
+pub(crate) fn sample_texture(texture: &MipTexture, uv: &Vec2) -> Color {
+    // no ray differentials, so always the full resolution level
+    let image = texture.base();
+    let (width, height) = image.dimensions();
+    let (w, h) = ((width - 1) as f32, (height - 1) as f32);
+    let x = (uv.u().rem_euclid(1.) * w) as u32;
+    let y = (h - uv.v().rem_euclid(1.) * h) as u32;
+    let c = image.get_pixel(x.min(width - 1), y.min(height - 1));
+    [c.0[0] as f32 / 255., c.0[1] as f32 / 255., c.0[2] as f32 / 255.]
+}
+
+// interpolated shading attributes of a hit, all in model space
+pub(crate) struct SurfaceHit {
+    pub position: Vector3,
+    pub normal: Vector3,
+    pub geometric_normal: Vector3,
+    pub albedo: Color,
+}
+
+impl SurfaceHit {
+    pub(crate) fn new(res: &TriangleResources, material: &SurfaceMaterial, hit: &RayHit) -> Self {
+        let (positions, normals, uvs) = res.triangle_attributes(hit.triangle);
+        let mut normal = &hit.barycentric * &Matrix::<3, 3>::from_rows(normals.to_vec());
+        normal.norm();
+        let mut geometric_normal = (&positions[1] - &positions[0]).cross(&(&positions[2] - &positions[0]));
+        geometric_normal.norm();
+        let uv = &hit.barycentric * &Matrix::<3, 2>::from_rows(uvs.to_vec());
+        let albedo = match res.texture_of(hit.triangle) {
+            Some(texture) => sample_texture(texture, &uv),
+            None => material.diffuse,
+        };
+        Self {
+            position: hit.position.clone(),
+            normal,
+            geometric_normal,
+            albedo,
+        }
+    }
+}
+
+// what `UnlitShader` and `NormalShader` show for a hit, no lighting and no bounces
+pub(crate) fn debug_color(mode: ShaderMode, surface: &SurfaceHit) -> Option<Color> {
+    match mode {
+        ShaderMode::Lambertian => None,
+        ShaderMode::Unlit => Some(surface.albedo),
+        ShaderMode::Normal => {
+            let n = &surface.normal;
+            Some([n.x(), n.y(), n.z()].map(|v| (v + 1.) * 0.5))
+        }
+    }
+}
+
+// mixed with `HIGHLIGHT_COLOR` like the rasterizer does
+pub(crate) fn highlighted(color: Color) -> Color {
+    lerp(color, HIGHLIGHT_COLOR.map(|c| c as f32 / 255.), 0.5)
+}
+
+pub(crate) fn to_pixel(color: Color, tui: bool) -> [u8; 4] {
+    let c = color.map(|v| (v.clamp(0., 1.) * 255.) as u8);
+    if tui {
+        let luma = 0.299 * color[0] + 0.587 * color[1] + 0.114 * color[2];
+        [c[0], c[1], c[2], luminance_byte(luma)]
+    } else {
+        [c[0], c[1], c[2], 255]
+    }
+}
+
+// maps pixel centers back into model space, like `RasterRunner::pick`
+pub(crate) fn screen_to_model(
+    model_mat: &HomoTransform,
+    camera: &Camera,
+    out: &OutputBuffer,
+) -> Option<HMat> {
+    let mvp = &(model_mat * &camera.to_view_matrix()) * &camera.perspective_projection;
+    (&mvp * &out.to_view_port_matrix()).inverse_matrix()
+}
+
+pub(crate) fn primary_ray(screen_to_model: &HMat, x: f32, y: f32) -> Ray {
+    let unproject = |z: f32| {
+        let s = HVec4::from_v3(Vector3::from_xyz(x, y, z));
+        Pos3::from_matrix(&(&s * screen_to_model))
+    };
+    let near = unproject(-1.);
+    let far = unproject(1.);
+    Ray::new(near.clone(), &far - &near)
+}
+
+impl RayTracer {
+    pub fn new(camera: Camera, light_source: Vector3, light_intensity: f32, tui: bool) -> Self {
+        let mut direction = light_source;
+        direction.norm();
+        Self {
+            model_mat: HomoTransform::identity_matrix(),
+            camera,
+            light: DirectionalLight {
+                direction,
+                intensity: light_intensity,
+            },
+            max_depth: 5,
+            shader_mode: ShaderMode::Lambertian,
+            highlight: None,
+            tui,
+            scene: None,
+        }
+    }
+
+    pub fn set_model(&mut self, m: HomoTransform) {
+        self.model_mat = m;
+    }
+
+    pub fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = depth;
+    }
+
+    pub fn set_highlight(&mut self, id: Option<PickId>) {
+        self.highlight = id;
+    }
+
+    pub fn shader_mode(&self) -> ShaderMode {
+        self.shader_mode
+    }
+
+    pub fn set_shader_mode(&mut self, mode: ShaderMode) {
+        self.shader_mode = mode;
+    }
+
+    pub fn camera_pose(&self) -> ([f32; 3], [f32; 3]) {
+        self.camera.pose()
+    }
+
+    pub fn set_camera_pose(&mut self, position: [f32; 3], forward: [f32; 3]) {
+        self.camera.set_pose(position, forward);
+    }
+
+    // builds the bvh, needs to be called again whenever the resources change
+    pub fn set_scene(&mut self, triangle_res: &TriangleResources) {
+        self.scene = Some(TracerScene::new(triangle_res));
+    }
+
+    // same as `RasterRunner::set_viewport`
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.camera.update_projection(width, height);
+    }
+
+    pub fn render_frame(&self, triangle_res: &TriangleResources, out: &mut OutputBuffer) {
+        let scene = match &self.scene {
+            Some(scene) => scene,
+            None => {
+                error!("ray tracer has no scene, call set_scene first");
+                return;
+            }
+        };
+        let screen_to_model = match screen_to_model(&self.model_mat, &self.camera, out) {
+            Some(m) => m,
+            None => return,
+        };
+
+        // the light lives in world space, the bvh in model space
+        let to_light = &self.light.direction.to_linear_matrix()
+            * &self.model_mat.inverse_matrix().unwrap_or_else(HomoTransform::identity_matrix);
+        let mut to_light = Vector3::from_xyz(-to_light.x(), -to_light.y(), -to_light.z());
+        to_light.norm();
+
+        let (width, height) = out.dimension();
+        for j in 0..height {
+            for i in 0..width {
+                let ray = primary_ray(&screen_to_model, i as f32 + 0.5, j as f32 + 0.5);
+                if let Some(hit) = scene.bvh.closest_hit(&ray) {
+                    let id = PickId::new(0, hit.triangle as u32);
+                    let mut color = self.shade(triangle_res, scene, &to_light, &ray, &hit, 0);
+                    if self.highlight == Some(id) {
+                        color = highlighted(color);
+                    }
+                    out.set_depth(i as usize, j as usize, -hit.t);
+                    out.set_id(i as usize, j as usize, id);
+                    out.put_pixel(i, j, &to_pixel(color, self.tui));
+                }
+            }
+        }
+    }
+
+    // like `RasterRunner::pick`, against the triangles the bvh was built from
+    pub fn pick(&self, triangle_res: &TriangleResources, out: &OutputBuffer, x: u32, y: u32) -> Option<PickResult> {
+        let id = out.get_id(x as usize, y as usize);
+        if id.is_none() {
+            return None;
+        }
+        let screen_to_model = screen_to_model(&self.model_mat, &self.camera, out)?;
+        let attributes = triangle_res.triangle_attributes(id.triangle as usize);
+        resolve_pick(id, attributes, &screen_to_model, &self.model_mat, x, y)
+    }
+
+    fn trace(&self, res: &TriangleResources, scene: &TracerScene, to_light: &Vector3, ray: &Ray, depth: u32) -> Color {
+        match scene.bvh.closest_hit(ray) {
+            Some(hit) => self.shade(res, scene, to_light, ray, &hit, depth),
+            None => [0., 0., 0.],
+        }
+    }
+
+    fn shade(
+        &self,
+        res: &TriangleResources,
+        scene: &TracerScene,
+        to_light: &Vector3,
+        ray: &Ray,
+        hit: &RayHit,
+        depth: u32,
+    ) -> Color {
+        let material = scene.material(res, hit.triangle);
+        let surface = SurfaceHit::new(res, material, hit);
+        if let Some(color) = debug_color(self.shader_mode, &surface) {
+            return color;
+        }
+
+        // shade the side the ray came from
+        let facing = if ray.direction.dot(&surface.geometric_normal) < 0. { 1. } else { -1. };
+        let mut n = surface.normal.clone();
+        n *= facing;
+        let mut ng = surface.geometric_normal.clone();
+        ng *= facing;
+
+        let cos = n.dot(to_light).max(0.);
+        let lit = cos > 0.
+            && !scene
+                .bvh
+                .occluded(&Ray::new(offset(&surface.position, &ng, 1.), to_light.clone()), 0., f32::INFINITY);
+        let light = AMBIENT + if lit { cos * self.light.intensity } else { 0. };
+        let mut color = scale(surface.albedo, light);
+
+        if depth >= self.max_depth {
+            return color;
+        }
+
+        if material.transparency > 0. {
+            // entering when the ray hits the front face
+            let eta = if facing > 0. { 1. / material.ior } else { material.ior };
+            let cos_i = -ray.direction.dot(&n);
+            let reflected = self.trace(
+                res,
+                scene,
+                to_light,
+                &Ray::new(offset(&surface.position, &ng, 1.), reflect(&ray.direction, &n)),
+                depth + 1,
+            );
+            let through = match refract(&ray.direction, &n, eta) {
+                Some(dir) => {
+                    let fresnel = schlick(cos_i, eta);
+                    let refracted = self.trace(
+                        res,
+                        scene,
+                        to_light,
+                        &Ray::new(offset(&surface.position, &ng, -1.), dir),
+                        depth + 1,
+                    );
+                    lerp(mul(refracted, surface.albedo), reflected, fresnel)
+                }
+                None => reflected,
+            };
+            color = lerp(color, through, material.transparency);
+        } else if material.reflectivity > 0. {
+            let reflected = self.trace(
+                res,
+                scene,
+                to_light,
+                &Ray::new(offset(&surface.position, &ng, 1.), reflect(&ray.direction, &n)),
+                depth + 1,
+            );
+            color = lerp(color, reflected, material.reflectivity);
+        }
+        color
+    }
+}
@@ -2,8 +2,13 @@ use crate::department::preview::homo_transformation::HomoTransform;
 use crate::department::preview::matrix::Matrix;
 use crate::department::preview::vector::Vector3;
 use crate::department::view::camera::Camera;
+use crate::pb::debugger::ShaderMode;
 
 pub static LUMINANCE_CHARS: [char; 12] = ['.', ',', '-', '~', ':', ';', '=', '!', '*', '#', '$', '@'];
+// more ramps for the terminal, dark to bright as well
+pub static CLASSIC_RAMP: &str = " .:-=+*#%@";
+pub static DETAILED_RAMP: &str = " .'`^\",:;Il!i><~+_-?][}{1)(|\\/tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$";
+pub static BLOCK_RAMP: &str = " ░▒▓█";
 
 //#[derive(Debug)]
 pub struct LambertianShader {
@@ -62,18 +67,84 @@ impl Shader for LambertianShader {
         }else {
             cos
         };
-        let index = ((LUMINANCE_CHARS.len() - 1) as f32 * intensity).ceil() as usize;
-        let final_char = LUMINANCE_CHARS[index];
-
         let (r, g, b) = (intensity * diffuse[0] as f32, intensity * diffuse[1] as f32, intensity* diffuse[2] as f32);
 
 
         if self.tui {
-            [r as u8 , g as u8, b as u8, final_char as u8]
+            [r as u8 , g as u8, b as u8, luminance_byte(intensity)]
         }else {
             [r as u8 , g as u8, b as u8, diffuse[3]]
         }
 
 
     }
-}
\ No newline at end of file
+}
+
+// the 4th byte of a tui pixel, 1 for dark up to 255 for bright. 0 means nothing was drawn,
+// the presenter picks the glyph from its ramp
+pub fn luminance_byte(intensity: f32) -> u8 {
+    1 + (intensity.clamp(0., 1.) * 254.).round() as u8
+}
+
+// the glyph of a luminance byte on a dark to bright ramp
+pub fn luminance_glyph(ramp: &[char], luminance: u8) -> char {
+    let level = luminance.saturating_sub(1) as f32 / 254.;
+    ramp[((ramp.len() - 1) as f32 * level).round() as usize]
+}
+
+// texture color as is, no lighting
+pub struct UnlitShader {
+    tui: bool,
+}
+
+impl UnlitShader {
+    pub fn new(tui: bool) -> Self {
+        Self { tui }
+    }
+}
+
+impl Shader for UnlitShader {
+    fn shade(&self, _normal: &Vec<Vector3>, diffuse: &[u8; 4], _bar: &Vector3) -> [u8; 4] {
+        if self.tui {
+            let luma = (0.299 * diffuse[0] as f32 + 0.587 * diffuse[1] as f32 + 0.114 * diffuse[2] as f32) / 255.;
+            [diffuse[0], diffuse[1], diffuse[2], luminance_byte(luma)]
+        } else {
+            *diffuse
+        }
+    }
+}
+
+// model space normal mapped from [-1, 1] to [0, 255]
+pub struct NormalShader {
+    tui: bool,
+}
+
+impl NormalShader {
+    pub fn new(tui: bool) -> Self {
+        Self { tui }
+    }
+}
+
+impl Shader for NormalShader {
+    fn shade(&self, normal: &Vec<Vector3>, diffuse: &[u8; 4], bar: &Vector3) -> [u8; 4] {
+        let mut n = bar * &Matrix::<3, 3>::from_rows(normal.clone());
+        n.norm();
+        let to_byte = |v: f32| ((v + 1.) * 0.5 * 255.) as u8;
+        let (r, g, b) = (to_byte(n.x()), to_byte(n.y()), to_byte(n.z()));
+
+        if self.tui {
+            [r, g, b, luminance_byte((n.z() + 1.) * 0.5)]
+        } else {
+            [r, g, b, diffuse[3]]
+        }
+    }
+}
+
+// the shaders that can be switched to at runtime; lambertian needs a camera so it is built by the caller
+pub fn shader_for_mode(mode: ShaderMode, tui: bool) -> Option<Box<dyn Shader>> {
+    match mode {
+        ShaderMode::Lambertian => None,
+        ShaderMode::Unlit => Some(Box::new(UnlitShader::new(tui))),
+        ShaderMode::Normal => Some(Box::new(NormalShader::new(tui))),
+    }
+}
@@ -0,0 +1,32 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageError, ImageFormat, ImageResult, RgbaImage};
+
+// a looping gif, every frame shown for 1 / `fps` seconds
+pub fn save_gif(path: &Path, frames: Vec<RgbaImage>, fps: f32) -> ImageResult<()> {
+    let file = File::create(path).map_err(ImageError::IoError)?;
+    let mut encoder = GifEncoder::new(BufWriter::new(file));
+    encoder.set_repeat(Repeat::Infinite)?;
+    // gif counts in hundredths of a second, the encoder rounds for us
+    let delay = Delay::from_saturating_duration(Duration::from_secs_f32(1. / fps));
+    encoder.encode_frames(frames.into_iter().map(|f| Frame::from_parts(f, 0, 0, delay)))
+}
+
+// `dognut.png` turns into dognut_0001.png, dognut_0002.png, ... returns what was written
+pub fn save_png_sequence(path: &Path, frames: &[RgbaImage]) -> ImageResult<Vec<PathBuf>> {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    let digits = frames.len().to_string().len().max(4);
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let p = path.with_file_name(format!("{}_{:0digits$}.png", stem, i + 1, digits = digits));
+            frame.save_with_format(&p, ImageFormat::Png)?;
+            Ok(p)
+        })
+        .collect()
+}
@@ -1,4 +1,5 @@
 use crate::department::preview::matrix::Matrix;
+use crate::department::preview::quaternion::Quaternion;
 use crate::department::preview::vector::Vector3;
 
 pub type HomoTransform = Matrix<4,4>;
@@ -18,6 +19,8 @@ impl HomoTransform {
         )
     }
 
+    // multiplied from the right onto a row vector this turns by -theta, which is
+    // `Quaternion::from_axis_angle(n, -theta).to_homo_transform()`
     pub fn rotation_matrix(n: &Vector3, theta: f32) -> Self{
         let mut vt = n.clone();
         let v = vt.norm();
@@ -47,18 +50,82 @@ impl HomoTransform {
 
 impl Transform {
     pub fn rotation_mat(n: &Vector3, theta: f32) -> Self{
-        let mut vt = n.clone();
-        let v = vt.norm();
-        let (sin_t, cos_t) = theta.sin_cos();
+        Self::rotation_matrix(n, theta)
+    }
+}
 
-        let (x, y, z) = (v.x(), v.y(), v.z());
-        let cminus1 = 1. - cos_t;
+// translation, rotation and scale of an affine transform, applied in the order
+// scale, rotate, translate to a row vector
+#[derive(Debug, Clone)]
+pub struct Trs {
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+}
+
+impl Default for Trs {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::from_xyz(0., 0., 0.),
+            rotation: Quaternion::identity(),
+            scale: Vector3::from_xyz(1., 1., 1.),
+        }
+    }
+}
+
+impl Trs {
+    pub fn to_homo_transform(&self) -> HomoTransform {
+        let s = HomoTransform::scale((self.scale.x(), self.scale.y(), self.scale.z()));
+        let t = HomoTransform::translation((self.translation.x(), self.translation.y(), self.translation.z()));
+        &(&s * &self.rotation.to_homo_transform()) * &t
+    }
+
+    // lerp for translation and scale, slerp for the rotation
+    pub fn interpolate(&self, other: &Trs, t: f32) -> Trs {
+        let lerp = |a: &Vector3, b: &Vector3| {
+            Vector3::from_xyz(
+                a.x() + (b.x() - a.x()) * t,
+                a.y() + (b.y() - a.y()) * t,
+                a.z() + (b.z() - a.z()) * t,
+            )
+        };
+        Trs {
+            translation: lerp(&self.translation, &other.translation),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: lerp(&self.scale, &other.scale),
+        }
+    }
+}
+
+impl HomoTransform {
+    pub fn from_trs(trs: &Trs) -> Self {
+        trs.to_homo_transform()
+    }
+
+    // shear and projection are dropped, a mirrored transform comes back with a negative x scale
+    pub fn decompose(&self) -> Trs {
+        let translation = Vector3::from_xyz(self.index(3, 0), self.index(3, 1), self.index(3, 2));
+
+        let mut rows = [0, 1, 2].map(|i| Vector3::from_xyz(self.index(i, 0), self.index(i, 1), self.index(i, 2)));
+        let mut scale = rows.clone().map(|r| r.magnitude());
+        if rows[0].cross(&rows[1]).dot(&rows[2]) < 0. {
+            scale[0] = -scale[0];
+        }
+
+        let mut rotation = HomoTransform::identity_matrix();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if scale[i] != 0. {
+                *row *= 1. / scale[i];
+            }
+            for j in 0..3 {
+                rotation.set(i, j, row.index(0, j));
+            }
+        }
 
-        Transform::from_vec(vec![
-            x.powi(2) * cminus1 + cos_t, x * y * cminus1 - z * sin_t, x * z * cminus1 + y * sin_t, 0.,
-            x * y * cminus1 + z * sin_t, y.powi(2) * cminus1 + cos_t, y * z * cminus1 - x * sin_t, 0.,
-            x * z * cminus1 - y * sin_t, y * z * cminus1 + x*sin_t, z.powi(2) * cminus1  + cos_t, 0.,
-            0., 0., 0., 1.
-        ])
+        Trs {
+            translation,
+            rotation: Quaternion::from_homo_transform(&rotation),
+            scale: Vector3::from_xyz(scale[0], scale[1], scale[2]),
+        }
     }
 }
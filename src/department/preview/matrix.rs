@@ -1,16 +1,12 @@
 use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Sub};
+use std::simd::f32x4;
 
 use dognut_macros::TriangularInverse;
 
+// row major and inline, so vectors and 4x4 transforms never touch the heap
 #[derive(Debug, Clone, TriangularInverse)]
 pub struct Matrix<const M: usize, const N: usize> {
-    // 0 1 2  m = 2, n = 3
-// 0 1 2
-//
-//
-    pub m: usize,
-    pub n: usize,
-    pub elements: Vec<f32>,
+    data: [[f32; N]; M],
 }
 
 pub type HMat = Matrix<4,4>;
@@ -31,25 +27,30 @@ impl<'a, const M: usize, const N: usize> MatrixIter<'a, M, N> {
     }
 }
 
+// every row of the product is a weighted sum of `b`'s rows, which is four lanes wide
+// whenever `b` has four columns: all of HVec4 * HMat and HMat * HMat.
+#[inline]
+fn mul_matrix<const M: usize, const N: usize, const K: usize>(a: &Matrix<M, N>, b: &Matrix<N, K>) -> Matrix<M, K> {
+    if K != 4 {
+        return a.mul_naive(b);
+    }
+
+    let mut ret = Matrix::<M, K>::new();
+    for i in 0..M {
+        let mut acc = f32x4::splat(0.);
+        for k in 0..N {
+            acc += f32x4::splat(a.data[i][k]) * f32x4::from_slice(&b.data[k]);
+        }
+        acc.copy_to_slice(&mut ret.data[i]);
+    }
+    ret
+}
+
 impl<const M: usize, const N: usize, const K: usize> Mul<Matrix<N, K>> for Matrix<M, N> {
     type Output = Matrix<M, K>;
 
     fn mul(self, other: Matrix<N, K>) -> Matrix<M, K> {
-        let _m = self.m();
-        let _n = other.n();
-        let _common_len = self.n();
-        let mut _ret = Matrix::<M, K>::new();
-        for i in 0.._m {
-            for j in 0.._n {
-                let mut _val = 0.;
-                for k in 0.._common_len {
-                    _val += self.index(i, k) * other.index(k, j);
-                }
-
-                _ret.set(i, j, _val);
-            }
-        }
-        _ret
+        mul_matrix(&self, &other)
     }
 }
 
@@ -58,29 +59,16 @@ impl<const M: usize, const N: usize, const K: usize> Mul<&Matrix<N, K>> for &Mat
     type Output = Matrix<M, K>;
 
     fn mul(self, other: &Matrix<N, K>) -> Self::Output {
-        let _m = self.m();
-        let _n = other.n();
-        let _common_len = self.n();
-        let mut _ret = Matrix::<M, K>::new();
-        for i in 0.._m {
-            for j in 0.._n {
-                let mut _val = 0.;
-                for k in 0.._common_len {
-                    _val += self.index(i, k) * other.index(k, j);
-                }
-
-                _ret.set(i, j, _val);
-            }
-        }
-        _ret
+        mul_matrix(self, other)
     }
 }
 
 impl<const M: usize, const N: usize> Mul<f32> for Matrix<M, N> {
     type Output = Matrix<M,N>;
 
-    fn mul(self, rhs: f32) -> Self::Output {
-        Self::Output::from_vec(self.elements.iter().map(|f| f*rhs).collect())
+    fn mul(mut self, rhs: f32) -> Self::Output {
+        self.as_mut_slice().iter_mut().for_each(|f| *f *= rhs);
+        self
     }
 }
 
@@ -88,26 +76,28 @@ impl<const M: usize, const N: usize> Div<f32> for &Matrix<M, N> {
     type Output = Matrix<M,N>;
 
     fn div(self, rhs: f32) -> Self::Output {
-        Self::Output::from_vec(self.elements.iter().map(|f| f/rhs).collect())
+        let mut ret = self.clone();
+        ret.as_mut_slice().iter_mut().for_each(|f| *f /= rhs);
+        ret
     }
 }
 
 impl<const M: usize, const N: usize> MulAssign<f32> for Matrix<M, N> {
     fn mul_assign(&mut self, rhs: f32) {
-        self.elements.iter_mut().for_each(|f| *f *= rhs);
+        self.as_mut_slice().iter_mut().for_each(|f| *f *= rhs);
     }
 }
 
 
 impl<const M: usize, const N: usize> AddAssign<f32> for Matrix<M, N> {
     fn add_assign(&mut self, rhs: f32) {
-        self.elements.iter_mut().for_each(|f| *f += rhs);
+        self.as_mut_slice().iter_mut().for_each(|f| *f += rhs);
     }
 }
 
 impl<const M: usize, const N: usize> AddAssign<f32> for &mut Matrix<M, N> {
     fn add_assign(&mut self, rhs: f32) {
-        self.elements.iter_mut().for_each(|f| *f += rhs);
+        self.as_mut_slice().iter_mut().for_each(|f| *f += rhs);
     }
 }
 
@@ -115,13 +105,7 @@ impl<const M: usize, const N: usize> Add for Matrix<M, N> {
     type Output = Matrix<M, N>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let mut ret = Matrix::<M, N>::new();
-        let mut i = 0;
-        ret.elements.iter_mut().for_each(|f| {
-            *f = self.elements[i] + rhs.elements[i];
-            i += 1;
-        });
-        ret
+        &self + &rhs
     }
 }
 
@@ -129,12 +113,11 @@ impl<const M: usize, const N: usize> Add for &Matrix<M, N> {
     type Output = Matrix<M, N>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let mut ret = Matrix::<M, N>::new();
-        let mut i = 0;
-        ret.elements.iter_mut().for_each(|f| {
-            *f = self.elements[i] + rhs.elements[i];
-            i += 1;
-        });
+        let mut ret = self.clone();
+        ret.as_mut_slice()
+            .iter_mut()
+            .zip(rhs.as_slice())
+            .for_each(|(f, r)| *f += r);
         ret
     }
 }
@@ -143,13 +126,7 @@ impl<const M: usize, const N: usize> Sub for Matrix<M, N> {
     type Output = Matrix<M, N>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let mut ret = Matrix::<M, N>::new();
-        let mut i = 0;
-        ret.elements.iter_mut().for_each(|f| {
-            *f = self.elements[i] - rhs.elements[i];
-            i += 1;
-        });
-        ret
+        &self - &rhs
     }
 }
 
@@ -157,12 +134,11 @@ impl<const M: usize, const N: usize> Sub for &Matrix<M, N> {
     type Output = Matrix<M, N>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let mut ret = Matrix::<M, N>::new();
-        let mut i = 0;
-        ret.elements.iter_mut().for_each(|f| {
-            *f = self.elements[i] - rhs.elements[i];
-            i += 1;
-        });
+        let mut ret = self.clone();
+        ret.as_mut_slice()
+            .iter_mut()
+            .zip(rhs.as_slice())
+            .for_each(|(f, r)| *f -= r);
         ret
     }
 }
@@ -175,35 +151,49 @@ impl<const M: usize, const N: usize> Into<[[f32; M]; N]> for Matrix<M, N> {
 
 impl<const M: usize, const N: usize> Matrix<M, N> {
     pub fn new() -> Self {
-
-
-        let mut _elements: Vec<f32> = Vec::with_capacity((M * N) as usize);
-        _elements.resize((M * N) as usize, 0.0);
         Self {
-            m: M,
-            n: N,
-            elements: _elements,
+            data: [[0.; N]; M],
         }
     }
 
-    pub fn from_rows(mut rows:Vec<Matrix<1, N>>) -> Matrix<M, N> {
-        let mut elem = Vec::<f32>::with_capacity(M*N);
-        for i in 0..rows.len() {
-           elem.append( & mut rows[i].elements)
-        }
-        Self {
-            m: M,
-            n: N,
-            elements: elem,
+    pub fn from_array(data: [[f32; N]; M]) -> Self {
+        Self { data }
+    }
+
+    pub fn from_rows(rows: Vec<Matrix<1, N>>) -> Matrix<M, N> {
+        let mut ret = Self::new();
+        for (i, r) in rows.iter().take(M).enumerate() {
+            ret.data[i] = r.data[0];
         }
+        ret
     }
 
+    // row major, exactly M * N elements
     pub fn from_vec(elements: Vec<f32>) -> Self {
-        Self {
-            m:M,
-            n:N,
-            elements,
-        }
+        Self::from_slice(&elements)
+    }
+
+    pub fn from_slice(elements: &[f32]) -> Self {
+        assert_eq!(elements.len(), M * N, "a {}x{} matrix needs {} elements", M, N, M * N);
+        let mut ret = Self::new();
+        ret.as_mut_slice()
+            .iter_mut()
+            .zip(elements)
+            .for_each(|(f, e)| *f = *e);
+        ret
+    }
+
+    // all elements row by row
+    pub fn as_slice(&self) -> &[f32] {
+        self.data.as_flattened()
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [f32] {
+        self.data.as_flattened_mut()
+    }
+
+    pub fn rows(&self) -> &[[f32; N]; M] {
+        &self.data
     }
 
     pub fn cut<const SUBM: usize, const SUBN: usize>(&self, x: usize, y: usize) -> Matrix<SUBM, SUBN> {
@@ -239,31 +229,22 @@ impl<const M: usize, const N: usize> Matrix<M, N> {
         println!("{}", _print);
     }
 
-    pub fn m(&self) -> usize {
-        self.m
+    pub const fn m(&self) -> usize {
+        M
     }
 
-    pub fn n(&self) -> usize {
-        self.n
+    pub const fn n(&self) -> usize {
+        N
     }
 
     pub fn t(&self) -> Matrix<N, M> {
-        let mut transposed_elems = self.elements.clone();
-        let _n = self.m;
-        let _m = self.n;
-
-        for i in 0.._m {
-            for j in 0.._n {
-                transposed_elems[i * _n + j] = self.elements[j * self.n + i];
+        let mut ret = Matrix::<N, M>::new();
+        for i in 0..M {
+            for j in 0..N {
+                ret.data[j][i] = self.data[i][j];
             }
         }
-
-
-        Matrix::<N, M> {
-            m: _m,
-            n: _n,
-            elements: transposed_elems,
-        }
+        ret
     }
 
     pub fn transform_t(&mut self) {}
@@ -273,34 +254,42 @@ impl<const M: usize, const N: usize> Matrix<M, N> {
     }
 
     pub fn set(&mut self, x: usize, y: usize, val: f32) {
-        self.elements[x * self.n + y] = val;
+        self.data[x][y] = val;
     }
 
     pub fn index(&self, x: usize, y: usize) -> f32 {
-        self.elements[x * self.n + y]
+        self.data[x][y]
     }
 
     pub fn result(&self) -> f32 {
-        self.elements[0]
+        self.data[0][0]
     }
 
     pub fn mul_num(&mut self, num: f32) -> &Self {
-        for i in self.elements.iter_mut() {
+        for i in self.as_mut_slice() {
             *i *= num;
         }
         self
     }
 
-    pub fn add_linear(&self) -> Self {
-        let mut ret = Matrix::new();
-
-        for i in 0..self.m() {
-            for j in 0..self.n() {
-                ret.set(i, j, self.index(i, j));
+    // plain triple loop, the reference the simd product is checked and benchmarked against
+    pub fn mul_naive<const K: usize>(&self, other: &Matrix<N, K>) -> Matrix<M, K> {
+        let mut ret = Matrix::<M, K>::new();
+        for i in 0..M {
+            for j in 0..K {
+                let mut val = 0.;
+                for k in 0..N {
+                    val += self.data[i][k] * other.data[k][j];
+                }
+                ret.data[i][j] = val;
             }
         }
+        ret
+    }
 
-        ret.set(ret.m() - 1, ret.n() - 1, 1.);
+    pub fn add_linear(&self) -> Self {
+        let mut ret = self.clone();
+        ret.set(M - 1, N - 1, 1.);
         ret
     }
 
@@ -314,12 +303,9 @@ impl<const M: usize, const N: usize> Matrix<M, N> {
     }
 
     pub fn to_slice(&self) -> [[f32; M]; N] {
-        let _iter = self.iter();
         let mut ret = [[0.; M]; N];
-        for i in 0..N {
-            let start = i * M;
-            let end = i * M + M;
-            ret[i].copy_from_slice(&self.elements[start..end]);
+        for (i, chunk) in self.as_slice().chunks_exact(M).take(N).enumerate() {
+            ret[i].copy_from_slice(chunk);
         }
 
         ret
@@ -413,3 +399,38 @@ impl<'a, const M: usize, const N: usize> Iterator for MatrixIter<'a, M, N> {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled<const M: usize, const N: usize>(seed: f32) -> Matrix<M, N> {
+        let values: Vec<f32> = (0..M * N).map(|i| (i as f32 * 0.37 + seed).sin() * 3.).collect();
+        Matrix::from_vec(values)
+    }
+
+    fn assert_close<const M: usize, const N: usize>(a: &Matrix<M, N>, b: &Matrix<M, N>) {
+        for (x, y) in a.as_slice().iter().zip(b.as_slice()) {
+            assert!((x - y).abs() <= 1e-5 * x.abs().max(1.), "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn simd_product_matches_naive() {
+        let (a, b) = (filled::<4, 4>(0.1), filled::<4, 4>(1.3));
+        assert_close(&mul_matrix(&a, &b), &a.mul_naive(&b));
+
+        let v = filled::<1, 4>(2.7);
+        assert_close(&mul_matrix(&v, &b), &v.mul_naive(&b));
+
+        let c = filled::<3, 3>(0.5);
+        let d = filled::<3, 2>(4.1);
+        assert_close(&mul_matrix(&c, &d), &c.mul_naive(&d));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_vec_rejects_wrong_length() {
+        Matrix::<2, 3>::from_vec(vec![1., 2., 3., 4., 5.]);
+    }
+}
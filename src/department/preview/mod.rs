@@ -3,4 +3,6 @@ pub mod vector;
 pub mod matrix;
 pub mod output_buffer;
 pub mod homo_transformation;
+pub mod quaternion;
 pub mod matrix_builder;
+pub mod frame_export;
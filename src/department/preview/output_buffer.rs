@@ -1,4 +1,4 @@
-use std::io::Stdout;
+use std::io::{Stdout, Write};
 use std::path::Path;
 
 use crossterm::cursor::MoveTo;
@@ -11,6 +11,8 @@ use log::error;
 
 use super::matrix::Matrix;
 use super::position::Pos3;
+use crate::department::pipeline::shader::{luminance_byte, luminance_glyph, LUMINANCE_CHARS};
+use crate::department::view::picking::{PickId, NO_PICK};
 
 pub type Display = image::ImageBuffer<image::Rgba<u8>, Vec<u8>>;
 
@@ -19,6 +21,7 @@ pub struct OutputBuffer<'a> {
     height: u32,
     pub display: Vec<u8>,
     pub depth: Vec<f32>,
+    pub ids: Vec<PickId>,
     tui: bool,
     pub stdout: Option<&'a Stdout>,
 }
@@ -39,11 +42,16 @@ impl<'a> OutputBuffer<'a> {
             height,
             display: _display,
             depth: _depth,
+            ids: vec![NO_PICK; pixels_num],
             tui,
             stdout: None,
         }
     }
 
+    pub fn dimension(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
     pub fn get_depth(&self, x: usize, y: usize) -> f32 {
         self.depth[y * self.width as usize + x]
     }
@@ -52,6 +60,17 @@ impl<'a> OutputBuffer<'a> {
         self.depth[y * self.width as usize + x] = val;
     }
 
+    pub fn get_id(&self, x: usize, y: usize) -> PickId {
+        if x >= self.width as usize || y >= self.height as usize {
+            return NO_PICK;
+        }
+        self.ids[y * self.width as usize + x]
+    }
+
+    pub fn set_id(&mut self, x: usize, y: usize, id: PickId) {
+        self.ids[y * self.width as usize + x] = id;
+    }
+
     pub fn put_pixel(&mut self, x: u32, y: u32, rgb: &[u8]) {
         let start = (y * self.width + x) as usize * RGB_STEP;
         let buf = &mut self.display[start..(start + RGB_STEP)];
@@ -107,11 +126,24 @@ impl<'a> OutputBuffer<'a> {
     }
 
     pub fn save_to_image(&self, path: &str) {
+        if let Err(e) = self.to_image().save_with_format(Path::new(path), ImageFormat::Png) {
+            error!("could not save image {}", e);
+        }
+    }
+
+    pub fn to_image(&self) -> RgbaImage {
         let mut img = RgbaImage::new(self.width, self.height);
-        image::imageops::resize(&img, 1920, 1080, image::imageops::FilterType::CatmullRom);
         img.copy_from_slice(self.display.as_slice());
-        if let Err(e) = img.save_with_format(Path::new(path), ImageFormat::Png) {
-            error!("could not save image {}", e);
+        img
+    }
+
+    // a gpu readback has alpha in the 4th byte, this turns it into the luminance byte the cpu
+    // shaders write. transparent pixels, the background among them, stay empty
+    pub fn fill_luminance(&mut self) {
+        for [r, g, b, a] in self.display.array_chunks_mut() {
+            if *a != 0 {
+                *a = luminance_byte((0.299 * *r as f32 + 0.587 * *g as f32 + 0.114 * *b as f32) / 255.);
+            }
         }
     }
 
@@ -120,6 +152,10 @@ impl<'a> OutputBuffer<'a> {
             return;
         }
         let mut stdout = self.stdout.unwrap();
+        self.queue_to(&mut stdout);
+    }
+
+    pub fn queue_to<W: Write>(&self, stdout: &mut W) {
         let (mut x, mut y) = (0, 0);
         for (n, [r, g, b, c]) in self.display.iter().array_chunks().enumerate() {
             if *c as u8 == 0 {
@@ -131,7 +167,7 @@ impl<'a> OutputBuffer<'a> {
             queue!(stdout, MoveTo(x as u16, y as u16)).unwrap();
             queue!(
                 stdout,
-                style::PrintStyledContent(('*' as char).with(Color::Rgb {
+                style::PrintStyledContent(luminance_glyph(&LUMINANCE_CHARS, *c).with(Color::Rgb {
                     r: *r,
                     g: *g,
                     b: *b
@@ -25,13 +25,13 @@ pub type Pos3 = Matrix<1, 3>;
 
 impl Default for Pos3 {
     fn default() -> Self {
-        Pos3::from_vec(vec![0., 0., 0.])
+        Pos3::new()
     }
 }
 
 impl Pos3 {
     pub fn new_pos(x: f32, y: f32, z: f32) -> Self {
-        Pos3::from_vec(vec![x, y, z])
+        Pos3::from_xyz(x, y, z)
     }
 
     pub fn from_matrix(matrix: &Matrix<1, 4>) -> Self {
@@ -46,7 +46,6 @@ impl Pos3 {
     }
 
     pub fn to_homogeneous(&self) -> HVec4 {
-        let _elements = vec![self.x(), self.y(), self.z(), 1.];
-        Matrix::from_vec(_elements)
+        Matrix::from_array([[self.x(), self.y(), self.z(), 1.]])
     }
 }
@@ -0,0 +1,175 @@
+use std::ops::Mul;
+
+use crate::department::preview::homo_transformation::HomoTransform;
+use crate::department::preview::vector::Vector3;
+
+// unit quaternion for rotations, `a * b` applies `b` first and then `a`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, r: Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w * r.w - self.x * r.x - self.y * r.y - self.z * r.z,
+            x: self.w * r.x + self.x * r.w + self.y * r.z - self.z * r.y,
+            y: self.w * r.y - self.x * r.z + self.y * r.w + self.z * r.x,
+            z: self.w * r.z + self.x * r.y - self.y * r.x + self.z * r.w,
+        }
+    }
+}
+
+impl Mul for &Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, r: &Quaternion) -> Quaternion {
+        *self * *r
+    }
+}
+
+impl Quaternion {
+    pub fn new(w: f32, x: f32, y: f32, z: f32) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(1., 0., 0., 0.)
+    }
+
+    // right handed, counter clockwise when looking down the axis
+    pub fn from_axis_angle(axis: &Vector3, theta: f32) -> Self {
+        let mut n = axis.clone();
+        n.norm();
+        let (sin, cos) = (theta / 2.).sin_cos();
+        Self::new(cos, n.x() * sin, n.y() * sin, n.z() * sin)
+    }
+
+    // radians, applied around x first, then y, then z
+    pub fn from_euler(x: f32, y: f32, z: f32) -> Self {
+        let qx = Self::from_axis_angle(&Vector3::from_xyz(1., 0., 0.), x);
+        let qy = Self::from_axis_angle(&Vector3::from_xyz(0., 1., 0.), y);
+        let qz = Self::from_axis_angle(&Vector3::from_xyz(0., 0., 1.), z);
+        qz * qy * qx
+    }
+
+    // inverse of `from_euler`, y is kept in [-pi/2, pi/2]
+    pub fn to_euler(&self) -> (f32, f32, f32) {
+        let Self { w, x, y, z } = *self;
+        let ex = (2. * (w * x + y * z)).atan2(1. - 2. * (x * x + y * y));
+        let ey = (2. * (w * y - z * x)).clamp(-1., 1.).asin();
+        let ez = (2. * (w * z + x * y)).atan2(1. - 2. * (y * y + z * z));
+        (ex, ey, ez)
+    }
+
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn norm(&mut self) -> &Self {
+        let mag = self.magnitude();
+        self.w /= mag;
+        self.x /= mag;
+        self.y /= mag;
+        self.z /= mag;
+        self
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn inverse(&self) -> Self {
+        let d = self.dot(self);
+        let c = self.conjugate();
+        Self::new(c.w / d, c.x / d, c.y / d, c.z / d)
+    }
+
+    pub fn rotate(&self, v: &Vector3) -> Vector3 {
+        let u = Vector3::from_xyz(self.x, self.y, self.z);
+        let uv = u.cross(v);
+        let uuv = u.cross(&uv);
+        Vector3::from_xyz(
+            v.x() + 2. * (self.w * uv.x() + uuv.x()),
+            v.y() + 2. * (self.w * uv.y() + uuv.y()),
+            v.z() + 2. * (self.w * uv.z() + uuv.z()),
+        )
+    }
+
+    // constant angular speed along the shorter arc
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        let mut cos = self.dot(other);
+        let mut to = *other;
+        if cos < 0. {
+            cos = -cos;
+            to = Self::new(-to.w, -to.x, -to.y, -to.z);
+        }
+
+        let (a, b) = if cos > 0.9995 {
+            // nearly parallel, sin(theta) would blow up
+            (1. - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+
+        let mut q = Self::new(
+            a * self.w + b * to.w,
+            a * self.x + b * to.x,
+            a * self.y + b * to.y,
+            a * self.z + b * to.z,
+        );
+        q.norm();
+        q
+    }
+
+    // for row vectors like the rest of the pipeline: `v * m` rotates `v` as `rotate` does
+    pub fn to_homo_transform(&self) -> HomoTransform {
+        let Self { w, x, y, z } = *self;
+        HomoTransform::from_array([
+            [1. - 2. * (y * y + z * z), 2. * (x * y + w * z), 2. * (x * z - w * y), 0.],
+            [2. * (x * y - w * z), 1. - 2. * (x * x + z * z), 2. * (y * z + w * x), 0.],
+            [2. * (x * z + w * y), 2. * (y * z - w * x), 1. - 2. * (x * x + y * y), 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    // the upper 3x3 of `m` has to be a pure rotation, see `HomoTransform::decompose` otherwise
+    pub fn from_homo_transform(m: &HomoTransform) -> Self {
+        // r(i, j) is the column vector convention element, so transposed to `m`
+        let r = |i: usize, j: usize| m.index(j, i);
+        let trace = r(0, 0) + r(1, 1) + r(2, 2);
+
+        let mut q = if trace > 0. {
+            let s = (trace + 1.).sqrt() * 2.;
+            Self::new(0.25 * s, (r(2, 1) - r(1, 2)) / s, (r(0, 2) - r(2, 0)) / s, (r(1, 0) - r(0, 1)) / s)
+        } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+            let s = (1. + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.;
+            Self::new((r(2, 1) - r(1, 2)) / s, 0.25 * s, (r(0, 1) + r(1, 0)) / s, (r(0, 2) + r(2, 0)) / s)
+        } else if r(1, 1) > r(2, 2) {
+            let s = (1. + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.;
+            Self::new((r(0, 2) - r(2, 0)) / s, (r(0, 1) + r(1, 0)) / s, 0.25 * s, (r(1, 2) + r(2, 1)) / s)
+        } else {
+            let s = (1. + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.;
+            Self::new((r(1, 0) - r(0, 1)) / s, (r(0, 2) + r(2, 0)) / s, (r(1, 2) + r(2, 1)) / s, 0.25 * s)
+        };
+        q.norm();
+        q
+    }
+}
@@ -10,29 +10,21 @@ pub type HVec4 = Matrix<1,4>;
 
 impl Default for Vec2 {
     fn default() -> Self {
-        Vec2{
-            m:1,
-            n:2,
-            elements: vec![0., 0.]
-        }
+        Vec2::new()
     }
 }
 
 impl Vec2 {
     pub fn from_xy(x: f32, y:f32) -> Self {
-        Vec2{
-            m:1,
-            n:2,
-            elements: vec![x,y]
-        }
+        Vec2::from_array([[x, y]])
     }
 
     pub fn u(&self) -> f32 {
-        self.elements[0]
+        self.index(0, 0)
     }
 
     pub fn v(&self) -> f32 {
-        self.elements[1]
+        self.index(0, 1)
     }
 }
 
@@ -40,16 +32,14 @@ pub type Vector3 = Matrix<1,3>;
 
 impl AddAssign for Vector3 {
     fn add_assign(&mut self, rhs: Self) {
-        for i  in 0..3 {
-            self.elements[i] += rhs.elements[i];
-        }
+        *self += &rhs;
     }
 }
 
 impl AddAssign<&Vector3> for Vector3 {
     fn add_assign(&mut self, rhs: &Self) {
         for i  in 0..3 {
-            self.elements[i] += rhs.elements[i];
+            self.set(0, i, self.index(0, i) + rhs.index(0, i));
         }
     }
 }
@@ -57,38 +47,32 @@ impl AddAssign<&Vector3> for Vector3 {
 impl SubAssign<&Vector3> for Vector3 {
     fn sub_assign(&mut self, rhs: &Vector3) {
         for i in 0..3 {
-            self.elements[i] -= rhs.elements[i];
+            self.set(0, i, self.index(0, i) - rhs.index(0, i));
         }
     }
 }
 
 impl Vector3 {
     pub fn from_xyz(x: f32, y: f32, z: f32) -> Self {
-        Vector3::from_vec(vec![x,y,z])
+        Vector3::from_array([[x, y, z]])
     }
 
     pub fn x(&self) -> f32 {
-        self.elements[0]
+        self.index(0, 0)
     }
     pub fn y(&self) -> f32 {
-        self.elements[1]
+        self.index(0, 1)
     }
     pub fn z(&self) -> f32 {
-        self.elements[2]
+        self.index(0, 2)
     }
 
     pub fn to_linear_matrix(&self) -> Matrix::<1,4> {
-        let  mut ele = self.elements.clone();
-        ele.push(0.);
-        Matrix::<1,4>::from_vec(ele)
+        Matrix::<1,4>::from_array([[self.x(), self.y(), self.z(), 0.]])
     }
 
     pub fn dot(&self, other: &Self) -> f32 {
-        let mut res = 0f32;
-        for i in 0..self.n {
-            res += self.elements[i] * other.elements[i];
-        }
-        res
+        self.x() * other.x() + self.y() * other.y() + self.z() * other.z()
     }
 
     pub fn cross(&self, other: &Self) -> Self {
@@ -103,9 +87,7 @@ impl Vector3 {
 
     pub fn norm(&mut self) -> &Self {
         let mag = self.magnitude();
-        self.elements[0] = self.elements[0] / mag;
-        self.elements[1] = self.elements[1] / mag;
-        self.elements[2] = self.elements[2] / mag;
+        *self *= 1. / mag;
         self
     }
 
@@ -129,36 +111,29 @@ impl Vector3 {
 
 impl HVec4 {
     pub fn from_v3(v: Vector3) -> Self {
-        let mut ele = v.elements.clone();
-        ele.push(1.);
-        Self {
-            m:1,
-            n:4,
-            elements: ele,
-        }
+        Self::from_array([[v.x(), v.y(), v.z(), 1.]])
     }
 
 
 
     pub fn persp_divide(&mut self){
-        for i in 0..self.n {
-            self.elements[i] /= self.elements[3];
-        }
+        let w = self.w();
+        *self *= 1. / w;
     }
 
     pub fn x(&self) -> f32{
-        self.elements[0]
+        self.index(0, 0)
     }
 
     pub fn y(&self) -> f32{
-        self.elements[1]
+        self.index(0, 1)
     }
 
     pub fn z(&self) -> f32{
-        self.elements[2]
+        self.index(0, 2)
     }
 
     pub fn w(&self) -> f32{
-        self.elements[3]
+        self.index(0, 3)
     }
 }
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver};
+use log::warn;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::department::model::mesh_cache::mtl_libraries;
+use crate::department::model::obj_sequence::sequence_frames;
+use crate::department::model::object_loader::ModelFormat;
+use crate::wgpu::SHADER_DIR;
+
+// editors save in bursts of truncate, write and rename, reload once the files went quiet
+const SETTLE_TIME: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Changes {
+    // the model file, its mtl libraries, textures or sequence frames
+    pub model: bool,
+    pub shaders: bool,
+}
+
+// watches the directories of the model and everything it pulls in, plus the shader
+// directory. directories rather than files, so a save by rename is seen as well
+pub struct AssetWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    model_path: PathBuf,
+    // canonical directory joined with the file name, which is what events carry
+    model_files: HashSet<PathBuf>,
+    shader_dir: Option<PathBuf>,
+    watched_dirs: HashSet<PathBuf>,
+    pending: Changes,
+    last_event: Option<Instant>,
+}
+
+impl AssetWatcher {
+    pub fn new(model_path: &str) -> notify::Result<Self> {
+        let (tx, events) = unbounded();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+        let mut asset_watcher = Self {
+            watcher,
+            events,
+            model_path: PathBuf::from(model_path),
+            model_files: HashSet::new(),
+            shader_dir: fs::canonicalize(SHADER_DIR).ok(),
+            watched_dirs: HashSet::new(),
+            pending: Changes::default(),
+            last_event: None,
+        };
+        match asset_watcher.shader_dir.clone() {
+            Some(dir) => asset_watcher.watch_dir(&dir),
+            None => warn!("{} not found, shaders won't be reloaded", SHADER_DIR),
+        }
+        asset_watcher.rescan();
+        Ok(asset_watcher)
+    }
+
+    // an edited obj or mtl may name other files, call after every model reload
+    pub fn rescan(&mut self) {
+        self.model_files = model_files(&self.model_path)
+            .iter()
+            .filter_map(|f| canonical(f))
+            .collect();
+        let dirs: Vec<PathBuf> = self
+            .model_files
+            .iter()
+            .filter_map(|f| f.parent().map(Path::to_path_buf))
+            .collect();
+        for dir in dirs {
+            self.watch_dir(&dir);
+        }
+    }
+
+    // what changed, once nothing has been written for `SETTLE_TIME`. empty until then
+    pub fn poll(&mut self) -> Changes {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) => self.record(&event),
+                Err(e) => warn!("file watcher: {}", e),
+            }
+        }
+        match self.last_event {
+            Some(t) if t.elapsed() >= SETTLE_TIME => {
+                self.last_event = None;
+                std::mem::take(&mut self.pending)
+            }
+            _ => Changes::default(),
+        }
+    }
+
+    fn record(&mut self, event: &Event) {
+        // reading the files back on reload shows up as access, that must not trigger another one
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            return;
+        }
+        for path in &event.paths {
+            if self.model_files.contains(path) {
+                self.pending.model = true;
+            } else if path.parent() == self.shader_dir.as_deref()
+                && path.extension().is_some_and(|e| e == "wgsl")
+            {
+                self.pending.shaders = true;
+            } else {
+                continue;
+            }
+            self.last_event = Some(Instant::now());
+        }
+    }
+
+    fn watch_dir(&mut self, dir: &Path) {
+        if self.watched_dirs.contains(dir) {
+            return;
+        }
+        match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                self.watched_dirs.insert(dir.to_path_buf());
+            }
+            Err(e) => warn!("can't watch {}: {}", dir.display(), e),
+        }
+    }
+}
+
+// the model and, for an obj, its sequence frames, mtl libraries and the textures they name
+fn model_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = vec![path.to_path_buf()];
+    if ModelFormat::from_path(path) != ModelFormat::Obj {
+        return files;
+    }
+    files.extend(sequence_frames(path).unwrap_or_default());
+    let Ok(obj) = fs::read(path) else {
+        return files;
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for name in mtl_libraries(&obj) {
+        let mtl = dir.join(name);
+        if let Ok((materials, _)) = tobj::load_mtl(&mtl) {
+            files.extend(materials.into_iter().filter_map(|m| m.diffuse_texture).map(|t| dir.join(t)));
+        }
+        files.push(mtl);
+    }
+    files
+}
+
+// only the directory is resolved, the file itself may be missing halfway through a save
+fn canonical(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Some(fs::canonicalize(dir).ok()?.join(path.file_name()?))
+}
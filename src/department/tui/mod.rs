@@ -1,12 +1,19 @@
 use std::error::Error;
 use std::io::{stdout, Stdout, Write};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossterm;
-use crossterm::event::Event;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, ClearType, EnterAlternateScreen};
-use crossterm::{event, execute, terminal};
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use crossterm::cursor::MoveTo;
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, BeginSynchronizedUpdate, Clear, ClearType, EndSynchronizedUpdate,
+    EnterAlternateScreen,
+};
+use crossterm::{event, execute, queue, terminal};
 use game_loop::{GameLoop, Time, TimeTrait};
+use log::{info, warn};
+use protobuf::{EnumOrUnknown, MessageField};
 
 use crate::department::{
     common::{
@@ -14,14 +21,25 @@ use crate::department::{
         self_type,
     },
     control::camera_controller::CameraController,
-    model::triangle_resources::TriangleResources,
-    pipeline::rasterizer::RasterRunner,
+    model::{load_error::LoadError, object_loader::ObjectLoader, triangle_resources::TriangleResources},
+    net::debug_service::{from_vec3, to_vec3, DebugHandle},
+    pipeline::{path_tracer::PathTracer, rasterizer::RasterRunner, ray_tracer::RayTracer},
     preview::{homo_transformation::HomoTransform, output_buffer::OutputBuffer, vector::Vector3},
     types::msg::TransferMsg,
+    view::picking::{PickId, PickResult},
+};
+use crate::pb::debugger::{
+    CameraState, DebugInfo, FrameStats, RenderState, RenderStateUpdate, ShaderMode,
 };
+use crate::util::ARG;
 
+pub mod hot_reload;
+pub mod presenter;
 pub mod term;
 
+use hot_reload::AssetWatcher;
+use presenter::Presenter;
+
 pub struct TuiApp {
     pub raster: RasterRunner,
     stdout: Stdout,
@@ -30,12 +48,37 @@ pub struct TuiApp {
     gpu: Option<self_type::StateImp>,
     is_playing_music: bool,
     music_stop_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    // cpu path: the scene and the last rendered frame, kept for its id buffer
+    resources: Option<TriangleResources>,
+    ray_tracer: Option<RayTracer>,
+    path_tracer: Option<PathTracer>,
+    frame: Option<OutputBuffer<'static>>,
+    presenter: Presenter,
+    // pixels of the frame, from the terminal size and how many pixels a cell shows
+    frame_size: (u32, u32),
+    pub last_pick: Option<PickResult>,
+    pub debug_info: DebugInfo,
+    debug: Option<DebugHandle>,
+    frame_count: u64,
+    last_frame: Option<Instant>,
+    frame_time: Duration,
+    watcher: Option<AssetWatcher>,
+    // why the last reload failed, drawn over the frame until a reload succeeds
+    model_error: Option<String>,
+    shader_error: Option<String>,
+    overlay_rows: u16,
 }
 
 static FPS: u32 = 30;
 
 static TIME_STEP: Duration = Duration::from_nanos(1_000_000_000 / FPS as u64);
 
+// used when the terminal can't tell its size
+const DEFAULT_TERM_SIZE: (u16, u16) = (256, 79);
+
+// a wgsl error comes with its source excerpt, the first lines say enough
+const MAX_ERROR_ROWS: usize = 8;
+
 pub fn game_loop<G, U, R>(
     game: G,
     updates_per_second: u32,
@@ -64,32 +107,82 @@ impl TuiApp {
             camera_controller: CameraController::new(2.0, 0.2, true),
             is_playing_music: false,
             music_stop_tx: None,
+            resources: None,
+            ray_tracer: None,
+            path_tracer: None,
+            frame: None,
+            presenter: Presenter::new(ARG.term_mode, ARG.ramp, ARG.dither),
+            frame_size: (0, 0),
+            last_pick: None,
+            debug_info: DebugInfo::new(),
+            debug: None,
+            frame_count: 0,
+            last_frame: None,
+            frame_time: Duration::ZERO,
+            watcher: None,
+            model_error: None,
+            shader_error: None,
+            overlay_rows: 0,
         }
     }
 
+    // replaces the rasterizer on the cpu path, the scene has to be set already
+    pub fn with_ray_tracer(mut self, ray_tracer: RayTracer) -> Self {
+        self.ray_tracer = Some(ray_tracer);
+        self
+    }
+
+    pub fn with_path_tracer(mut self, path_tracer: PathTracer) -> Self {
+        self.path_tracer = Some(path_tracer);
+        self
+    }
+
+    pub fn with_debug_service(mut self, handle: DebugHandle) -> Self {
+        self.debug = Some(handle);
+        self
+    }
+
+    pub fn with_resources(mut self, resources: TriangleResources) -> Self {
+        if let Some(skin) = &resources.skin {
+            self.camera_controller.model_ctrl.set_rig(skin.rig.clone());
+        }
+        self.resources = Some(resources);
+        self
+    }
+
     pub async fn run(
         mut self,
         state: Option<self_type::StateImp>,
     ) -> Result<(), Box<dyn Error>> {
-        let _dimension = (256, 79);
-        let camera = self_type::camera_instance(WIDTH, HEIGHT);
-        let state = crate::wgpu::wgpu_helper::State::new(
-            winit::dpi::LogicalSize {
-                width: WIDTH,
-                height: HEIGHT,
-            },
-            camera,
-        )
-        .await;
+        if ARG.use_gpu {
+            let camera = self_type::camera_instance(WIDTH, HEIGHT);
+            let state = crate::wgpu::wgpu_helper::State::new(
+                winit::dpi::LogicalSize {
+                    width: WIDTH,
+                    height: HEIGHT,
+                },
+                camera,
+            )
+            .await;
+            if let Some(rig) = state.rig() {
+                self.camera_controller.model_ctrl.set_rig(rig);
+            }
+            self.gpu = Some(state);
+        }
+        if ARG.watch {
+            match AssetWatcher::new(&ARG.obj_path) {
+                Ok(watcher) => self.watcher = Some(watcher),
+                Err(e) => warn!("hot reload is off, {}", e),
+            }
+        }
+        let term_size = terminal::size().unwrap_or(DEFAULT_TERM_SIZE);
+        self.set_frame_size(self.presenter.frame_size(term_size));
         enable_raw_mode()?;
 
         execute!(self.stdout, crossterm::cursor::Hide)?;
         execute!(self.stdout, EnterAlternateScreen, event::EnableMouseCapture)?;
         execute!(self.stdout, crossterm::terminal::Clear(ClearType::All))?;
 
-        let dimension = (256, 79);
-        self.gpu = Some(state);
-
         let _lop = game_loop(
             self,
             FPS,
@@ -100,6 +193,8 @@ impl TuiApp {
             },
             |g| {
                 let mut should_exit = false;
+                // a drag resizes many times, only the last size is rendered for
+                let mut resize = None;
                 loop {
                     if let Ok(ready) = event::poll(Duration::from_secs(0)) {
                         if ready {
@@ -155,12 +250,14 @@ impl TuiApp {
                                             });
                                         }
                                     }
-                                    Event::Mouse(_) => {}
-                                    Event::Paste(_) => {}
-                                    Event::Resize(w, h) => {
-                                        //println!("terminal window update to new size {} {}", w, h);
-                                        //self.draw((w as u32, h as u32), &res);
+                                    Event::Mouse(m) => {
+                                        if m.kind == MouseEventKind::Down(MouseButton::Left) {
+                                            let (x, y) = g.game.presenter.cell_to_pixel(m.column, m.row);
+                                            g.game.pick(x, y);
+                                        }
                                     }
+                                    Event::Paste(_) => {}
+                                    Event::Resize(w, h) => resize = Some((w, h)),
                                 }
                             } else {
                                 break;
@@ -176,8 +273,11 @@ impl TuiApp {
                 if should_exit {
                     g.exit();
                 }
+                if let Some(term_size) = resize {
+                    g.game.resize(term_size);
+                }
                 // execute!(g.game.stdout, terminal::Clear(ClearType::All));
-                g.game.draw((dimension.0 as u32, dimension.1 as u32));
+                g.game.draw();
 
                 let st = TIME_STEP.as_secs_f64() - Time::now().sub(&g.current_instant());
                 if st > 0. {
@@ -189,25 +289,367 @@ impl TuiApp {
     }
 
     pub fn update(&mut self, last_frame_time: f64) {
+        self.hot_reload();
         if let Some(ref mut gpu) = self.gpu {
             gpu.update_outside(
                 &mut self.camera_controller,
                 Duration::from_secs_f64(last_frame_time),
             );
+        } else {
+            // the rasterizer's camera leads, the tracers only hear about actual moves so the
+            // path tracer keeps its samples while the view stands still
+            let before = self.raster.camera_pose();
+            self.raster
+                .update_camera(&mut self.camera_controller, Duration::from_secs_f64(last_frame_time));
+            let (position, forward) = self.raster.camera_pose();
+            if (position, forward) != before {
+                self.set_cpu_camera_pose(position, forward);
+            }
+
+            let model_ctrl = &mut self.camera_controller.model_ctrl;
+            model_ctrl.animate(last_frame_time as f32);
+            if let Some(matrices) = model_ctrl.joint_matrices() {
+                self.raster.set_joint_matrices(matrices.to_vec());
+            }
+            // the tracers built their scene once, only the rasterizer follows the sequence
+            if self.ray_tracer.is_none() && self.path_tracer.is_none() {
+                if let Some(res) = self.resources.as_mut() {
+                    res.pose_sequence(model_ctrl.sequence_time());
+                }
+            }
         }
     }
 
-    pub fn draw(&mut self, dim: (u32, u32)) {
+    // every renderer projects for the new frame, see `Presenter::projection_size`
+    fn set_frame_size(&mut self, size: (u32, u32)) {
+        let (width, height) = self.presenter.projection_size(size);
+        self.frame_size = size;
+        self.raster.set_viewport(width, height);
+        if let Some(rt) = self.ray_tracer.as_mut() {
+            rt.set_viewport(width, height);
+        }
+        if let Some(pt) = self.path_tracer.as_mut() {
+            pt.set_viewport(width, height);
+        }
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.set_tui_size(size, (width, height));
+        }
+        self.frame = None;
+    }
+
+    // the new size is rendered from the next frame on. the screen is cleared first, the old
+    // frame would otherwise stay around the new one or wrap at the new width
+    fn resize(&mut self, term_size: (u16, u16)) {
+        self.set_frame_size(self.presenter.frame_size(term_size));
+        let _ = queue!(self.stdout, Clear(ClearType::All));
+        self.presenter.invalidate();
+        self.overlay_rows = 0;
+    }
+
+    pub fn draw(&mut self) {
+        let dim = self.frame_size;
+        let now = Instant::now();
+        if let Some(last) = self.last_frame {
+            self.frame_time = now - last;
+        }
+        self.last_frame = Some(now);
+        self.frame_count += 1;
+        self.sync_debug();
+        // terminals without synchronized updates ignore it and draw as the cells come in
+        let _ = queue!(self.stdout, BeginSynchronizedUpdate);
+        self.clear_overlay();
+
         if let Some(ref mut gpu) = self.gpu {
-            let mut out_buf = OutputBuffer::new(dim.0 as u32, dim.1 as u32, true);
-            out_buf.stdout = Some(&mut self.stdout);
+            let mut out_buf = OutputBuffer::new(dim.0, dim.1, true);
             let out = gpu.render(true, false);
             out_buf.display.copy_from_slice(&out.0);
+            out_buf.fill_luminance();
             //self.raster.encoder_tx.enc.send(TransferMsg::RenderPc(out)).unwrap();
-            out_buf.queue_to_stdout();
-            drop(out_buf);
-            self.stdout.flush().unwrap();
+            if let Err(e) = self.presenter.queue(&mut self.stdout, &out_buf) {
+                warn!("frame not written, {}", e);
+            }
+        } else if let Some(res) = &self.resources {
+            let mut out_buf = OutputBuffer::new(dim.0, dim.1, true);
+            if let Some(pt) = &mut self.path_tracer {
+                pt.render_frame(res, &mut out_buf);
+            } else if let Some(rt) = &self.ray_tracer {
+                rt.render_frame(res, &mut out_buf);
+            } else {
+                self.raster.render_frame(res, &mut out_buf);
+            }
+            if let Err(e) = self.presenter.queue(&mut self.stdout, &out_buf) {
+                warn!("frame not written, {}", e);
+            }
+            self.frame = Some(out_buf);
+        }
+        self.queue_overlay(dim.0 / self.presenter.cell_pixels().0);
+        let _ = queue!(self.stdout, EndSynchronizedUpdate);
+        self.stdout.flush().unwrap();
+    }
+
+    // (x, y) is a pixel of the tui frame, `Presenter::cell_to_pixel` of the clicked cell
+    pub fn pick(&mut self, x: u32, y: u32) {
+        let result = if let Some(ref mut gpu) = self.gpu {
+            gpu.pick((x, y), true)
+        } else if let (Some(res), Some(frame)) = (&self.resources, &self.frame) {
+            // the renderer that drew `frame` wrote its ids
+            let result = if let Some(pt) = &self.path_tracer {
+                pt.pick(res, frame, x, y)
+            } else if let Some(rt) = &self.ray_tracer {
+                rt.pick(res, frame, x, y)
+            } else {
+                self.raster.pick(res, frame, x, y)
+            };
+            self.set_cpu_highlight(result.as_ref().map(|r| r.id));
+            result
+        } else {
+            None
+        };
+
+        if let Some(r) = &result {
+            info!(
+                "picked object {} triangle {} at {:?}, normal {:?}, uv {:?}",
+                r.id.object, r.id.triangle, r.world_position.as_slice(), r.normal.as_slice(), r.uv.as_slice()
+            );
+            self.debug_info = r.to_debug_info();
+        }
+        self.last_pick = result;
+    }
+}
+
+impl TuiApp {
+    // reloads whatever the watcher saw change. a failed reload keeps the old assets on screen
+    // together with the reason, the camera is left alone either way
+    fn hot_reload(&mut self) {
+        let Some(changes) = self.watcher.as_mut().map(AssetWatcher::poll) else {
             return;
+        };
+        if changes.model {
+            let result = match self.gpu.as_mut() {
+                Some(gpu) => gpu.reload_model().map_err(|e| format!("{:#}", e)),
+                None => self.reload_resources().map_err(|e| e.to_string()),
+            };
+            if result.is_ok() {
+                let model_ctrl = &mut self.camera_controller.model_ctrl;
+                let rig = match self.gpu.as_ref() {
+                    Some(gpu) => gpu.rig(),
+                    None => self.resources.as_ref().and_then(|r| r.skin.as_ref()).map(|s| s.rig.clone()),
+                };
+                match rig {
+                    Some(rig) => model_ctrl.set_rig(rig),
+                    None => model_ctrl.clear_rig(),
+                }
+                self.last_pick = None;
+            }
+            self.model_error = reload_outcome("model", result);
+            if let Some(watcher) = self.watcher.as_mut() {
+                watcher.rescan();
+            }
+        }
+        if changes.shaders {
+            // the cpu renderers have no shaders on disk
+            if let Some(gpu) = self.gpu.as_mut() {
+                let result = gpu.reload_shaders().map_err(|e| format!("{:#}", e));
+                self.shader_error = reload_outcome("shaders", result);
+            }
+        }
+    }
+
+    // cpu path, set up like main does minus framing the camera
+    fn reload_resources(&mut self) -> Result<(), LoadError> {
+        if self.resources.is_none() {
+            return Ok(());
+        }
+        let mut res = ObjectLoader::load_triangle_resources(&ARG.obj_path)?;
+        if ARG.normalize {
+            res.normalize();
+        }
+        if let Some(sequence) = res.sequence.as_mut() {
+            sequence.set_fps(ARG.sequence_fps);
+        }
+        if let Some(rt) = self.ray_tracer.as_mut() {
+            rt.set_scene(&res);
+        }
+        if let Some(pt) = self.path_tracer.as_mut() {
+            pt.set_scene(&res);
+        }
+        self.set_cpu_highlight(None);
+        self.frame = None;
+        self.resources = Some(res);
+        Ok(())
+    }
+
+    // the overlay of the previous frame, cells the new frame leaves empty would keep it
+    fn clear_overlay(&mut self) {
+        for row in 0..self.overlay_rows {
+            let _ = queue!(self.stdout, MoveTo(0, row), Clear(ClearType::CurrentLine));
+        }
+        self.presenter.invalidate_rows(0..self.overlay_rows);
+        self.overlay_rows = 0;
+    }
+
+    // reload errors in the top rows, cut to the terminal width
+    fn queue_overlay(&mut self, width: u32) {
+        let rows: Vec<String> = [&self.model_error, &self.shader_error]
+            .into_iter()
+            .flatten()
+            .flat_map(|e| e.lines())
+            .filter(|l| !l.trim().is_empty())
+            .take(MAX_ERROR_ROWS)
+            .map(|l| l.chars().take(width as usize).collect())
+            .collect();
+        for (row, text) in rows.iter().enumerate() {
+            let _ = queue!(
+                self.stdout,
+                MoveTo(0, row as u16),
+                SetForegroundColor(Color::White),
+                SetBackgroundColor(Color::DarkRed),
+                Print(text),
+                ResetColor
+            );
+        }
+        self.overlay_rows = rows.len() as u16;
+    }
+
+    // applies what debug clients asked for, then publishes the state this frame renders with
+    fn sync_debug(&mut self) {
+        if let Some(handle) = self.debug.clone() {
+            let pending = std::mem::take(&mut handle.lock().unwrap().pending);
+            for update in pending {
+                self.apply_render_update(update);
+            }
+            let state = self.render_state();
+            handle.lock().unwrap().render_state = state;
+        }
+    }
+
+    fn apply_render_update(&mut self, update: RenderStateUpdate) {
+        if let Some(triangle) = update.triangleIndex {
+            let id = Some(PickId::new(0, triangle));
+            if let Some(ref mut gpu) = self.gpu {
+                gpu.set_highlight(id);
+            } else {
+                self.set_cpu_highlight(id);
+            }
+            self.debug_info.triangleIndex = triangle;
+        }
+
+        if let Some(camera) = update.camera.as_ref() {
+            let (position, forward) = self.camera_pose();
+            let position = camera.position.as_ref().map_or(position, from_vec3);
+            let forward = camera.forward.as_ref().map_or(forward, from_vec3);
+            if let Some(ref mut gpu) = self.gpu {
+                gpu.set_camera_pose(position, forward);
+            } else {
+                self.set_cpu_camera_pose(position, forward);
+            }
+        }
+
+        if let Some(mode) = update.shaderMode {
+            match mode.enum_value() {
+                Ok(mode) if self.gpu.is_some() => {
+                    warn!("shader mode {:?} is only supported by the cpu renderer", mode)
+                }
+                Ok(mode) => {
+                    self.raster.set_shader_mode(mode);
+                    if let Some(rt) = self.ray_tracer.as_mut() {
+                        rt.set_shader_mode(mode);
+                    }
+                    if let Some(pt) = self.path_tracer.as_mut() {
+                        pt.set_shader_mode(mode);
+                    }
+                }
+                Err(v) => warn!("unknown shader mode {}", v),
+            }
+        }
+    }
+
+    fn set_cpu_highlight(&mut self, id: Option<PickId>) {
+        self.raster.set_highlight(id);
+        if let Some(rt) = self.ray_tracer.as_mut() {
+            rt.set_highlight(id);
+        }
+        if let Some(pt) = self.path_tracer.as_mut() {
+            pt.set_highlight(id);
+        }
+    }
+
+    // the cpu renderers share one view, whichever of them draws. the debugger and the
+    // controller both go through here
+    fn set_cpu_camera_pose(&mut self, position: [f32; 3], forward: [f32; 3]) {
+        self.raster.set_camera_pose(position, forward);
+        if let Some(rt) = self.ray_tracer.as_mut() {
+            rt.set_camera_pose(position, forward);
+        }
+        if let Some(pt) = self.path_tracer.as_mut() {
+            pt.set_camera_pose(position, forward);
+        }
+    }
+
+    // the pose of the renderer that draws, same order as `draw`
+    fn camera_pose(&self) -> ([f32; 3], [f32; 3]) {
+        if let Some(ref gpu) = self.gpu {
+            gpu.camera_pose()
+        } else if let Some(ref pt) = self.path_tracer {
+            pt.camera_pose()
+        } else if let Some(ref rt) = self.ray_tracer {
+            rt.camera_pose()
+        } else {
+            self.raster.camera_pose()
+        }
+    }
+
+    fn shader_mode(&self) -> ShaderMode {
+        if self.gpu.is_some() {
+            ShaderMode::Lambertian
+        } else if let Some(ref pt) = self.path_tracer {
+            pt.shader_mode()
+        } else if let Some(ref rt) = self.ray_tracer {
+            rt.shader_mode()
+        } else {
+            self.raster.shader_mode()
+        }
+    }
+
+    pub fn render_state(&self) -> RenderState {
+        let (position, forward) = self.camera_pose();
+        let mut camera = CameraState::new();
+        camera.position = MessageField::some(to_vec3(position));
+        camera.forward = MessageField::some(to_vec3(forward));
+
+        let mut stats = FrameStats::new();
+        stats.frameCount = self.frame_count;
+        stats.frameTimeMs = self.frame_time.as_secs_f32() * 1000.;
+        stats.fps = if self.frame_time.is_zero() {
+            0.
+        } else {
+            1. / self.frame_time.as_secs_f32()
+        };
+        stats.triangleCount = if let Some(ref gpu) = self.gpu {
+            gpu.triangle_count()
+        } else {
+            self.resources.as_ref().map_or(0, |r| r.triangle_count() as u32)
+        };
+
+        let mut state = RenderState::new();
+        state.debugInfo = MessageField::some(self.debug_info.clone());
+        state.camera = MessageField::some(camera);
+        state.shaderMode = EnumOrUnknown::new(self.shader_mode());
+        state.stats = MessageField::some(stats);
+        state
+    }
+}
+
+// logs how a reload went, the error to show for a failed one
+fn reload_outcome(what: &str, result: Result<(), String>) -> Option<String> {
+    match result {
+        Ok(()) => {
+            info!("{} reloaded", what);
+            None
+        }
+        Err(e) => {
+            warn!("{} not reloaded, {}", what, e);
+            Some(format!("{} not reloaded: {}", what, e))
         }
     }
 }
@@ -0,0 +1,443 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+
+use crate::department::pipeline::shader::{luminance_glyph, BLOCK_RAMP, CLASSIC_RAMP, DETAILED_RAMP, LUMINANCE_CHARS};
+use crate::department::preview::output_buffer::OutputBuffer;
+use crate::util::{Dither, Ramp, TermMode};
+
+// a terminal cell is about twice as tall as it is wide
+const CELL_ASPECT: u32 = 2;
+
+// dot bits of U+2800 onwards, indexed by [y][x] within the cell
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// turns rendered frames into terminal cells
+pub struct Presenter {
+    mode: TermMode,
+    ramp: Vec<char>,
+    dither: Dither,
+    // what is on screen, none where it's unknown
+    front: Vec<Option<Cell>>,
+    columns: usize,
+}
+
+impl Presenter {
+    pub fn new(mode: TermMode, ramp: Ramp, dither: Dither) -> Self {
+        let ramp = match ramp {
+            Ramp::Standard => LUMINANCE_CHARS.to_vec(),
+            Ramp::Classic => CLASSIC_RAMP.chars().collect(),
+            Ramp::Detailed => DETAILED_RAMP.chars().collect(),
+            Ramp::Blocks => BLOCK_RAMP.chars().collect(),
+        };
+        Self {
+            mode,
+            ramp,
+            dither,
+            front: Vec::new(),
+            columns: 0,
+        }
+    }
+
+    pub fn mode(&self) -> TermMode {
+        self.mode
+    }
+
+    // frame pixels per cell, across and down
+    pub fn cell_pixels(&self) -> (u32, u32) {
+        match self.mode {
+            TermMode::Glyph => (1, 1),
+            TermMode::HalfBlock => (1, 2),
+            TermMode::Braille => (2, 4),
+        }
+    }
+
+    // the frame that fills a terminal of `cols` x `rows` cells
+    pub fn frame_size(&self, (cols, rows): (u16, u16)) -> (u32, u32) {
+        let (cx, cy) = self.cell_pixels();
+        ((cols as u32).max(1) * cx, (rows as u32).max(1) * cy)
+    }
+
+    // what the camera projects for, the frame stretched to square pixels
+    pub fn projection_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let (cx, cy) = self.cell_pixels();
+        (width * cy, height * cx * CELL_ASPECT)
+    }
+
+    // the frame pixel in the middle of a cell, what a click on that cell picks
+    pub fn cell_to_pixel(&self, col: u16, row: u16) -> (u32, u32) {
+        let (cx, cy) = self.cell_pixels();
+        (col as u32 * cx + cx / 2, row as u32 * cy + cy / 2)
+    }
+
+    // writes the cells that differ from what is on screen, see `queue_cells`
+    pub fn queue<W: Write>(&mut self, out: &mut W, frame: &OutputBuffer) -> io::Result<()> {
+        let cells = match self.mode {
+            TermMode::Glyph => glyph_cells(frame, &self.ramp),
+            TermMode::HalfBlock => half_block_cells(frame),
+            TermMode::Braille => braille_cells(frame, self.dither),
+        };
+        let columns = frame.dimension().0.div_ceil(self.cell_pixels().0) as usize;
+        if self.front.len() != cells.len() || self.columns != columns {
+            self.front = vec![None; cells.len()];
+            self.columns = columns;
+        }
+        let result = queue_cells(out, &mut self.front, &cells, columns);
+        // part of the frame may not have made it out
+        if result.is_err() {
+            self.invalidate();
+        }
+        result
+    }
+
+    // everything is written again with the next frame, after the screen was cleared
+    pub fn invalidate(&mut self) {
+        self.front.clear();
+    }
+
+    // `rows` are written again with the next frame, something else was drawn over them
+    pub fn invalidate_rows(&mut self, rows: Range<u16>) {
+        let columns = self.columns;
+        for row in rows {
+            if let Some(cells) = self.front.get_mut(row as usize * columns..(row as usize + 1) * columns) {
+                cells.fill(None);
+            }
+        }
+    }
+}
+
+// what one terminal cell shows. the foreground of a blank cell doesn't matter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    glyph: char,
+    fg: Color,
+    bg: Color,
+}
+
+impl Cell {
+    const EMPTY: Cell = Cell {
+        glyph: ' ',
+        fg: Color::Reset,
+        bg: Color::Reset,
+    };
+
+    fn new(glyph: char, fg: Color) -> Self {
+        Self { glyph, fg, bg: Color::Reset }
+    }
+}
+
+// only cells that differ from `front` are written, and `front` is updated to match. changed
+// cells next to each other in the same colours go out as one print, a gap costs a `MoveTo`
+fn queue_cells<W: Write>(out: &mut W, front: &mut [Option<Cell>], back: &[Cell], columns: usize) -> io::Result<()> {
+    let (mut fg, mut bg) = (None, None);
+    let mut run = String::new();
+    // where the cursor is once `run` is printed
+    let mut cursor = None;
+    for (i, (shown, &cell)) in front.iter_mut().zip(back).enumerate() {
+        if *shown == Some(cell) {
+            continue;
+        }
+        *shown = Some(cell);
+        let at = ((i % columns) as u16, (i / columns) as u16);
+        let restyle = (cell.glyph != ' ' && fg != Some(cell.fg)) || bg != Some(cell.bg);
+        if cursor != Some(at) || restyle {
+            if !run.is_empty() {
+                queue!(out, Print(&run))?;
+                run.clear();
+            }
+            if cursor != Some(at) {
+                queue!(out, MoveTo(at.0, at.1))?;
+            }
+            if cell.glyph != ' ' && fg != Some(cell.fg) {
+                queue!(out, SetForegroundColor(cell.fg))?;
+                fg = Some(cell.fg);
+            }
+            if bg != Some(cell.bg) {
+                queue!(out, SetBackgroundColor(cell.bg))?;
+                bg = Some(cell.bg);
+            }
+        }
+        run.push(cell.glyph);
+        // a row ends at the terminal's edge, the next one needs its own `MoveTo`
+        cursor = (at.0 as usize + 1 < columns).then_some((at.0 + 1, at.1));
+    }
+    if !run.is_empty() {
+        queue!(out, Print(&run))?;
+    }
+    queue!(out, ResetColor)
+}
+
+// rgb of a pixel, none where nothing was drawn
+fn pixel(frame: &OutputBuffer, x: u32, y: u32) -> Option<Color> {
+    let (width, height) = frame.dimension();
+    if x >= width || y >= height {
+        return None;
+    }
+    let i = (y * width + x) as usize * 4;
+    let &[r, g, b, c] = &frame.display[i..i + 4] else {
+        return None;
+    };
+    (c != 0).then_some(Color::Rgb { r, g, b })
+}
+
+// one pixel per cell, its luminance byte picks the glyph
+fn glyph_cells(frame: &OutputBuffer, ramp: &[char]) -> Vec<Cell> {
+    frame
+        .display
+        .array_chunks()
+        .map(|&[r, g, b, luminance]| match luminance {
+            0 => Cell::EMPTY,
+            _ => Cell::new(luminance_glyph(ramp, luminance), Color::Rgb { r, g, b }),
+        })
+        .collect()
+}
+
+// the upper pixel of each cell is the foreground of `▀`, the lower one its background.
+// empty pixels show the terminal's own background
+fn half_block_cells(frame: &OutputBuffer) -> Vec<Cell> {
+    let (width, height) = frame.dimension();
+    let mut cells = Vec::with_capacity((width * height.div_ceil(2)) as usize);
+    for row in 0..height.div_ceil(2) {
+        for x in 0..width {
+            cells.push(match (pixel(frame, x, row * 2), pixel(frame, x, row * 2 + 1)) {
+                (Some(top), bottom) => Cell {
+                    glyph: '▀',
+                    fg: top,
+                    bg: bottom.unwrap_or(Color::Reset),
+                },
+                (None, Some(bottom)) => Cell::new('▄', bottom),
+                (None, None) => Cell::EMPTY,
+            });
+        }
+    }
+    cells
+}
+
+// a dot is lit when the luminance of its pixel clears the threshold, the cell takes the
+// average colour of its lit dots
+fn braille_cells(frame: &OutputBuffer, dither: Dither) -> Vec<Cell> {
+    let (width, height) = frame.dimension();
+    let mut cells = Vec::with_capacity((width.div_ceil(2) * height.div_ceil(4)) as usize);
+    for row in 0..height.div_ceil(4) {
+        for col in 0..width.div_ceil(2) {
+            let (mut bits, mut sum, mut lit) = (0u8, [0u32; 3], 0u32);
+            for (dy, dots) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, bit) in dots.iter().enumerate() {
+                    let (x, y) = (col * 2 + dx as u32, row * 4 + dy as u32);
+                    let Some(Color::Rgb { r, g, b }) = pixel(frame, x, y) else {
+                        continue;
+                    };
+                    let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.;
+                    let threshold = match dither {
+                        Dither::Threshold => 0.5,
+                        Dither::Ordered => (BAYER[y as usize % 4][x as usize % 4] as f32 + 0.5) / 16.,
+                    };
+                    if luma > threshold {
+                        bits |= bit;
+                        sum = [sum[0] + r as u32, sum[1] + g as u32, sum[2] + b as u32];
+                        lit += 1;
+                    }
+                }
+            }
+            if lit == 0 {
+                cells.push(Cell::EMPTY);
+                continue;
+            }
+            let [r, g, b] = sum.map(|c| (c / lit) as u8);
+            let glyph = char::from_u32(0x2800 + bits as u32).unwrap_or(' ');
+            cells.push(Cell::new(glyph, Color::Rgb { r, g, b }));
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::Rgb { r: 255, g: 0, b: 0 };
+    const GREEN: Color = Color::Rgb { r: 0, g: 255, b: 0 };
+    const BLUE: Color = Color::Rgb { r: 0, g: 0, b: 255 };
+    const WHITE: Color = Color::Rgb { r: 255, g: 255, b: 255 };
+
+    // a frame with only `pixels` drawn, the last byte is the luminance the rasterizer writes
+    fn frame(width: u32, height: u32, pixels: &[(u32, u32, [u8; 4])]) -> OutputBuffer<'static> {
+        let mut frame = OutputBuffer::new(width, height, true);
+        for &(x, y, rgba) in pixels {
+            let i = (y * width + x) as usize * 4;
+            frame.display[i..i + 4].copy_from_slice(&rgba);
+        }
+        frame
+    }
+
+    #[test]
+    fn half_block_pairs_rows_and_pads_odd_heights() {
+        let frame = frame(
+            2,
+            3,
+            &[
+                (0, 0, [255, 0, 0, 1]),
+                (0, 1, [0, 0, 255, 1]),
+                (1, 1, [0, 255, 0, 1]),
+                (0, 2, [255, 255, 255, 1]),
+            ],
+        );
+        let cells = half_block_cells(&frame);
+        assert_eq!(
+            cells,
+            vec![
+                Cell { glyph: '▀', fg: RED, bg: BLUE },
+                Cell::new('▄', GREEN),
+                // the missing last row shows the terminal's background
+                Cell { glyph: '▀', fg: WHITE, bg: Color::Reset },
+                Cell::EMPTY,
+            ]
+        );
+    }
+
+    #[test]
+    fn half_block_projects_to_square_pixels() {
+        let presenter = Presenter::new(TermMode::HalfBlock, Ramp::Standard, Dither::Threshold);
+        let size = presenter.frame_size((80, 24));
+        assert_eq!(size, (80, 48));
+        // 80 x 24 cells twice as tall as wide
+        assert_eq!(presenter.projection_size(size), (160, 96));
+        assert_eq!(presenter.cell_to_pixel(3, 5), (3, 11));
+    }
+
+    #[test]
+    fn glyph_cells_leave_undrawn_pixels_empty() {
+        let frame = frame(2, 1, &[(1, 0, [255, 0, 0, 255])]);
+        assert_eq!(glyph_cells(&frame, &LUMINANCE_CHARS), vec![Cell::EMPTY, Cell::new('@', RED)]);
+    }
+
+    #[test]
+    fn luminance_glyph_spans_the_ramp() {
+        assert_eq!(luminance_glyph(&LUMINANCE_CHARS, 1), '.');
+        assert_eq!(luminance_glyph(&LUMINANCE_CHARS, 128), '=');
+        assert_eq!(luminance_glyph(&LUMINANCE_CHARS, 255), '@');
+        let blocks: Vec<char> = BLOCK_RAMP.chars().collect();
+        assert_eq!(luminance_glyph(&blocks, 255), '█');
+    }
+
+    #[test]
+    fn braille_lights_dots_over_the_threshold() {
+        let mut pixels: Vec<_> = (0..2).flat_map(|x| (0..4).map(move |y| (x, y, [255, 255, 255, 1]))).collect();
+        // drawn but too dark to light its dot
+        pixels[7] = (1, 3, [10, 10, 10, 1]);
+        let cells = braille_cells(&frame(2, 4, &pixels), Dither::Threshold);
+        assert_eq!(cells, vec![Cell::new('\u{287f}', WHITE)]);
+    }
+
+    #[test]
+    fn braille_orders_dots_with_the_bayer_matrix() {
+        let pixels: Vec<_> = (0..2).flat_map(|x| (0..4).map(move |y| (x, y, [128, 128, 128, 1]))).collect();
+        let cells = braille_cells(&frame(2, 4, &pixels), Dither::Ordered);
+        let gray = Color::Rgb { r: 128, g: 128, b: 128 };
+        assert_eq!(cells, vec![Cell::new('\u{2895}', gray)]);
+    }
+
+    #[test]
+    fn braille_pads_partial_cells() {
+        let cells = braille_cells(&frame(3, 5, &[(2, 4, [255, 0, 0, 1])]), Dither::Threshold);
+        // the red dot is at the top left of the last cell, its luminance is under the threshold
+        assert_eq!(cells, vec![Cell::EMPTY; 4]);
+        let cells = braille_cells(&frame(3, 5, &[(2, 4, [255, 255, 255, 1])]), Dither::Threshold);
+        assert_eq!(cells, vec![Cell::EMPTY, Cell::EMPTY, Cell::EMPTY, Cell::new('\u{2801}', WHITE)]);
+    }
+
+    fn queued(front: &mut [Option<Cell>], back: &[Cell], columns: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        queue_cells(&mut out, front, back, columns).unwrap();
+        out
+    }
+
+    #[test]
+    fn queue_breaks_runs_at_row_ends() {
+        let back = [Cell::new('a', RED), Cell::new('b', RED), Cell::new('c', RED), Cell::new('d', RED)];
+        let mut front = vec![None; 4];
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            MoveTo(0, 0),
+            SetForegroundColor(RED),
+            SetBackgroundColor(Color::Reset),
+            Print("ab"),
+            MoveTo(0, 1),
+            Print("cd"),
+            ResetColor
+        )
+        .unwrap();
+        assert_eq!(queued(&mut front, &back, 2), expected);
+        assert_eq!(front, back.map(Some));
+
+        // nothing changed, nothing but the reset goes out
+        let mut expected = Vec::new();
+        queue!(expected, ResetColor).unwrap();
+        assert_eq!(queued(&mut front, &back, 2), expected);
+    }
+
+    #[test]
+    fn queue_writes_a_single_changed_cell() {
+        let mut back = [Cell::new('a', RED), Cell::new('b', RED), Cell::new('c', RED), Cell::new('d', RED)];
+        let mut front = back.map(Some).to_vec();
+        back[1] = Cell::new('x', RED);
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            MoveTo(1, 0),
+            SetForegroundColor(RED),
+            SetBackgroundColor(Color::Reset),
+            Print("x"),
+            ResetColor
+        )
+        .unwrap();
+        assert_eq!(queued(&mut front, &back, 2), expected);
+    }
+
+    #[test]
+    fn queue_restyles_only_for_visible_colours() {
+        // a blank cell keeps the run going whatever its foreground
+        let back = [Cell::new('a', RED), Cell::EMPTY, Cell::new('b', BLUE)];
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            MoveTo(0, 0),
+            SetForegroundColor(RED),
+            SetBackgroundColor(Color::Reset),
+            Print("a "),
+            SetForegroundColor(BLUE),
+            Print("b"),
+            ResetColor
+        )
+        .unwrap();
+        assert_eq!(queued(&mut [None; 3], &back, 3), expected);
+    }
+
+    #[test]
+    fn invalidated_rows_are_written_again() {
+        let mut presenter = Presenter::new(TermMode::Glyph, Ramp::Standard, Dither::Threshold);
+        let frame = frame(2, 2, &[(0, 0, [255, 0, 0, 255]), (1, 1, [0, 0, 255, 255])]);
+        presenter.queue(&mut Vec::new(), &frame).unwrap();
+        presenter.invalidate_rows(1..2);
+        let mut out = Vec::new();
+        presenter.queue(&mut out, &frame).unwrap();
+        // the blank cell needs no foreground, `@` sets its own after it
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            MoveTo(0, 1),
+            SetBackgroundColor(Color::Reset),
+            Print(" "),
+            SetForegroundColor(BLUE),
+            Print("@"),
+            ResetColor
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+}
@@ -1,6 +1,5 @@
-use std::f32::consts::PI;
+use std::f32::consts::{FRAC_PI_2, PI};
 
-use image::GenericImageView;
 use winit::keyboard::KeyCode;
 
 use crate::department::common::constant;
@@ -13,6 +12,7 @@ use crate::department::preview::output_buffer::OutputBuffer;
 use crate::department::preview::position::Pos3;
 use crate::department::preview::vector::Vector3;
 use crate::department::view::camera_trait;
+use crate::department::view::camera_trait::CameraTrait;
 
 pub struct Camera {
     fov_y: f32,
@@ -26,17 +26,37 @@ pub struct Camera {
     pub perspective_projection: HMat,
 }
 
+// keeps the forward vector off the poles, where yaw is undefined
+const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
+
 impl camera_trait::CameraTrait for Camera {
+    // same fly camera as the wgpu one, yaw and pitch are read back from `forward`
     fn update_camera(
         &mut self,
-        _forward_dt: f32,
-        _right_dt: f32,
-        _scroll_dt: f32,
-        _up_dt: f32,
-        _hori: f32,
-        _ver: f32,
-        _sensi: f32,
+        forward_dt: f32,
+        right_dt: f32,
+        scroll_dt: f32,
+        up_dt: f32,
+        hori: f32,
+        ver: f32,
+        sensi: f32,
     ) {
+        let (eye, forward) = self.pose();
+        let yaw = forward[2].atan2(forward[0]);
+        let pitch = forward[1].clamp(-1., 1.).asin();
+        let (yaw_sin, yaw_cos) = yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = pitch.sin_cos();
+        let flat = [yaw_cos, 0., yaw_sin];
+        let right = [-yaw_sin, 0., yaw_cos];
+        let scrollward = [pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin];
+        let mut eye = [0, 1, 2].map(|k| eye[k] + flat[k] * forward_dt + right[k] * right_dt + scrollward[k] * scroll_dt);
+        eye[1] += up_dt;
+
+        let yaw = yaw + hori * sensi;
+        let pitch = (pitch - ver * sensi).clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2);
+        let (yaw_sin, yaw_cos) = yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = pitch.sin_cos();
+        self.set_pose(eye, [pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin]);
     }
 
     fn to_view_position(&self) -> [f32; 4] {
@@ -55,6 +75,24 @@ impl camera_trait::CameraTrait for Camera {
             Camera::perspective_projection_mat_right_hand(self.fov_y, self.ratio, self.n, self.z)
         };
     }
+
+    fn pose(&self) -> ([f32; 3], [f32; 3]) {
+        (
+            [self.eye.x(), self.eye.y(), self.eye.z()],
+            [self.forward.x(), self.forward.y(), self.forward.z()],
+        )
+    }
+
+    fn set_pose(&mut self, position: [f32; 3], forward: [f32; 3]) {
+        self.eye = Pos3::from_xyz(position[0], position[1], position[2]);
+        let mut forward = Vector3::from_xyz(forward[0], forward[1], forward[2]);
+        forward.norm();
+        self.forward = forward;
+    }
+
+    fn fov_y(&self) -> f32 {
+        self.fov_y.to_radians()
+    }
 }
 
 impl Camera {
@@ -379,7 +417,7 @@ impl Camera {
         let mvp = &mvp * &view_port;
         let mvp_1 = mvp.inverse_matrix();
 
-        let image = triangle_res.image.as_ref().unwrap();
+        let texture = triangle_res.texture.as_ref().unwrap();
 
         for _tri in triangle_res.iter() {
             let trans_poses = _tri
@@ -452,9 +490,9 @@ impl Camera {
                         if depth > cur_depth {
                             _out.set_depth(i as usize, j as usize, depth);
                             let uv = _tri.get_uv(&bar);
-                            let color = image.get_pixel(uv.u() as u32, uv.v() as u32);
+                            let color = texture.texel(uv.u(), uv.v(), 0);
 
-                            _out.put_pixel(i, j.try_into().unwrap(), &color.0);
+                            _out.put_pixel(i, j.try_into().unwrap(), &color);
                         }
                     }
                 }
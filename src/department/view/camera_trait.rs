@@ -1,3 +1,5 @@
+use crate::department::model::auto_fit::framing_distance;
+
 pub trait CameraTrait {
     fn update_camera(
         &mut self,
@@ -15,4 +17,20 @@ pub trait CameraTrait {
     fn to_view_proj(&self) -> [[f32; 4]; 4];
 
     fn update_projection(&mut self, width: u32, height: u32);
+
+    // world space eye position and unit forward direction
+    fn pose(&self) -> ([f32; 3], [f32; 3]);
+
+    fn set_pose(&mut self, position: [f32; 3], forward: [f32; 3]);
+
+    // vertical field of view in radians
+    fn fov_y(&self) -> f32;
+
+    // backs off along the current forward direction until the sphere fills the view
+    fn frame(&mut self, center: [f32; 3], radius: f32) {
+        let (_, forward) = self.pose();
+        let d = framing_distance(radius, self.fov_y());
+        let position = [0, 1, 2].map(|k| center[k] - forward[k] * d);
+        self.set_pose(position, forward);
+    }
 }
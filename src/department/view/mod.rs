@@ -2,3 +2,4 @@ pub mod camera;
 pub mod render;
 pub mod camera_trait;
 pub mod local_window;
+pub mod picking;
@@ -0,0 +1,46 @@
+use crate::department::preview::vector::{Vec2, Vector3};
+use crate::pb::debugger::DebugInfo;
+
+// what sits under a pixel, written next to the depth buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickId {
+    pub object: u32,
+    pub triangle: u32,
+}
+
+pub const NO_PICK: PickId = PickId {
+    object: u32::MAX,
+    triangle: u32::MAX,
+};
+
+impl PickId {
+    pub fn new(object: u32, triangle: u32) -> Self {
+        Self { object, triangle }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == NO_PICK
+    }
+}
+
+impl Default for PickId {
+    fn default() -> Self {
+        NO_PICK
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PickResult {
+    pub id: PickId,
+    pub world_position: Vector3,
+    pub normal: Vector3,
+    pub uv: Vec2,
+}
+
+impl PickResult {
+    pub fn to_debug_info(&self) -> DebugInfo {
+        let mut info = DebugInfo::new();
+        info.triangleIndex = self.id.triangle;
+        info
+    }
+}
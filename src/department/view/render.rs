@@ -6,12 +6,12 @@ use crossbeam_channel::Sender;
 use crate::department::common::constant::{HEIGHT, WIDTH};
 use crate::department::model::object_loader::ObjectLoader;
 use crate::department::model::triangle_resources::TriangleResources;
-use crate::department::preview::homo_transformation::{HomoTransform, Transform};
-use crate::department::preview::matrix::HMat;
+use crate::department::preview::homo_transformation::Transform;
 use crate::department::preview::position::Pos3;
 use crate::department::preview::vector::Vector3;
 use crate::department::types::msg::TransferMsg;
 use crate::department::view::camera::Camera;
+use crate::department::view::camera_trait::CameraTrait;
 
 /// Representation of the application state. In this example, a box will bounce around the screen.
 struct Render {
@@ -23,20 +23,23 @@ struct Render {
 impl Render {
     /// Create a new `World` instance that can draw a moving box.
     fn new() -> Self {
-        let res = ObjectLoader::load_triangle_resources("./res/Link/link_adult.obj");
+        let mut res = ObjectLoader::load_triangle_resources("./res/Link/link_adult.obj")
+            .expect("the bundled Link model should load");
+        res.normalize();
 
-        let mut cameras = Vec::new();
-        cameras.push(
-            Camera::new(
-                45., 
-                WIDTH as f32 / HEIGHT as f32, 
-                -5., 
-                -50., 
-                Pos3::from_xyz(0., 0., 10.,),
-                Vector3::from_xyz(0., 0., -1.),
-                Vector3::from_xyz(0., -1., 0.)
-            )
+        let mut camera = Camera::new(
+            45., 
+            WIDTH as f32 / HEIGHT as f32, 
+            -0.1, 
+            -1000., 
+            Pos3::from_xyz(0., 0., 10.,),
+            Vector3::from_xyz(0., 0., -1.),
+            Vector3::from_xyz(0., -1., 0.)
         );
+        camera.frame(res.bounds.center(), res.bounds.radius());
+
+        let mut cameras = Vec::new();
+        cameras.push(camera);
 
         Self {
             cameras: cameras,
@@ -54,27 +57,8 @@ impl Render {
     ///
     /// Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
     fn draw(&mut self) -> Vec<u8> {
-        // link_adult is too big
-        let scale = HomoTransform::scale((0.05, 0.05, 0.05));
-        // let mut scale = HomoTransform::identity_matrix();
-
-
-        let _move_origin = HomoTransform::translation((-0., -0., 0.));
-        let rotate = Transform::rotation_mat(&Vector3::from_xyz(0.,1.,0.), self.theta);
-        // let _move_back = HomoTransform::translation((0., 0., -0.0));
-        // let _mat = _move_origin * rotate * _move_back;
-        // let _mat = HomoTransform::identity_matrix();
-        let _mat = scale * rotate;
-
-        let _move = HMat::from_vec(vec![
-            1., 0., 0., 0.,
-            0., 1., 0., 0.,
-            0., 0., 1., 0.,
-            0., -3., -5.0, 1.,
-        ]);
-
-        //let _mat = _mat * scale;
-        let _mat = _mat * _move;
+        // the model is normalized around the origin, spinning it in place is enough
+        let _mat = Transform::rotation_mat(&Vector3::from_xyz(0.,1.,0.), self.theta);
 
         let _buf = self.cameras[0].render_triangle_obejct(WIDTH, HEIGHT, &self.resources, &_mat);
 
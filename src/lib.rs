@@ -2,6 +2,7 @@
 #![feature(generic_const_exprs)]
 #![feature(iter_array_chunks)]
 #![feature(is_some_and)]
+#![feature(portable_simd)]
 
 pub mod department;
 pub mod util;
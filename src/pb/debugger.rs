@@ -250,21 +250,1106 @@ impl ::protobuf::reflect::ProtobufValue for Void {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:proto.debugger.Vec3)
+pub struct Vec3 {
+    // message fields
+    // @@protoc_insertion_point(field:proto.debugger.Vec3.x)
+    pub x: f32,
+    // @@protoc_insertion_point(field:proto.debugger.Vec3.y)
+    pub y: f32,
+    // @@protoc_insertion_point(field:proto.debugger.Vec3.z)
+    pub z: f32,
+    // special fields
+    // @@protoc_insertion_point(special_field:proto.debugger.Vec3.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a Vec3 {
+    fn default() -> &'a Vec3 {
+        <Vec3 as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Vec3 {
+    pub fn new() -> Vec3 {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "x",
+            |m: &Vec3| { &m.x },
+            |m: &mut Vec3| { &mut m.x },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "y",
+            |m: &Vec3| { &m.y },
+            |m: &mut Vec3| { &mut m.y },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "z",
+            |m: &Vec3| { &m.z },
+            |m: &mut Vec3| { &mut m.z },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<Vec3>(
+            "Vec3",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for Vec3 {
+    const NAME: &'static str = "Vec3";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.x = is.read_float()?;
+                },
+                21 => {
+                    self.y = is.read_float()?;
+                },
+                29 => {
+                    self.z = is.read_float()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.x != 0. {
+            my_size += 1 + 4;
+        }
+        if self.y != 0. {
+            my_size += 1 + 4;
+        }
+        if self.z != 0. {
+            my_size += 1 + 4;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.x != 0. {
+            os.write_float(1, self.x)?;
+        }
+        if self.y != 0. {
+            os.write_float(2, self.y)?;
+        }
+        if self.z != 0. {
+            os.write_float(3, self.z)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> Vec3 {
+        Vec3::new()
+    }
+
+    fn clear(&mut self) {
+        self.x = 0.;
+        self.y = 0.;
+        self.z = 0.;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static Vec3 {
+        static instance: Vec3 = Vec3 {
+            x: 0.,
+            y: 0.,
+            z: 0.,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for Vec3 {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("Vec3").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Vec3 {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:proto.debugger.CameraState)
+pub struct CameraState {
+    // message fields
+    // @@protoc_insertion_point(field:proto.debugger.CameraState.position)
+    pub position: ::protobuf::MessageField<Vec3>,
+    // @@protoc_insertion_point(field:proto.debugger.CameraState.forward)
+    pub forward: ::protobuf::MessageField<Vec3>,
+    // special fields
+    // @@protoc_insertion_point(special_field:proto.debugger.CameraState.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a CameraState {
+    fn default() -> &'a CameraState {
+        <CameraState as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl CameraState {
+    pub fn new() -> CameraState {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, Vec3>(
+            "position",
+            |m: &CameraState| { &m.position },
+            |m: &mut CameraState| { &mut m.position },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, Vec3>(
+            "forward",
+            |m: &CameraState| { &m.forward },
+            |m: &mut CameraState| { &mut m.forward },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<CameraState>(
+            "CameraState",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for CameraState {
+    const NAME: &'static str = "CameraState";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.position)?;
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.forward)?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let Some(v) = self.position.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        if let Some(v) = self.forward.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let Some(v) = self.position.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        }
+        if let Some(v) = self.forward.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> CameraState {
+        CameraState::new()
+    }
+
+    fn clear(&mut self) {
+        self.position.clear();
+        self.forward.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static CameraState {
+        static instance: CameraState = CameraState {
+            position: ::protobuf::MessageField::none(),
+            forward: ::protobuf::MessageField::none(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for CameraState {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("CameraState").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for CameraState {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for CameraState {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:proto.debugger.FrameStats)
+pub struct FrameStats {
+    // message fields
+    // @@protoc_insertion_point(field:proto.debugger.FrameStats.frameCount)
+    pub frameCount: u64,
+    // @@protoc_insertion_point(field:proto.debugger.FrameStats.fps)
+    pub fps: f32,
+    // @@protoc_insertion_point(field:proto.debugger.FrameStats.frameTimeMs)
+    pub frameTimeMs: f32,
+    // @@protoc_insertion_point(field:proto.debugger.FrameStats.triangleCount)
+    pub triangleCount: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:proto.debugger.FrameStats.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a FrameStats {
+    fn default() -> &'a FrameStats {
+        <FrameStats as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "frameCount",
+            |m: &FrameStats| { &m.frameCount },
+            |m: &mut FrameStats| { &mut m.frameCount },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "fps",
+            |m: &FrameStats| { &m.fps },
+            |m: &mut FrameStats| { &mut m.fps },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "frameTimeMs",
+            |m: &FrameStats| { &m.frameTimeMs },
+            |m: &mut FrameStats| { &mut m.frameTimeMs },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "triangleCount",
+            |m: &FrameStats| { &m.triangleCount },
+            |m: &mut FrameStats| { &mut m.triangleCount },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<FrameStats>(
+            "FrameStats",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for FrameStats {
+    const NAME: &'static str = "FrameStats";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.frameCount = is.read_uint64()?;
+                },
+                21 => {
+                    self.fps = is.read_float()?;
+                },
+                29 => {
+                    self.frameTimeMs = is.read_float()?;
+                },
+                32 => {
+                    self.triangleCount = is.read_uint32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.frameCount != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.frameCount);
+        }
+        if self.fps != 0. {
+            my_size += 1 + 4;
+        }
+        if self.frameTimeMs != 0. {
+            my_size += 1 + 4;
+        }
+        if self.triangleCount != 0 {
+            my_size += ::protobuf::rt::uint32_size(4, self.triangleCount);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.frameCount != 0 {
+            os.write_uint64(1, self.frameCount)?;
+        }
+        if self.fps != 0. {
+            os.write_float(2, self.fps)?;
+        }
+        if self.frameTimeMs != 0. {
+            os.write_float(3, self.frameTimeMs)?;
+        }
+        if self.triangleCount != 0 {
+            os.write_uint32(4, self.triangleCount)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> FrameStats {
+        FrameStats::new()
+    }
+
+    fn clear(&mut self) {
+        self.frameCount = 0;
+        self.fps = 0.;
+        self.frameTimeMs = 0.;
+        self.triangleCount = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static FrameStats {
+        static instance: FrameStats = FrameStats {
+            frameCount: 0,
+            fps: 0.,
+            frameTimeMs: 0.,
+            triangleCount: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for FrameStats {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("FrameStats").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for FrameStats {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:proto.debugger.RenderState)
+pub struct RenderState {
+    // message fields
+    // @@protoc_insertion_point(field:proto.debugger.RenderState.debugInfo)
+    pub debugInfo: ::protobuf::MessageField<DebugInfo>,
+    // @@protoc_insertion_point(field:proto.debugger.RenderState.camera)
+    pub camera: ::protobuf::MessageField<CameraState>,
+    // @@protoc_insertion_point(field:proto.debugger.RenderState.shaderMode)
+    pub shaderMode: ::protobuf::EnumOrUnknown<ShaderMode>,
+    // @@protoc_insertion_point(field:proto.debugger.RenderState.stats)
+    pub stats: ::protobuf::MessageField<FrameStats>,
+    // special fields
+    // @@protoc_insertion_point(special_field:proto.debugger.RenderState.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RenderState {
+    fn default() -> &'a RenderState {
+        <RenderState as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RenderState {
+    pub fn new() -> RenderState {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, DebugInfo>(
+            "debugInfo",
+            |m: &RenderState| { &m.debugInfo },
+            |m: &mut RenderState| { &mut m.debugInfo },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, CameraState>(
+            "camera",
+            |m: &RenderState| { &m.camera },
+            |m: &mut RenderState| { &mut m.camera },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "shaderMode",
+            |m: &RenderState| { &m.shaderMode },
+            |m: &mut RenderState| { &mut m.shaderMode },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, FrameStats>(
+            "stats",
+            |m: &RenderState| { &m.stats },
+            |m: &mut RenderState| { &mut m.stats },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RenderState>(
+            "RenderState",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RenderState {
+    const NAME: &'static str = "RenderState";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.debugInfo)?;
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.camera)?;
+                },
+                24 => {
+                    self.shaderMode = is.read_enum_or_unknown()?;
+                },
+                34 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.stats)?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let Some(v) = self.debugInfo.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        if let Some(v) = self.camera.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        if self.shaderMode != ::protobuf::EnumOrUnknown::new(ShaderMode::Lambertian) {
+            my_size += ::protobuf::rt::int32_size(3, self.shaderMode.value());
+        }
+        if let Some(v) = self.stats.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let Some(v) = self.debugInfo.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        }
+        if let Some(v) = self.camera.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        }
+        if self.shaderMode != ::protobuf::EnumOrUnknown::new(ShaderMode::Lambertian) {
+            os.write_enum(3, ::protobuf::EnumOrUnknown::value(&self.shaderMode))?;
+        }
+        if let Some(v) = self.stats.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(4, v, os)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RenderState {
+        RenderState::new()
+    }
+
+    fn clear(&mut self) {
+        self.debugInfo.clear();
+        self.camera.clear();
+        self.shaderMode = ::protobuf::EnumOrUnknown::new(ShaderMode::Lambertian);
+        self.stats.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RenderState {
+        static instance: RenderState = RenderState {
+            debugInfo: ::protobuf::MessageField::none(),
+            camera: ::protobuf::MessageField::none(),
+            shaderMode: ::protobuf::EnumOrUnknown::from_i32(0),
+            stats: ::protobuf::MessageField::none(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RenderState {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RenderState").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RenderState {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RenderState {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:proto.debugger.RenderStateUpdate)
+pub struct RenderStateUpdate {
+    // message fields
+    // @@protoc_insertion_point(field:proto.debugger.RenderStateUpdate.triangleIndex)
+    pub triangleIndex: ::std::option::Option<u32>,
+    // @@protoc_insertion_point(field:proto.debugger.RenderStateUpdate.camera)
+    pub camera: ::protobuf::MessageField<CameraState>,
+    // @@protoc_insertion_point(field:proto.debugger.RenderStateUpdate.shaderMode)
+    pub shaderMode: ::std::option::Option<::protobuf::EnumOrUnknown<ShaderMode>>,
+    // special fields
+    // @@protoc_insertion_point(special_field:proto.debugger.RenderStateUpdate.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RenderStateUpdate {
+    fn default() -> &'a RenderStateUpdate {
+        <RenderStateUpdate as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RenderStateUpdate {
+    pub fn new() -> RenderStateUpdate {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_option_accessor::<_, _>(
+            "triangleIndex",
+            |m: &RenderStateUpdate| { &m.triangleIndex },
+            |m: &mut RenderStateUpdate| { &mut m.triangleIndex },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, CameraState>(
+            "camera",
+            |m: &RenderStateUpdate| { &m.camera },
+            |m: &mut RenderStateUpdate| { &mut m.camera },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_option_accessor::<_, _>(
+            "shaderMode",
+            |m: &RenderStateUpdate| { &m.shaderMode },
+            |m: &mut RenderStateUpdate| { &mut m.shaderMode },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RenderStateUpdate>(
+            "RenderStateUpdate",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RenderStateUpdate {
+    const NAME: &'static str = "RenderStateUpdate";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.triangleIndex = ::std::option::Option::Some(is.read_uint32()?);
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.camera)?;
+                },
+                24 => {
+                    self.shaderMode = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let Some(v) = self.triangleIndex {
+            my_size += ::protobuf::rt::uint32_size(1, v);
+        }
+        if let Some(v) = self.camera.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        if let Some(v) = self.shaderMode {
+            my_size += ::protobuf::rt::int32_size(3, v.value());
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let Some(v) = self.triangleIndex {
+            os.write_uint32(1, v)?;
+        }
+        if let Some(v) = self.camera.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        }
+        if let Some(v) = self.shaderMode {
+            os.write_enum(3, ::protobuf::EnumOrUnknown::value(&v))?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RenderStateUpdate {
+        RenderStateUpdate::new()
+    }
+
+    fn clear(&mut self) {
+        self.triangleIndex = ::std::option::Option::None;
+        self.camera.clear();
+        self.shaderMode = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RenderStateUpdate {
+        static instance: RenderStateUpdate = RenderStateUpdate {
+            triangleIndex: ::std::option::Option::None,
+            camera: ::protobuf::MessageField::none(),
+            shaderMode: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RenderStateUpdate {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RenderStateUpdate").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RenderStateUpdate {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RenderStateUpdate {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:proto.debugger.DebugPacket)
+pub struct DebugPacket {
+    // message fields
+    // @@protoc_insertion_point(field:proto.debugger.DebugPacket.method)
+    pub method: ::protobuf::EnumOrUnknown<DebugMethod>,
+    // @@protoc_insertion_point(field:proto.debugger.DebugPacket.payload)
+    pub payload: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:proto.debugger.DebugPacket.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a DebugPacket {
+    fn default() -> &'a DebugPacket {
+        <DebugPacket as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DebugPacket {
+    pub fn new() -> DebugPacket {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "method",
+            |m: &DebugPacket| { &m.method },
+            |m: &mut DebugPacket| { &mut m.method },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "payload",
+            |m: &DebugPacket| { &m.payload },
+            |m: &mut DebugPacket| { &mut m.payload },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<DebugPacket>(
+            "DebugPacket",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for DebugPacket {
+    const NAME: &'static str = "DebugPacket";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.method = is.read_enum_or_unknown()?;
+                },
+                18 => {
+                    self.payload = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.method != ::protobuf::EnumOrUnknown::new(DebugMethod::OnGetDebugInfo) {
+            my_size += ::protobuf::rt::int32_size(1, self.method.value());
+        }
+        if !self.payload.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(2, &self.payload);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.method != ::protobuf::EnumOrUnknown::new(DebugMethod::OnGetDebugInfo) {
+            os.write_enum(1, ::protobuf::EnumOrUnknown::value(&self.method))?;
+        }
+        if !self.payload.is_empty() {
+            os.write_bytes(2, &self.payload)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> DebugPacket {
+        DebugPacket::new()
+    }
+
+    fn clear(&mut self) {
+        self.method = ::protobuf::EnumOrUnknown::new(DebugMethod::OnGetDebugInfo);
+        self.payload.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static DebugPacket {
+        static instance: DebugPacket = DebugPacket {
+            method: ::protobuf::EnumOrUnknown::from_i32(0),
+            payload: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for DebugPacket {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("DebugPacket").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for DebugPacket {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DebugPacket {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:proto.debugger.ShaderMode)
+pub enum ShaderMode {
+    // @@protoc_insertion_point(enum_value:proto.debugger.ShaderMode.Lambertian)
+    Lambertian = 0,
+    // @@protoc_insertion_point(enum_value:proto.debugger.ShaderMode.Unlit)
+    Unlit = 1,
+    // @@protoc_insertion_point(enum_value:proto.debugger.ShaderMode.Normal)
+    Normal = 2,
+}
+
+impl ::protobuf::Enum for ShaderMode {
+    const NAME: &'static str = "ShaderMode";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<ShaderMode> {
+        match value {
+            0 => ::std::option::Option::Some(ShaderMode::Lambertian),
+            1 => ::std::option::Option::Some(ShaderMode::Unlit),
+            2 => ::std::option::Option::Some(ShaderMode::Normal),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [ShaderMode] = &[
+        ShaderMode::Lambertian,
+        ShaderMode::Unlit,
+        ShaderMode::Normal,
+    ];
+}
+
+impl ::protobuf::EnumFull for ShaderMode {
+    fn enum_descriptor() -> ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().enum_by_package_relative_name("ShaderMode").unwrap()).clone()
+    }
+
+    fn descriptor(&self) -> ::protobuf::reflect::EnumValueDescriptor {
+        let index = *self as usize;
+        Self::enum_descriptor().value_by_index(index)
+    }
+}
+
+impl ::std::default::Default for ShaderMode {
+    fn default() -> Self {
+        ShaderMode::Lambertian
+    }
+}
+
+impl ShaderMode {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new::<ShaderMode>("ShaderMode")
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:proto.debugger.DebugMethod)
+pub enum DebugMethod {
+    // @@protoc_insertion_point(enum_value:proto.debugger.DebugMethod.OnGetDebugInfo)
+    OnGetDebugInfo = 0,
+    // @@protoc_insertion_point(enum_value:proto.debugger.DebugMethod.GetRenderState)
+    GetRenderState = 1,
+    // @@protoc_insertion_point(enum_value:proto.debugger.DebugMethod.SetRenderState)
+    SetRenderState = 2,
+}
+
+impl ::protobuf::Enum for DebugMethod {
+    const NAME: &'static str = "DebugMethod";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<DebugMethod> {
+        match value {
+            0 => ::std::option::Option::Some(DebugMethod::OnGetDebugInfo),
+            1 => ::std::option::Option::Some(DebugMethod::GetRenderState),
+            2 => ::std::option::Option::Some(DebugMethod::SetRenderState),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [DebugMethod] = &[
+        DebugMethod::OnGetDebugInfo,
+        DebugMethod::GetRenderState,
+        DebugMethod::SetRenderState,
+    ];
+}
+
+impl ::protobuf::EnumFull for DebugMethod {
+    fn enum_descriptor() -> ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().enum_by_package_relative_name("DebugMethod").unwrap()).clone()
+    }
+
+    fn descriptor(&self) -> ::protobuf::reflect::EnumValueDescriptor {
+        let index = *self as usize;
+        Self::enum_descriptor().value_by_index(index)
+    }
+}
+
+impl ::std::default::Default for DebugMethod {
+    fn default() -> Self {
+        DebugMethod::OnGetDebugInfo
+    }
+}
+
+impl DebugMethod {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new::<DebugMethod>("DebugMethod")
+    }
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x11pb/debugger.proto\x12\x0eproto.debugger\"1\n\tDebugInfo\x12$\n\rtr\
-    iangleIndex\x18\x01\x20\x01(\rR\rtriangleIndex\"\x06\n\x04Void2P\n\x0bDe\
-    bugServer\x12A\n\x0eonGetDebugInfo\x12\x19.proto.debugger.DebugInfo\x1a\
-    \x14.proto.debugger.VoidJ\xd2\x01\n\x06\x12\x04\0\0\x0e\x01\n\x08\n\x01\
-    \x0c\x12\x03\0\0\x12\n\x08\n\x01\x02\x12\x03\x01\0\x17\n\n\n\x02\x04\0\
-    \x12\x04\x03\0\x05\x01\n\n\n\x03\x04\0\x01\x12\x03\x03\x08\x11\n\x0b\n\
-    \x04\x04\0\x02\0\x12\x03\x04\x04\x1d\n\x0c\n\x05\x04\0\x02\0\x05\x12\x03\
-    \x04\x04\n\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\x04\x0b\x18\n\x0c\n\x05\
-    \x04\0\x02\0\x03\x12\x03\x04\x1b\x1c\n\n\n\x02\x04\x01\x12\x04\x07\0\t\
-    \x01\n\n\n\x03\x04\x01\x01\x12\x03\x07\x08\x0c\n\n\n\x02\x06\0\x12\x04\
-    \x0c\0\x0e\x01\n\n\n\x03\x06\0\x01\x12\x03\x0c\x08\x13\n\x0b\n\x04\x06\0\
-    \x02\0\x12\x03\r\x040\n\x0c\n\x05\x06\0\x02\0\x01\x12\x03\r\x08\x16\n\
-    \x0c\n\x05\x06\0\x02\0\x02\x12\x03\r\x17\x20\n\x0c\n\x05\x06\0\x02\0\x03\
-    \x12\x03\r*.b\x06proto3\
+    iangleIndex\x18\x01\x20\x01(\rR\rtriangleIndex\"\x06\n\x04Void\"0\n\x04V\
+    ec3\x12\x0c\n\x01x\x18\x01\x20\x01(\x02R\x01x\x12\x0c\n\x01y\x18\x02\x20\
+    \x01(\x02R\x01y\x12\x0c\n\x01z\x18\x03\x20\x01(\x02R\x01z\"o\n\x0bCamera\
+    State\x120\n\x08position\x18\x01\x20\x01(\x0b2\x14.proto.debugger.Vec3R\
+    \x08position\x12.\n\x07forward\x18\x02\x20\x01(\x0b2\x14.proto.debugger.\
+    Vec3R\x07forward\"\x86\x01\n\nFrameStats\x12\x1e\n\nframeCount\x18\x01\
+    \x20\x01(\x04R\nframeCount\x12\x10\n\x03fps\x18\x02\x20\x01(\x02R\x03fps\
+    \x12\x20\n\x0bframeTimeMs\x18\x03\x20\x01(\x02R\x0bframeTimeMs\x12$\n\rt\
+    riangleCount\x18\x04\x20\x01(\rR\rtriangleCount\"\xe9\x01\n\x0bRenderSta\
+    te\x127\n\tdebugInfo\x18\x01\x20\x01(\x0b2\x19.proto.debugger.DebugInfoR\
+    \tdebugInfo\x123\n\x06camera\x18\x02\x20\x01(\x0b2\x1b.proto.debugger.Ca\
+    meraStateR\x06camera\x12:\n\nshaderMode\x18\x03\x20\x01(\x0e2\x1a.proto.\
+    debugger.ShaderModeR\nshaderMode\x120\n\x05stats\x18\x04\x20\x01(\x0b2\
+    \x1a.proto.debugger.FrameStatsR\x05stats\"\xd5\x01\n\x11RenderStateUpdat\
+    e\x12)\n\rtriangleIndex\x18\x01\x20\x01(\rH\0R\rtriangleIndex\x88\x01\
+    \x01\x123\n\x06camera\x18\x02\x20\x01(\x0b2\x1b.proto.debugger.CameraSta\
+    teR\x06camera\x12?\n\nshaderMode\x18\x03\x20\x01(\x0e2\x1a.proto.debugge\
+    r.ShaderModeH\x01R\nshaderMode\x88\x01\x01B\x10\n\x0e_triangleIndexB\r\n\
+    \x0b_shaderMode\"\\\n\x0bDebugPacket\x123\n\x06method\x18\x01\x20\x01(\
+    \x0e2\x1b.proto.debugger.DebugMethodR\x06method\x12\x18\n\x07payload\x18\
+    \x02\x20\x01(\x0cR\x07payload*3\n\nShaderMode\x12\x0e\n\nLambertian\x10\
+    \0\x12\t\n\x05Unlit\x10\x01\x12\n\n\x06Normal\x10\x02*I\n\x0bDebugMethod\
+    \x12\x12\n\x0eOnGetDebugInfo\x10\0\x12\x12\n\x0eGetRenderState\x10\x01\
+    \x12\x12\n\x0eSetRenderState\x10\x022\xe7\x01\n\x0bDebugServer\x12A\n\
+    \x0eonGetDebugInfo\x12\x19.proto.debugger.DebugInfo\x1a\x14.proto.debugg\
+    er.Void\x12C\n\x0egetRenderState\x12\x14.proto.debugger.Void\x1a\x1b.pro\
+    to.debugger.RenderState\x12P\n\x0esetRenderState\x12!.proto.debugger.Ren\
+    derStateUpdate\x1a\x1b.proto.debugger.RenderStateb\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -282,10 +1367,18 @@ pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
     file_descriptor.get(|| {
         let generated_file_descriptor = generated_file_descriptor_lazy.get(|| {
             let mut deps = ::std::vec::Vec::with_capacity(0);
-            let mut messages = ::std::vec::Vec::with_capacity(2);
+            let mut messages = ::std::vec::Vec::with_capacity(8);
             messages.push(DebugInfo::generated_message_descriptor_data());
             messages.push(Void::generated_message_descriptor_data());
-            let mut enums = ::std::vec::Vec::with_capacity(0);
+            messages.push(Vec3::generated_message_descriptor_data());
+            messages.push(CameraState::generated_message_descriptor_data());
+            messages.push(FrameStats::generated_message_descriptor_data());
+            messages.push(RenderState::generated_message_descriptor_data());
+            messages.push(RenderStateUpdate::generated_message_descriptor_data());
+            messages.push(DebugPacket::generated_message_descriptor_data());
+            let mut enums = ::std::vec::Vec::with_capacity(2);
+            enums.push(ShaderMode::generated_enum_descriptor_data());
+            enums.push(DebugMethod::generated_enum_descriptor_data());
             ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
                 file_descriptor_proto(),
                 deps,
@@ -1,6 +1,60 @@
 use clap::Parser;
 use lazy_static::lazy_static;
 
+use crate::department::common::constant;
+
+
+/// which cpu renderer draws the frame when the gpu is not used
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Raster,
+    /// whitted ray tracer, a slow reference image for the rasterizer
+    Ray,
+    /// progressive path tracer, converges over frames up to `--samples`
+    Path,
+}
+
+/// what `-r` writes for an animated model
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// one looping gif, dognut.gif unless --output says otherwise
+    Gif,
+    /// numbered pngs next to --output, dognut_0001.png, dognut_0002.png, ... by default
+    Png,
+}
+
+/// how the terminal shows a frame
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TermMode {
+    /// one glyph of --ramp per pixel, pixels are twice as tall as wide
+    Glyph,
+    /// `▀` with separate foreground and background colour, two square pixels per cell
+    HalfBlock,
+    /// braille dots, 2x4 square pixels per cell in one colour. for monochrome views
+    Braille,
+}
+
+/// glyphs of --term-mode glyph, dark to bright
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ramp {
+    /// `.,-~:;=!*#$@`
+    Standard,
+    /// ` .:-=+*#%@`
+    Classic,
+    /// 70 characters, for large terminals
+    Detailed,
+    /// ` ░▒▓█`
+    Blocks,
+}
+
+/// which braille dots are lit
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dither {
+    /// pixels brighter than half
+    Threshold,
+    /// 4x4 bayer matrix, keeps shading as dot density
+    Ordered,
+}
 
 /// render a object to window or terminal
 #[derive(Parser, Debug)]
@@ -16,13 +70,69 @@ pub struct Args {
 
     #[arg(short, long, default_value_t=false)]
     pub split: bool,
-    /// object path to load, only support triangulated obj.
+    /// obj, gltf, glb, stl or ply model to load, textures and mtl are looked up next to it
     #[arg(long, default_value_t=String::from("./res/merry_tree/tree3/ctree3.obj"))]
     pub obj_path: String,
 
-    /// only render a jpeg picture
+    /// serve render state to `debug_client` on the debug port
+    #[arg(long, default_value_t=false)]
+    pub debug_server: bool,
+
+    /// address the debug service listens on, anyone who can reach it can drive the renderer
+    #[arg(long, default_value_t=constant::DEBUG_HOST.to_string())]
+    pub debug_host: String,
+
+    /// only render a png picture with the cpu backend
     #[arg(short, default_value_t=false)]
     pub render_a_picture: bool,
+
+    #[arg(long, value_enum, default_value_t=Backend::Raster)]
+    pub backend: Backend,
+
+    /// samples per pixel the path tracer stops at, also what `-r` renders with
+    #[arg(long, default_value_t=64)]
+    pub samples: u32,
+
+    /// recentre the model and scale it to unit size, `--normalize false` keeps its own units
+    #[arg(long, default_value_t=true, action=clap::ArgAction::Set)]
+    pub normalize: bool,
+
+    /// write the loaded model to this .stl or .ply file and exit, only --normalize is applied
+    #[arg(long)]
+    pub export: Option<String>,
+
+    /// export as ascii instead of binary
+    #[arg(long, default_value_t=false)]
+    pub export_ascii: bool,
+
+    /// playback rate of a numbered obj sequence (tree_0001.obj, tree_0002.obj, ...), frames in between are blended
+    #[arg(long, default_value_t=24.)]
+    pub sequence_fps: f32,
+
+    /// frame rate `-r` renders an obj sequence at, the playback rate when not given
+    #[arg(long)]
+    pub export_fps: Option<f32>,
+
+    #[arg(long, value_enum, default_value_t=AnimationFormat::Gif)]
+    pub animation_format: AnimationFormat,
+
+    /// where `-r` writes the picture or animation, dognut.png or dognut.gif when not given
+    #[arg(long)]
+    pub output: Option<String>,
+
+    #[arg(long, value_enum, default_value_t=TermMode::Glyph)]
+    pub term_mode: TermMode,
+
+    #[arg(long, value_enum, default_value_t=Ramp::Standard)]
+    pub ramp: Ramp,
+
+    /// how --term-mode braille picks the lit dots
+    #[arg(long, value_enum, default_value_t=Dither::Ordered)]
+    pub dither: Dither,
+
+    /// reload the model, its mtl and textures and res/shaders/*.wgsl when they change on disk
+    #[arg(long, default_value_t=false)]
+    pub watch: bool,
 }
 
 
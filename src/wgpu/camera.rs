@@ -73,6 +73,23 @@ impl camera_trait::CameraTrait for Camera {
     fn update_projection(&mut self, width: u32, height: u32) {
         self.proj.resize(width, height);
     }
+
+    fn pose(&self) -> ([f32; 3], [f32; 3]) {
+        let forward = self.forward();
+        (self.position.into(), forward.into())
+    }
+
+    // there is no roll, so the forward vector maps back onto yaw and pitch
+    fn set_pose(&mut self, position: [f32; 3], forward: [f32; 3]) {
+        self.position = Point3::from(position);
+        let forward = Vector3::from(forward).normalize();
+        self.yaw = Rad(forward.z.atan2(forward.x));
+        self.pitch = Rad(forward.y.clamp(-1.0, 1.0).asin().clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2));
+    }
+
+    fn fov_y(&self) -> f32 {
+        self.proj.fovy.0
+    }
 }
 
 impl Camera {
@@ -98,14 +115,18 @@ impl Camera {
         self.proj = proj;
     }
 
+    pub fn forward(&self) -> Vector3<f32> {
+        Vector3::new(
+            self.yaw.0.cos(),
+            self.pitch.0.sin(),
+            self.yaw.0.sin(),
+        ).normalize()
+    }
+
     pub fn calc_matrix(&self) -> Matrix4<f32> {
         Matrix4::look_to_rh(
             self.position,
-            Vector3::new(
-                self.yaw.0.cos(),
-                self.pitch.0.sin(),
-                self.yaw.0.sin(),
-            ).normalize(),
+            self.forward(),
             Vector3::unit_y(),
         )
     }
@@ -6,9 +6,13 @@ pub mod instance;
 mod light;
 pub mod camera;
 pub mod snow_flake;
+mod picking;
 
 pub struct Runner{}
 
+// where hot reload reads the wgsl from, the build embeds the same files
+pub const SHADER_DIR: &str = "./res/shaders";
+
 use pixels::wgpu;
 
 fn create_render_pipeline(
@@ -1,6 +1,12 @@
 use std::ops::Range;
+use std::sync::Arc;
 
 use super::texture;
+use crate::department::model::animation::Rig;
+use crate::department::model::auto_fit::{ModelBounds, UnitFit};
+use crate::department::model::lod::level_for;
+use crate::department::model::obj_sequence::VertexAnimation;
+use crate::department::model::skeleton::Mat4;
 use pixels::wgpu;
 
 pub trait Vertex {
@@ -13,6 +19,9 @@ pub struct ModelVertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
     pub normal: [f32; 3],
+    // up to four joints moving the vertex, all weights 0 leaves it in place
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
 }
 
 impl Vertex for ModelVertex {
@@ -37,6 +46,16 @@ impl Vertex for ModelVertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -54,11 +73,79 @@ pub struct Mesh {
     pub index_buffer: wgpu::Buffer,
     pub num_elements: u32,
     pub material: usize,
+    // cpu copies of the uploaded data, used for picking
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+    // where every detail level sits in the buffers, 0 is the full mesh
+    pub lods: Vec<LodRange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LodRange {
+    pub indices: Range<u32>,
+    pub base_vertex: i32,
+}
+
+impl Mesh {
+    // meshes too small to simplify have fewer levels, they stay at their coarsest
+    pub fn lod_range(&self, level: usize) -> &LodRange {
+        &self.lods[level.min(self.lods.len() - 1)]
+    }
 }
 
 pub struct Model {
     pub meshes: Vec<Mesh>,
     pub materials: Vec<Material>,
+    // where the camera has to look to see all of it
+    pub bounds: ModelBounds,
+    // detail level the next draw uses, picked from the projected size every frame
+    pub lod: usize,
+    // skeleton and clips of a skinned model
+    pub rig: Option<Arc<Rig>>,
+    // how the positions were normalized, the skinning matrices have to follow
+    pub fit: Option<UnitFit>,
+    // frames of a numbered obj sequence, one model per mesh
+    pub sequence: Option<VertexAnimation>,
+}
+
+impl Model {
+    // rewrites the vertices of every mesh to where the sequence has them `time` seconds in,
+    // sequences are uploaded without detail levels so that is the whole buffer
+    pub fn pose_sequence(&mut self, queue: &wgpu::Queue, time: f32) {
+        let Some(sequence) = &self.sequence else {
+            return;
+        };
+        for (i, mesh) in self.meshes.iter_mut().enumerate() {
+            let (positions, normals) = sequence.sample(i, time);
+            for (v, (p, n)) in mesh.vertices.iter_mut().zip(positions.iter().zip(&normals)) {
+                v.position = *p;
+                v.normal = *n;
+            }
+            queue.write_buffer(&mesh.vertex_buffer, 0, bytemuck::cast_slice(&mesh.vertices));
+        }
+    }
+
+    // matrices of an `AnimationPlayer` for `rig`, in the space the uploaded positions are in
+    pub fn fitted_joints(&self, matrices: &[Mat4]) -> Vec<Mat4> {
+        match &self.fit {
+            Some(fit) => matrices.iter().map(|m| fit.conjugate(m)).collect(),
+            None => matrices.to_vec(),
+        }
+    }
+
+    // the level counts of all meshes together decide, so the parts don't drift apart
+    pub fn select_lod(&self, projected_radius: f32) -> usize {
+        let levels = self.meshes.iter().map(|m| m.lods.len()).max().unwrap_or(1);
+        let counts: Vec<usize> = (0..levels)
+            .map(|level| {
+                self.meshes
+                    .iter()
+                    .map(|m| m.lod_range(level).indices.len() / 3)
+                    .sum()
+            })
+            .collect();
+        level_for(&counts, projected_radius)
+    }
 }
 
 pub trait DrawModel<'a> {
@@ -77,6 +164,14 @@ pub trait DrawModel<'a> {
         camera_bind_group: &'a wgpu::BindGroup,
         //light_bind_group: &'a wgpu::BindGroup,
     );
+    fn draw_mesh_lod_instanced(
+        &mut self,
+        mesh: &'a Mesh,
+        level: usize,
+        material: &'a Material,
+        instances: Range<u32>,
+        camera_bind_group: &'a wgpu::BindGroup,
+    );
 
     fn draw_model(
         &mut self,
@@ -115,12 +210,24 @@ where
         camera_bind_group: &'b wgpu::BindGroup,
         // light_bind_group: &'a wgpu::BindGroup
     ) {
+        self.draw_mesh_lod_instanced(mesh, 0, material, instances, camera_bind_group);
+    }
+
+    fn draw_mesh_lod_instanced(
+        &mut self,
+        mesh: &'b Mesh,
+        level: usize,
+        material: &'b Material,
+        instances: Range<u32>,
+        camera_bind_group: &'b wgpu::BindGroup,
+    ) {
+        let lod = mesh.lod_range(level);
         self.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
         self.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
         self.set_bind_group(0, &material.bind_group, &[]);
         self.set_bind_group(1, camera_bind_group, &[]);
         //self.set_bind_group(2, light_bind_group, &[]);
-        self.draw_indexed(0..mesh.num_elements, 0, instances);
+        self.draw_indexed(lod.indices.clone(), lod.base_vertex, instances);
     }
 
     fn draw_model(
@@ -141,8 +248,9 @@ where
     ) {
         for mesh in &model.meshes {
             let material = &model.materials[mesh.material];
-            self.draw_mesh_instanced(
+            self.draw_mesh_lod_instanced(
                 mesh,
+                model.lod,
                 material,
                 instances.clone(),
                 camera_bind_group,
@@ -0,0 +1,468 @@
+use std::{iter, mem};
+
+use cgmath::{InnerSpace, Matrix3, Matrix4, SquareMatrix, Vector2, Vector3, Vector4};
+use pixels::wgpu;
+use pixels::wgpu::util::DeviceExt as _;
+
+use super::instance::InstanceRaw;
+use super::model::{self, Vertex};
+use super::texture;
+use crate::department::preview::vector::{Vec2, Vector3 as DnVector3};
+use crate::department::view::picking::{PickId, PickResult};
+
+// the packed id and the instance it was drawn for
+pub const PICK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Uint;
+
+// ids are packed as mesh << 24 | triangle, plus one so that 0 stays background
+const TRIANGLE_BITS: u32 = 24;
+const TRIANGLE_MASK: u32 = (1 << TRIANGLE_BITS) - 1;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PickVertex {
+    position: [f32; 3],
+    id: u32,
+}
+
+impl Vertex for PickVertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<PickVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+            ],
+        }
+    }
+}
+
+fn encode_id(mesh: usize, triangle: usize) -> u32 {
+    ((mesh as u32) << TRIANGLE_BITS | (triangle as u32 & TRIANGLE_MASK)) + 1
+}
+
+fn decode_id(raw: u32) -> Option<PickId> {
+    if raw == 0 {
+        return None;
+    }
+    let raw = raw - 1;
+    Some(PickId::new(raw >> TRIANGLE_BITS, raw & TRIANGLE_MASK))
+}
+
+pub struct PickingSystem {
+    // one de-indexed buffer per mesh, every vertex carries the id of its triangle
+    pick_buffers: Vec<(wgpu::Buffer, u32)>,
+    layout: wgpu::PipelineLayout,
+    pick_pipeline: wgpu::RenderPipeline,
+    highlight_pipeline: wgpu::RenderPipeline,
+    highlight_buffer: wgpu::Buffer,
+    pub highlight: Option<PickId>,
+    highlight_instance: u32,
+}
+
+impl PickingSystem {
+    pub fn new(
+        device: &wgpu::Device,
+        obj_model: &model::Model,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let pick_buffers = Self::create_pick_buffers(device, obj_model);
+
+        let highlight_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Highlight Vertex Buffer"),
+            size: (mem::size_of::<PickVertex>() * 3) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Picking Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../res/shaders/picking.wgsl").into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Picking Pipeline Layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let (pick_pipeline, highlight_pipeline) = create_pipelines(device, &layout, &shader);
+
+        Self {
+            pick_buffers,
+            layout,
+            pick_pipeline,
+            highlight_pipeline,
+            highlight_buffer,
+            highlight: None,
+            highlight_instance: 0,
+        }
+    }
+
+    // a reloaded model, whatever was highlighted belonged to the old one
+    pub fn set_model(&mut self, device: &wgpu::Device, obj_model: &model::Model) {
+        self.pick_buffers = Self::create_pick_buffers(device, obj_model);
+        self.highlight = None;
+    }
+
+    // pick and highlight pipelines from an edited picking.wgsl, see `set_pipelines`
+    pub fn create_pipelines(
+        &self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+    ) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        create_pipelines(device, &self.layout, shader)
+    }
+
+    pub fn set_pipelines(&mut self, (pick, highlight): (wgpu::RenderPipeline, wgpu::RenderPipeline)) {
+        self.pick_pipeline = pick;
+        self.highlight_pipeline = highlight;
+    }
+
+    fn create_pick_buffers(device: &wgpu::Device, obj_model: &model::Model) -> Vec<(wgpu::Buffer, u32)> {
+        obj_model
+            .meshes
+            .iter()
+            .enumerate()
+            .map(|(mesh_idx, mesh)| {
+                let vertices = mesh
+                    .indices
+                    .iter()
+                    .enumerate()
+                    .map(|(n, i)| PickVertex {
+                        position: mesh.vertices[*i as usize].position,
+                        id: encode_id(mesh_idx, n / 3),
+                    })
+                    .collect::<Vec<_>>();
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&format!("{:?} Pick Buffer", mesh.name)),
+                    contents: bytemuck::cast_slice(&vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+                (buffer, vertices.len() as u32)
+            })
+            .collect()
+    }
+
+    // renders the id target at `size` and reads back the single texel under `cursor`, the id
+    // and the instance it belongs to
+    pub fn pick(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera_bind_group: &wgpu::BindGroup,
+        instance_buffer: &wgpu::Buffer,
+        instance_count: u32,
+        size: (u32, u32),
+        cursor: (u32, u32),
+    ) -> Option<(PickId, u32)> {
+        if cursor.0 >= size.0 || cursor.1 >= size.1 {
+            return None;
+        }
+
+        let texture_desc = wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: PICK_FORMAT,
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("pick_texture"),
+            view_formats: &[PICK_FORMAT],
+        };
+        let texture = device.create_texture(&texture_desc);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_texture = texture::Texture::create_depth_texture(device, size, "pick_depth_texture");
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pick Encoder"),
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Pick Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            render_pass.set_pipeline(&self.pick_pipeline);
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            for (buffer, count) in &self.pick_buffers {
+                render_pass.set_vertex_buffer(0, buffer.slice(..));
+                render_pass.draw(0..*count, 0..instance_count);
+            }
+        }
+
+        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            label: Some("Pick Output Buffer"),
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: cursor.0,
+                    y: cursor.1,
+                    z: 0,
+                },
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: Some(1),
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(iter::once(encoder.finish()));
+
+        let slice = output_buffer.slice(..);
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(rx.receive());
+
+        let (raw, instance) = {
+            let data = slice.get_mapped_range();
+            (
+                u32::from_ne_bytes([data[0], data[1], data[2], data[3]]),
+                u32::from_ne_bytes([data[4], data[5], data[6], data[7]]),
+            )
+        };
+        output_buffer.unmap();
+
+        decode_id(raw).map(|id| (id, instance))
+    }
+
+    // `instance` is the index into the instance buffer the triangle is drawn with
+    pub fn set_highlight(&mut self, queue: &wgpu::Queue, obj_model: &model::Model, id: Option<PickId>, instance: u32) {
+        self.highlight = id;
+        self.highlight_instance = instance;
+        let Some(id) = id else {
+            return;
+        };
+        let Some(mesh) = obj_model.meshes.get(id.object as usize) else {
+            return;
+        };
+        let vertices = (0..3)
+            .map(|k| PickVertex {
+                position: mesh.vertices[mesh.indices[id.triangle as usize * 3 + k] as usize].position,
+                id: 0,
+            })
+            .collect::<Vec<_>>();
+        queue.write_buffer(&self.highlight_buffer, 0, bytemuck::cast_slice(&vertices));
+    }
+
+    pub fn render_highlight<'a>(
+        &'a self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+        camera_bind_group: &'a wgpu::BindGroup,
+        instance_buffer: &'a wgpu::Buffer,
+    ) {
+        if self.highlight.is_none() {
+            return;
+        }
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Highlight Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        render_pass.set_pipeline(&self.highlight_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.highlight_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.draw(0..3, self.highlight_instance..self.highlight_instance + 1);
+    }
+}
+
+// Möller–Trumbore against the picked triangle to recover the exact hit and its barycentrics
+fn ray_triangle(origin: Vector3<f32>, dir: Vector3<f32>, v: &[Vector3<f32>; 3]) -> Option<Vector3<f32>> {
+    let e1 = v[1] - v[0];
+    let e2 = v[2] - v[0];
+    let p = dir.cross(e2);
+    let det = e1.dot(p);
+    if det.abs() < 1e-9 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - v[0];
+    let u = s.dot(p) * inv_det;
+    let q = s.cross(e1);
+    let w = dir.dot(q) * inv_det;
+    Some(Vector3::new(1.0 - u - w, u, w))
+}
+
+// builds the full pick result of `id` for a cursor on a `size` sized target
+pub fn resolve_pick(
+    id: PickId,
+    obj_model: &model::Model,
+    instance: &InstanceRaw,
+    view_proj: [[f32; 4]; 4],
+    size: (u32, u32),
+    cursor: (u32, u32),
+) -> Option<PickResult> {
+    let mesh = obj_model.meshes.get(id.object as usize)?;
+    let corners: Vec<&model::ModelVertex> = (0..3)
+        .map(|k| &mesh.vertices[mesh.indices[id.triangle as usize * 3 + k] as usize])
+        .collect();
+
+    let model_mat = Matrix4::from(instance.model);
+    let normal_mat = Matrix3::from(instance.normal);
+    let world = |p: [f32; 3]| (model_mat * Vector4::new(p[0], p[1], p[2], 1.0)).truncate();
+    let tri = [
+        world(corners[0].position),
+        world(corners[1].position),
+        world(corners[2].position),
+    ];
+
+    let inv_view_proj = Matrix4::from(view_proj).invert()?;
+    let ndc_x = (cursor.0 as f32 + 0.5) / size.0 as f32 * 2.0 - 1.0;
+    let ndc_y = 1.0 - (cursor.1 as f32 + 0.5) / size.1 as f32 * 2.0;
+    let unproject = |z: f32| {
+        let p = inv_view_proj * Vector4::new(ndc_x, ndc_y, z, 1.0);
+        p.truncate() / p.w
+    };
+    let near = unproject(0.0);
+    let far = unproject(1.0);
+    let bar = ray_triangle(near, (far - near).normalize(), &tri)?;
+
+    let position = tri[0] * bar.x + tri[1] * bar.y + tri[2] * bar.z;
+    let n = |c: &model::ModelVertex| Vector3::from(c.normal);
+    let normal = (normal_mat * (n(corners[0]) * bar.x + n(corners[1]) * bar.y + n(corners[2]) * bar.z)).normalize();
+    let t = |c: &model::ModelVertex| Vector2::from(c.tex_coords);
+    let uv = t(corners[0]) * bar.x + t(corners[1]) * bar.y + t(corners[2]) * bar.z;
+
+    Some(PickResult {
+        id,
+        world_position: DnVector3::from_xyz(position.x, position.y, position.z),
+        normal: DnVector3::from_xyz(normal.x, normal.y, normal.z),
+        uv: Vec2::from_xy(uv.x, uv.y),
+    })
+}
+
+fn create_pipelines(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+    let pick_pipeline = create_picking_pipeline(
+        device,
+        layout,
+        shader,
+        ("vs_pick", "fs_pick"),
+        PICK_FORMAT,
+        None,
+        wgpu::CompareFunction::Less,
+        "pick_pipeline",
+    );
+    let highlight_pipeline = create_picking_pipeline(
+        device,
+        layout,
+        shader,
+        ("vs_highlight", "fs_highlight"),
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        Some(wgpu::BlendState::REPLACE),
+        wgpu::CompareFunction::LessEqual,
+        "highlight_pipeline",
+    );
+    (pick_pipeline, highlight_pipeline)
+}
+
+fn create_picking_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    entry_points: (&str, &str),
+    color_format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+    depth_compare: wgpu::CompareFunction,
+    debug_name: &str,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(debug_name),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: entry_points.0,
+            buffers: &[PickVertex::desc(), InstanceRaw::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: entry_points.1,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: texture::Texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
@@ -1,36 +1,104 @@
+use std::borrow::Cow;
 use std::io::{BufReader, Cursor};
+use std::path::Path;
 
 use pixels::wgpu;
 use pixels::wgpu::util::DeviceExt;
 
+use crate::department::model::auto_fit::{ModelBounds, UnitFit};
+use crate::department::model::gltf_loader::load_gltf;
+use crate::department::model::object_loader::{ModelFormat, ObjectLoader};
+use crate::department::model::lod::{LodChain, MeshLods};
+use crate::department::model::obj_sequence::load_sequence;
+use crate::department::model::skeleton::SkinWeights;
+use crate::department::model::texture::{self as texture_cache, TextureRole};
+use crate::department::model::vertex_buffer::VertexBuffer;
+
 use super::{model, texture};
 
-pub async fn load_obj(file_name: &str) -> anyhow::Result<String> {
-    let path = std::path::Path::new(file_name);
+// the christmas tree, served whenever one of its files can't be found on disk
+#[cfg(feature = "bundled_demo")]
+fn bundled(file_name: &str) -> Option<&'static [u8]> {
+    match Path::new(file_name).file_name()?.to_str()? {
+        "ctree3.obj" => Some(include_bytes!("../../res/merry_tree/tree3/ctree3.obj")),
+        "ctree3.mtl" => Some(include_bytes!("../../res/merry_tree/tree3/ctree3.mtl")),
+        "deco0047.png" => Some(include_bytes!("../../res/merry_tree/tree3/deco0047.png")),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "bundled_demo"))]
+fn bundled(_file_name: &str) -> Option<&'static [u8]> {
+    None
+}
+
+fn read_asset(file_name: &str) -> std::io::Result<Cow<'static, [u8]>> {
+    match std::fs::read(file_name) {
+        Ok(data) => Ok(Cow::Owned(data)),
+        Err(e) => match bundled(file_name) {
+            Some(data) => {
+                log::warn!("{} not readable ({}), using the bundled demo scene", file_name, e);
+                Ok(Cow::Borrowed(data))
+            }
+            None => Err(e),
+        },
+    }
+}
 
-    let obj_content = include_str!("../../res/merry_tree/tree3/ctree3.obj");
-    Ok(obj_content.to_string())
+pub async fn load_obj(file_name: &str) -> anyhow::Result<String> {
+    Ok(String::from_utf8(read_asset(file_name)?.into_owned())?)
 }
 
-pub async fn load_mtl() -> anyhow::Result<String> {
-    let obj_content = include_str!("../../res/merry_tree/tree3/ctree3.mtl");
-    Ok(obj_content.to_string())
+pub async fn load_mtl(file_name: &str) -> anyhow::Result<String> {
+    Ok(String::from_utf8(read_asset(file_name)?.into_owned())?)
 }
 
 pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
-    let path = std::path::Path::new(file_name);
-    Ok(std::fs::read(path)?)
+    Ok(read_asset(file_name)?.into_owned())
 }
 
+// files on disk go through the texture cache, only the bundled demo is decoded every time
 pub async fn load_texture(
     file_name: &str,
+    role: TextureRole,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
 ) -> anyhow::Result<texture::Texture> {
-    //let data = load_binary(file_name).await?;
+    let path = Path::new(file_name);
+    if path.is_file() {
+        let mips = texture_cache::load_texture(path, role)?;
+        return texture::Texture::from_mips(device, queue, &mips, Some(file_name));
+    }
+    let data = load_binary(file_name).await?;
+    texture::Texture::from_bytes(device, queue, &data, role, file_name)
+}
+
+fn material_from_texture(
+    name: String,
+    diffuse_texture: texture::Texture,
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+) -> model::Material {
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+            },
+        ],
+        label: None,
+    });
 
-    let data = include_bytes!("../../res/merry_tree/tree3/deco0047.png");
-    texture::Texture::from_bytes(device, queue, data, file_name)
+    model::Material {
+        name,
+        diffuse_texture,
+        bind_group,
+    }
 }
 
 pub async fn load_model(
@@ -38,84 +106,187 @@ pub async fn load_model(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     layout: &wgpu::BindGroupLayout,
+    normalize: bool,
 ) -> anyhow::Result<model::Model> {
-    let file_path = std::path::Path::new(file_name);
-
-    let obj_text = load_obj(file_name).await?;
-    let obj_cursor = Cursor::new(obj_text);
-    let mut obj_reader = BufReader::new(obj_cursor);
-
-    let (models, obj_materials) = tobj::load_obj_buf_async(
-        &mut obj_reader,
-        &tobj::LoadOptions {
-            triangulate: true,
-            single_index: true,
-            ..Default::default()
-        },
-        |p| async move {
-            let mat_text = load_mtl().await.unwrap();
-            tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text)))
-        },
-    )
-    .await?;
+    let file_path = Path::new(file_name);
+    let dir = file_path.parent().unwrap_or(Path::new("."));
 
-    let mut materials = Vec::new();
-    for m in obj_materials? {
-        let texture_file_name = file_path
-            .parent()
-            .unwrap()
-            .join(&m.diffuse_texture.unwrap());
-        let diffuse_texture =
-            load_texture(texture_file_name.to_str().unwrap(), device, queue).await?;
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
-                },
-            ],
-            label: None,
+    let format = ModelFormat::from_path(file_path);
+    if format == ModelFormat::Stl || format == ModelFormat::Ply {
+        // no materials, everything gets the placeholder texture
+        let (models, _, _) = ObjectLoader::load_models(file_path)?;
+        let mut meshes: Vec<MeshLods> = models.into_iter().map(MeshLods::build).collect();
+        let (bounds, fit) = fit_meshes(&mut meshes, normalize);
+        let placeholder = texture::Texture::placeholder(device, queue)?;
+        let materials = vec![material_from_texture("placeholder".to_string(), placeholder, device, layout)];
+        let meshes = upload_meshes(file_name, meshes, 0, device);
+        return Ok(model::Model { meshes, materials, bounds, lod: 0, rig: None, fit, sequence: None });
+    }
+    if format == ModelFormat::Gltf {
+        let scene = load_gltf(file_path)?;
+        let mut meshes: Vec<MeshLods> = scene
+            .models
+            .into_iter()
+            .zip(scene.skins)
+            .map(|(model, skin)| MeshLods {
+                skin,
+                ..MeshLods::build(model)
+            })
+            .collect();
+        let (bounds, fit) = fit_meshes(&mut meshes, normalize);
+        let mut materials = Vec::new();
+        for (m, image) in scene.materials.into_iter().zip(scene.textures) {
+            let diffuse_texture = match image {
+                Some(img) => texture::Texture::from_decoded(device, queue, img, TextureRole::Diffuse, &m.name)?,
+                None => texture::Texture::placeholder(device, queue)?,
+            };
+            materials.push(material_from_texture(m.name, diffuse_texture, device, layout));
+        }
+        let meshes = upload_meshes(file_name, meshes, materials.len() - 1, device);
+        return Ok(model::Model {
+            meshes,
+            materials,
+            bounds,
+            lod: 0,
+            rig: scene.rig,
+            fit,
+            sequence: None,
         });
+    }
 
-        materials.push(model::Material {
-            name: m.name,
-            diffuse_texture,
-            bind_group,
-        })
+    // a file on disk goes through the mesh cache, only the bundled demo is parsed every time
+    let (mut meshes, obj_materials) = if file_path.is_file() {
+        let (cache, _) = ObjectLoader::load_obj_cached(file_path)?;
+        (cache.meshes, cache.materials)
+    } else {
+        let obj_text = load_obj(file_name).await?;
+        let (models, obj_materials, _) = ObjectLoader::parse_obj(file_path, &obj_text, |p| {
+            let mtl_path = dir.join(p);
+            match read_asset(&mtl_path.to_string_lossy()) {
+                Ok(data) => tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(data))),
+                Err(_) => Err(tobj::LoadError::OpenFileFailed),
+            }
+        })?;
+        (models.into_iter().map(MeshLods::build).collect::<Vec<_>>(), obj_materials)
+    };
+
+    let mut materials = Vec::new();
+    for m in obj_materials {
+        let diffuse_texture = match &m.diffuse_texture {
+            Some(name) => {
+                let texture_file_name = dir.join(name);
+                match load_texture(&texture_file_name.to_string_lossy(), TextureRole::Diffuse, device, queue).await {
+                    Ok(t) => t,
+                    Err(e) => {
+                        log::warn!("texture {} of material '{}' not loaded, {}", texture_file_name.display(), m.name, e);
+                        texture::Texture::placeholder(device, queue)?
+                    }
+                }
+            }
+            None => texture::Texture::placeholder(device, queue)?,
+        };
+        materials.push(material_from_texture(m.name, diffuse_texture, device, layout));
     }
 
-    let meshes = models
+    // meshes without a usable material all share one placeholder at the end
+    let fallback = materials.len();
+    let needs_fallback = meshes
+        .iter()
+        .any(|m| m.model.mesh.material_id.map_or(true, |i| i >= fallback));
+    if needs_fallback {
+        let placeholder = texture::Texture::placeholder(device, queue)?;
+        materials.push(material_from_texture("placeholder".to_string(), placeholder, device, layout));
+    }
+
+    // numbered obj frames next to it make it a sequence, drawn at full detail only
+    let mut sequence = if file_path.is_file() { load_sequence(file_path) } else { None };
+    if sequence.is_some() {
+        for mesh in meshes.iter_mut() {
+            mesh.lods = LodChain::default();
+        }
+    }
+
+    let (bounds, fit) = fit_meshes(&mut meshes, normalize);
+    if let (Some(sequence), Some(fit)) = (sequence.as_mut(), &fit) {
+        sequence.apply_fit(fit);
+    }
+    let meshes = upload_meshes(file_name, meshes, fallback, device);
+
+    Ok(model::Model { meshes, materials, bounds, lod: 0, rig: None, fit, sequence })
+}
+
+// bounds of the whole model, after recentring and scaling it to unit size when asked to.
+// the detail levels move along with their model
+fn fit_meshes(meshes: &mut [MeshLods], normalize: bool) -> (ModelBounds, Option<UnitFit>) {
+    let bounds = ModelBounds::from_models(meshes.iter().map(|m| &m.model));
+    if !normalize {
+        return (bounds, None);
+    }
+    let fit = UnitFit::new(meshes.iter().map(|m| &m.model), &bounds);
+    for mesh in meshes.iter_mut() {
+        fit.apply_to_model(&mut mesh.model);
+        for level in mesh.lods.levels.iter_mut() {
+            fit.apply_to_buffer(&mut level.buffer);
+        }
+    }
+    (ModelBounds::from_models(meshes.iter().map(|m| &m.model)), Some(fit))
+}
+
+// meshes whose material is out of range use `fallback`. the lod levels are appended
+// to the same buffers, `lods` says where each one starts
+fn upload_meshes(file_name: &str, meshes: Vec<MeshLods>, fallback: usize, device: &wgpu::Device) -> Vec<model::Mesh> {
+    // joints and weights are looked up through the mesh position each vertex came from
+    let to_model_vertices = |buffer: &VertexBuffer, skin: Option<&SkinWeights>| {
+        buffer
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let (joints, weights) = match (skin, buffer.position_indices.get(i)) {
+                    (Some(skin), Some(&p)) => skin.influence(p),
+                    _ => ([0; 4], [0.; 4]),
+                };
+                model::ModelVertex {
+                    position: v.position,
+                    tex_coords: v.uv,
+                    normal: v.normal,
+                    joints: joints.map(u32::from),
+                    weights,
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    meshes
         .into_iter()
-        .map(|m| {
-            let vertices = (0..m.mesh.positions.len() / 3)
-                .map(|i| model::ModelVertex {
-                    position: [
-                        m.mesh.positions[i * 3],
-                        m.mesh.positions[i * 3 + 1],
-                        m.mesh.positions[i * 3 + 2],
-                    ],
-                    tex_coords: [m.mesh.texcoords[i * 2], m.mesh.texcoords[i * 2 + 1]],
-                    normal: [
-                        m.mesh.normals[i * 3],
-                        m.mesh.normals[i * 3 + 1],
-                        m.mesh.normals[i * 3 + 2],
-                    ],
-                })
-                .collect::<Vec<_>>();
+        .map(|MeshLods { model: m, lods: chain, skin }| {
+            let packed = VertexBuffer::from_mesh(&m.mesh, None);
+            let vertices = to_model_vertices(&packed, skin.as_ref());
+
+            let mut all_vertices = vertices.clone();
+            let mut all_indices = packed.indices.clone();
+            let mut lods = vec![model::LodRange {
+                indices: 0..packed.indices.len() as u32,
+                base_vertex: 0,
+            }];
+            for level in &chain.levels {
+                let start = all_indices.len() as u32;
+                lods.push(model::LodRange {
+                    indices: start..start + level.buffer.indices.len() as u32,
+                    base_vertex: all_vertices.len() as i32,
+                });
+                all_vertices.extend(to_model_vertices(&level.buffer, skin.as_ref()));
+                all_indices.extend(&level.buffer.indices);
+            }
 
             let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some(&format!("{:?} Vertex Buffer", file_name)),
-                contents: bytemuck::cast_slice(&vertices),
-                usage: wgpu::BufferUsages::VERTEX,
+                contents: bytemuck::cast_slice(&all_vertices),
+                // sequences rewrite their vertices every frame
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             });
             let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some(&format!("{:?} Index Buffer", file_name)),
-                contents: bytemuck::cast_slice(&m.mesh.indices),
+                contents: bytemuck::cast_slice(&all_indices),
                 usage: wgpu::BufferUsages::INDEX,
             });
 
@@ -123,11 +294,12 @@ pub async fn load_model(
                 name: file_name.to_string(),
                 vertex_buffer,
                 index_buffer,
-                num_elements: m.mesh.indices.len() as u32,
-                material: m.mesh.material_id.unwrap_or(0),
+                num_elements: packed.indices.len() as u32,
+                material: m.mesh.material_id.filter(|&i| i < fallback).unwrap_or(fallback),
+                vertices,
+                indices: packed.indices,
+                lods,
             }
         })
-        .collect::<Vec<_>>();
-
-    Ok(model::Model { meshes, materials })
+        .collect::<Vec<_>>()
 }
@@ -115,6 +115,11 @@ impl SnowfallSystem {
         }
     }
 
+    // the pipeline of an edited snow.wgsl, built by `create_snow_pipeline`
+    pub fn set_pipeline(&mut self, pipeline: wgpu::RenderPipeline) {
+        self.render_pipeline = pipeline;
+    }
+
     pub fn render<'a>(
         &'a self,
         encoder: &mut wgpu::CommandEncoder,
@@ -275,7 +280,7 @@ impl SnowfallSystem {
     // }
 }
 
-fn create_snow_pipeline(device: &Device, shader: &wgpu::ShaderModule) -> wgpu::RenderPipeline {
+pub fn create_snow_pipeline(device: &Device, shader: &wgpu::ShaderModule) -> wgpu::RenderPipeline {
     let camera_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[wgpu::BindGroupLayoutEntry {
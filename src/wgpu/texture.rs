@@ -1,7 +1,9 @@
+use std::borrow::Cow;
+
 use anyhow::*;
-use image::GenericImageView;
 use pixels::wgpu;
 use crate::department::common::constant::{IS_NEED_FLIPV};
+use crate::department::model::texture::{ColorSpace, MipTexture, TextureRole};
 
 pub struct Texture {
     pub texture: wgpu::Texture,
@@ -55,64 +57,99 @@ impl Texture {
         }
     }
 
-    #[allow(dead_code)]
     pub fn from_bytes(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         bytes: &[u8],
+        role: TextureRole,
         label: &str,
     ) -> Result<Self> {
-        let img = if IS_NEED_FLIPV {
-            let _img = image::load_from_memory(bytes).unwrap();
-            _img.flipv()
-        }
-        else {
-            image::load_from_memory(bytes).unwrap()
-        };
-        Self::from_image(device, queue, &img, Some(label))
+        Self::from_decoded(device, queue, image::load_from_memory(bytes)?, role, label)
+    }
+
+    // for images that were decoded elsewhere, e.g. embedded in a gltf
+    pub fn from_decoded(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: image::DynamicImage,
+        role: TextureRole,
+        label: &str,
+    ) -> Result<Self> {
+        Self::from_mips(device, queue, &MipTexture::new(img, role), Some(label))
     }
 
-    pub fn from_image(
+    // magenta and black checkers, stands in for textures that are missing
+    pub fn placeholder(device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Self> {
+        let img = image::RgbaImage::from_fn(8, 8, |x, y| {
+            if (x + y) % 2 == 0 {
+                image::Rgba([255, 0, 255, 255])
+            } else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        });
+        let mips = MipTexture::new(image::DynamicImage::ImageRgba8(img), TextureRole::Diffuse);
+        Self::from_mips(device, queue, &mips, Some("placeholder"))
+    }
+
+    // uploads every level of the chain. colour textures are sampled through an srgb format,
+    // so the shader gets linear values, data textures are read as they are
+    pub fn from_mips(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        img: &image::DynamicImage,
+        mips: &MipTexture,
         label: Option<&str>,
     ) -> Result<Self> {
-        let dimensions = img.dimensions();
-        let rgba = img.to_rgba8();
+        let (width, height) = mips.dimensions();
+        let format = match mips.color_space() {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        };
 
         let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
+            width,
+            height,
             depth_or_array_layers: 1,
         };
-        let view_formats = [wgpu::TextureFormat::Rgba8UnormSrgb];
+        let view_formats = [format];
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count: mips.levels.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &view_formats,
         });
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-        );
+        for (level, img) in mips.levels.iter().enumerate() {
+            // the cpu side keeps images the way they are stored on disk
+            let img = if IS_NEED_FLIPV {
+                Cow::Owned(image::imageops::flip_vertical(img))
+            } else {
+                Cow::Borrowed(img)
+            };
+            let (w, h) = img.dimensions();
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                &img,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * w),
+                    rows_per_image: Some(h),
+                },
+                wgpu::Extent3d {
+                    width: w,
+                    height: h,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -120,8 +157,8 @@ impl Texture {
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
 
@@ -1,31 +1,42 @@
 use std::iter;
+use std::path::Path;
 
 use cgmath::prelude::*;
 use pixels::wgpu;
 use pixels::wgpu::util::DeviceExt;
 use winit::event::*;
 
+use crate::department::model::animation::Rig;
+use crate::department::model::lod;
+use crate::department::model::skeleton::{Mat4, IDENTITY};
 use crate::department::view::camera_trait;
 use crate::wgpu::snow_flake::SnowflakeInstance;
 use crate::wgpu::snow_flake::SnowflakeVertex;
 use log::info;
+use std::sync::Arc;
 use std::time::Duration;
 use winit::dpi::{LogicalSize, PhysicalSize};
 
 use super::model;
+use super::picking::{self, PickingSystem};
 use super::resources;
-use super::snow_flake::SnowfallSystem;
+use super::snow_flake::{create_snow_pipeline, SnowfallSystem};
 use super::texture;
 
 use model::{DrawModel, Vertex};
 
-use crate::wgpu::create_render_pipeline;
+use crate::wgpu::{create_render_pipeline, SHADER_DIR};
 use crate::wgpu::instance::{Instance, InstanceRaw};
 
 use crate::department::control::camera_controller::CameraController;
+use crate::department::view::picking::{PickId, PickResult};
 use crate::util::ARG;
 
 const NUM_INSTANCES_PER_ROW: u32 = 10;
+// offscreen frames for the window are opaque black, the terminal ones are transparent so
+// the presenter can tell the background from black pixels of the model
+const WINDOW_CLEAR: wgpu::Color = wgpu::Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+const TUI_CLEAR: wgpu::Color = wgpu::Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -59,7 +70,11 @@ where
     tui_size: (u32, u32),
     device: wgpu::Device,
     queue: wgpu::Queue,
+    render_pipeline_layout: wgpu::PipelineLayout,
     render_pipeline: wgpu::RenderPipeline,
+    // kept to rebuild the model and its joint buffer on hot reload
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    joint_bind_group_layout: wgpu::BindGroupLayout,
     obj_model: model::Model,
     //light_model: model::Model,
     camera: T,
@@ -67,22 +82,32 @@ where
     camera_uniform: CameraUniform,
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
+    // same camera with the aspect of the terminal frame, see `set_tui_size`
+    tui_projection: (u32, u32),
+    tui_camera_buffer: wgpu::Buffer,
+    tui_camera_bind_group: wgpu::BindGroup,
+    // skinning matrices the vertex shader reads, one per joint of the model's rig
+    joint_buffer: wgpu::Buffer,
+    joint_count: usize,
+    joint_bind_group: wgpu::BindGroup,
     instances: Vec<Instance>,
     #[allow(dead_code)]
     instance_buffer: wgpu::Buffer,
+    instance_data: Vec<InstanceRaw>,
     depth_texture: texture::Texture,
     tui_depth_texture: texture::Texture,
     size: LogicalSize<u32>,
     pub mouse_pressed: bool,
     pub scale_factor: f64,
     pub snowfall_system: SnowfallSystem,
+    picking: PickingSystem,
 }
 
 impl<T> State<T>
 where
     T: camera_trait::CameraTrait,
 {
-    pub async fn new(size: LogicalSize<u32>, camera: T) -> Self {
+    pub async fn new(size: LogicalSize<u32>, mut camera: T) -> Self {
         // The instance is a handle to our GPU
         // BackendBit::PRIMARY => Vulkan + Metal + DX12 + Browser WebGPU
         log::warn!("WGPU setup");
@@ -136,7 +161,7 @@ where
             });
         //let camera = camera::Camera::new((0.0, 0., 10.), cgmath::Deg(-90.0), cgmath::Deg(-0.0));
         //let projection = camera::Projection::new(size.width, size.height, cgmath::Deg(45.), 0.1, 100.0);
-        let camera_controller = CameraController::new(2.0, 0.2, false);
+        let mut camera_controller = CameraController::new(2.0, 0.2, false);
 
         let mut camera_uniform = CameraUniform::new();
         camera_uniform.update_view_proj(&camera);
@@ -152,10 +177,10 @@ where
         let position = cgmath::Vector3 {
             x: 0.,
             y: 0.,
-            z: -5.,
+            z: 0.,
         };
 
-        let rotation = cgmath::Quaternion::from_axis_angle(position.normalize(), cgmath::Deg(0.0));
+        let rotation = cgmath::Quaternion::from_angle_y(cgmath::Deg(0.0));
         let instances = vec![Instance { position, rotation }];
 
         let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
@@ -189,13 +214,55 @@ where
             }],
             label: Some("camera_bind_group"),
         });
+        let tui_camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tui Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let tui_camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: tui_camera_buffer.as_entire_binding(),
+            }],
+            label: Some("tui_camera_bind_group"),
+        });
 
         log::warn!("Load model");
-        let obj_model =
-            resources::load_model(&ARG.obj_path, &device, &queue, &texture_bind_group_layout)
+        let mut obj_model =
+            resources::load_model(&ARG.obj_path, &device, &queue, &texture_bind_group_layout, ARG.normalize)
                 .await
                 .unwrap();
 
+        // the model sits at the origin, so framing its bounds replaces any fixed start position
+        camera.frame(obj_model.bounds.center(), obj_model.bounds.radius());
+        camera_uniform.update_view_proj(&camera);
+        queue.write_buffer(&camera_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
+        queue.write_buffer(&tui_camera_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
+
+        let joint_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("joint_bind_group_layout"),
+            });
+        let (joint_buffer, joint_count, joint_bind_group) =
+            create_joint_buffer(&device, &joint_bind_group_layout, &obj_model);
+        if let Some(rig) = &obj_model.rig {
+            camera_controller.model_ctrl.set_rig(rig.clone());
+        }
+        if let Some(sequence) = obj_model.sequence.as_mut() {
+            sequence.set_fps(ARG.sequence_fps);
+        }
+
         // let light_model = resources::load_model(
         //     "./res/nice_cube/light_ball.obj",
         //     &device,
@@ -220,50 +287,51 @@ where
                 bind_group_layouts: &[
                     &texture_bind_group_layout,
                     &camera_bind_group_layout,
+                    &joint_bind_group_layout,
                     //&light_bind_group_layout,
                 ],
                 push_constant_ranges: &[],
             });
 
-        let render_pipeline = {
-            let shader = wgpu::ShaderModuleDescriptor {
-                label: Some("Normal shader"),
-                source: wgpu::ShaderSource::Wgsl(
-                    include_str!("../../res/shaders/shader.wgsl").into(),
-                ),
-            };
-            create_render_pipeline(
-                &device,
-                &render_pipeline_layout,
-                wgpu::TextureFormat::Rgba8UnormSrgb,
-                Some(texture::Texture::DEPTH_FORMAT),
-                &[model::ModelVertex::desc(), InstanceRaw::desc()],
-                shader,
-                "render_pipeline",
-            )
-        };
+        let render_pipeline = create_model_pipeline(
+            &device,
+            &render_pipeline_layout,
+            include_str!("../../res/shaders/shader.wgsl"),
+        );
 
         let snowfall_system = SnowfallSystem::new(&device, 1000); // 1000个雪花
+        let picking = PickingSystem::new(&device, &obj_model, &camera_bind_group_layout);
 
         Self {
             tui_size: (256, 79),
             device,
             queue,
+            render_pipeline_layout,
             render_pipeline,
+            texture_bind_group_layout,
+            joint_bind_group_layout,
             obj_model,
             camera,
             camera_controller,
             camera_buffer,
             camera_bind_group,
+            tui_projection: (size.width, size.height),
+            tui_camera_buffer,
+            tui_camera_bind_group,
+            joint_buffer,
+            joint_count,
+            joint_bind_group,
             camera_uniform,
             instances,
             instance_buffer,
+            instance_data,
             depth_texture,
             tui_depth_texture,
             size,
             mouse_pressed: false,
             scale_factor: 1.0f64,
             snowfall_system,
+            picking,
         }
     }
 
@@ -285,6 +353,35 @@ where
         }
     }
 
+    // the terminal frame is `size`, projected as if it were `projection` square pixels
+    pub fn set_tui_size(&mut self, size: (u32, u32), projection: (u32, u32)) {
+        if size == self.tui_size {
+            return;
+        }
+        self.tui_size = size;
+        self.tui_depth_texture = texture::Texture::create_depth_texture(&self.device, size, "tui_depth_texture");
+        self.tui_projection = projection;
+        self.write_camera();
+    }
+
+    // the camera for the terminal frame, the window keeps its own aspect
+    fn tui_camera_uniform(&mut self) -> CameraUniform {
+        let mut uniform = CameraUniform::new();
+        self.camera.update_projection(self.tui_projection.0, self.tui_projection.1);
+        uniform.update_view_proj(&self.camera);
+        self.camera.update_projection(self.size.width, self.size.height);
+        uniform
+    }
+
+    fn write_camera(&mut self) {
+        self.camera_uniform.update_view_proj(&self.camera);
+        self.queue
+            .write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[self.camera_uniform]));
+        let tui = self.tui_camera_uniform();
+        self.queue
+            .write_buffer(&self.tui_camera_buffer, 0, bytemuck::cast_slice(&[tui]));
+    }
+
     pub fn input(&mut self, event: &DeviceEvent) -> bool {
         match event {
             DeviceEvent::Key(RawKeyEvent {
@@ -323,24 +420,168 @@ where
         }
     }
 
+    // picks against the tui target or the window target and highlights the hit from the next frame on
+    pub fn pick(&mut self, cursor: (u32, u32), tui: bool) -> Option<PickResult> {
+        let (size, view_proj) = if tui {
+            (self.tui_size, self.tui_camera_uniform().view_proj)
+        } else {
+            ((self.size.width, self.size.height), self.camera.to_view_proj())
+        };
+        let camera_bind_group = if tui {
+            &self.tui_camera_bind_group
+        } else {
+            &self.camera_bind_group
+        };
+        let hit = self.picking.pick(
+            &self.device,
+            &self.queue,
+            camera_bind_group,
+            &self.instance_buffer,
+            self.instances.len() as u32,
+            size,
+            cursor,
+        );
+        let (id, instance) = match hit {
+            Some((id, instance)) => (Some(id), instance),
+            None => (None, 0),
+        };
+        self.picking.set_highlight(&self.queue, &self.obj_model, id, instance);
+
+        let id = id?;
+        let instance = self.instance_data.get(instance as usize)?;
+        picking::resolve_pick(
+            id,
+            &self.obj_model,
+            instance,
+            view_proj,
+            size,
+            cursor,
+        )
+    }
+
+    // a triangle of the first instance, an id alone doesn't say which one
+    pub fn set_highlight(&mut self, id: Option<PickId>) {
+        self.picking.set_highlight(&self.queue, &self.obj_model, id, 0);
+    }
+
+    // loads `--obj-path` again and swaps it in, the camera stays where it is. the old model
+    // keeps being drawn when the new one fails to load
+    pub fn reload_model(&mut self) -> anyhow::Result<()> {
+        let mut obj_model = pollster::block_on(resources::load_model(
+            &ARG.obj_path,
+            &self.device,
+            &self.queue,
+            &self.texture_bind_group_layout,
+            ARG.normalize,
+        ))?;
+        if let Some(sequence) = obj_model.sequence.as_mut() {
+            sequence.set_fps(ARG.sequence_fps);
+        }
+        (self.joint_buffer, self.joint_count, self.joint_bind_group) =
+            create_joint_buffer(&self.device, &self.joint_bind_group_layout, &obj_model);
+        self.picking.set_model(&self.device, &obj_model);
+        self.obj_model = obj_model;
+        Ok(())
+    }
+
+    // rebuilds every pipeline from the wgsl in `SHADER_DIR`. validation errors are caught
+    // instead of panicking, all pipelines stay as they were unless every shader compiles
+    pub fn reload_shaders(&mut self) -> anyhow::Result<()> {
+        let read = |name: &str| {
+            let path = Path::new(SHADER_DIR).join(name);
+            std::fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+        };
+        let (model_source, picking_source, snow_source) =
+            (read("shader.wgsl")?, read("picking.wgsl")?, read("snow.wgsl")?);
+
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let render_pipeline = create_model_pipeline(&self.device, &self.render_pipeline_layout, &model_source);
+        let picking_shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Picking Shader"),
+            source: wgpu::ShaderSource::Wgsl(picking_source.into()),
+        });
+        let picking_pipelines = self.picking.create_pipelines(&self.device, &picking_shader);
+        let snow_shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Snow Shader"),
+            source: wgpu::ShaderSource::Wgsl(snow_source.into()),
+        });
+        let snow_pipeline = create_snow_pipeline(&self.device, &snow_shader);
+        if let Some(error) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(anyhow::anyhow!("{}", error));
+        }
+
+        self.render_pipeline = render_pipeline;
+        self.picking.set_pipelines(picking_pipelines);
+        self.snowfall_system.set_pipeline(snow_pipeline);
+        Ok(())
+    }
+
+    pub fn rig(&self) -> Option<Arc<Rig>> {
+        self.obj_model.rig.clone()
+    }
+
+    pub fn triangle_count(&self) -> u32 {
+        self.obj_model
+            .meshes
+            .iter()
+            .map(|m| m.indices.len() as u32 / 3)
+            .sum()
+    }
+
+    pub fn camera_pose(&self) -> ([f32; 3], [f32; 3]) {
+        self.camera.pose()
+    }
+
+    // the camera uniform is rewritten by the next `update_outside`
+    pub fn set_camera_pose(&mut self, position: [f32; 3], forward: [f32; 3]) {
+        self.camera.set_pose(position, forward);
+    }
+
     pub fn update_outside(&mut self, controller: &mut CameraController, dt: Duration) {
         controller.update_camera(&mut self.camera, dt);
-        self.camera_uniform.update_view_proj(&self.camera);
-        self.queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&[self.camera_uniform]),
-        );
+        self.write_camera();
 
         let data = controller.model_ctrl.update_model(dt);
         self.queue
             .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&data));
+        self.instance_data = data;
+
+        if let Some(matrices) = controller.model_ctrl.joint_matrices() {
+            let matrices: Vec<Mat4> = self.obj_model.fitted_joints(matrices);
+            let count = matrices.len().min(self.joint_count);
+            self.queue
+                .write_buffer(&self.joint_buffer, 0, bytemuck::cast_slice(&matrices[..count]));
+        }
+        self.obj_model
+            .pose_sequence(&self.queue, controller.model_ctrl.sequence_time());
 
         self.snowfall_system.update(&self.queue, dt);
     }
 
+    // detail level for a viewport `viewport_height` pixels tall, from how big the bounding
+    // sphere of the placed instance shows up in it
+    fn update_lod(&mut self, viewport_height: u32) {
+        let Some(instance) = self.instance_data.first() else {
+            return;
+        };
+        // column major, the last column is the translation
+        let m = &instance.model;
+        let c = self.obj_model.bounds.center();
+        let world: [f32; 3] = [0, 1, 2].map(|r| (0..3).map(|k| m[k][r] * c[k]).sum::<f32>() + m[3][r]);
+        let (eye, _) = self.camera.pose();
+        let distance = (0..3).map(|k| (world[k] - eye[k]).powi(2)).sum::<f32>().sqrt();
+        let radius = lod::projected_radius(
+            self.obj_model.bounds.radius(),
+            distance,
+            self.camera.fov_y(),
+            viewport_height as f32,
+        );
+        self.obj_model.lod = self.obj_model.select_lod(radius);
+    }
+
     // the first return Vec is for gui, the second is for tui
     pub fn render(&mut self, only_tui: bool, tui_with_window: bool) -> (Vec<u8>, Option<Vec<u8>>) {
+        self.update_lod(if only_tui { self.tui_size.1 } else { self.size.height });
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -354,9 +595,10 @@ where
                 (self.tui_size.0, self.tui_size.1),
                 &self.tui_depth_texture,
                 &view_formats,
+                TUI_CLEAR,
+                &self.tui_camera_bind_group,
             );
-            let u32_size = std::mem::size_of::<u32>() as u32;
-            let tui_output_buffer = self.create_output_buffer(self.tui_size, u32_size);
+            let tui_output_buffer = self.create_output_buffer(self.tui_size);
             encoder.copy_texture_to_buffer(
                 wgpu::ImageCopyTexture {
                     aspect: wgpu::TextureAspect::All,
@@ -368,7 +610,7 @@ where
                     buffer: &tui_output_buffer,
                     layout: wgpu::ImageDataLayout {
                         offset: 0,
-                        bytes_per_row: Some(u32_size * self.tui_size.0),
+                        bytes_per_row: Some(padded_bytes_per_row(self.tui_size.0)),
                         rows_per_image: Some(self.tui_size.1),
                     },
                 },
@@ -389,7 +631,7 @@ where
             pollster::block_on(rx.receive());
 
             let data = tui_slice.get_mapped_range();
-            let tui_buf = data.iter().map(|x| *x).collect();
+            let tui_buf = unpad_rows(&data, self.tui_size);
 
             return (tui_buf, None);
         }
@@ -399,10 +641,10 @@ where
             (self.size.width, self.size.height),
             &self.depth_texture,
             &[wgpu::TextureFormat::Rgba8UnormSrgb],
+            WINDOW_CLEAR,
+            &self.camera_bind_group,
         );
-        let u32_size = std::mem::size_of::<u32>() as u32;
-        let output_buffer =
-            self.create_output_buffer((self.size.width, self.size.height), u32_size);
+        let output_buffer = self.create_output_buffer((self.size.width, self.size.height));
 
         encoder.copy_texture_to_buffer(
             wgpu::ImageCopyTexture {
@@ -415,14 +657,14 @@ where
                 buffer: &output_buffer,
                 layout: wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(u32_size * self.size.width),
+                    bytes_per_row: Some(padded_bytes_per_row(self.size.width)),
                     rows_per_image: Some(self.size.height),
                 },
             },
             texture_desc.size,
         );
 
-        let tui_output_buffer = self.create_output_buffer(self.tui_size, u32_size);
+        let tui_output_buffer = self.create_output_buffer(self.tui_size);
         if tui_with_window {
             let view_formats = vec![wgpu::TextureFormat::Rgba8UnormSrgb];
             let (tui_desc, tui_texture) = self.encode_a_new_render_texutre(
@@ -430,6 +672,8 @@ where
                 (self.tui_size.0, self.tui_size.1),
                 &self.tui_depth_texture,
                 &view_formats,
+                TUI_CLEAR,
+                &self.tui_camera_bind_group,
             );
 
             encoder.copy_texture_to_buffer(
@@ -443,7 +687,7 @@ where
                     buffer: &tui_output_buffer,
                     layout: wgpu::ImageDataLayout {
                         offset: 0,
-                        bytes_per_row: Some(u32_size * self.tui_size.0),
+                        bytes_per_row: Some(padded_bytes_per_row(self.tui_size.0)),
                         rows_per_image: Some(self.tui_size.1),
                     },
                 },
@@ -477,18 +721,18 @@ where
             pollster::block_on(rx.receive());
 
             let data = buffer_slice.get_mapped_range();
-            ret_buf = data.iter().map(|x| *x).collect();
+            ret_buf = unpad_rows(&data, (self.size.width, self.size.height));
             if tui_with_window {
                 let data = tui_slice.unwrap().get_mapped_range();
-                tui_buf = Some(data.iter().map(|x| *x).collect());
+                tui_buf = Some(unpad_rows(&data, self.tui_size));
             }
         }
         output_buffer.unmap();
         (ret_buf, tui_buf)
     }
 
-    fn create_output_buffer(&self, size: (u32, u32), u32_size: u32) -> wgpu::Buffer {
-        let output_buffer_size = (u32_size * size.0 * size.1) as wgpu::BufferAddress;
+    fn create_output_buffer(&self, size: (u32, u32)) -> wgpu::Buffer {
+        let output_buffer_size = (padded_bytes_per_row(size.0) * size.1) as wgpu::BufferAddress;
         let output_buffer_desc = wgpu::BufferDescriptor {
             size: output_buffer_size,
             usage: wgpu::BufferUsages::COPY_DST
@@ -507,6 +751,8 @@ where
         w_h: (u32, u32),
         depth_texture: &texture::Texture,
         view_formats: &'a [wgpu::TextureFormat],
+        clear: wgpu::Color,
+        camera_bind_group: &wgpu::BindGroup,
     ) -> (wgpu::TextureDescriptor<'a>, wgpu::Texture) {
         let texture_desc = wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
@@ -532,12 +778,7 @@ where
                     view: &view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 1.0,
-                        }),
+                        load: wgpu::LoadOp::Clear(clear),
                         store: true,
                     },
                 })],
@@ -553,15 +794,81 @@ where
 
             render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
             render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(2, &self.joint_bind_group, &[]);
             render_pass.draw_model_instanced(
                 &self.obj_model,
                 0..self.instances.len() as u32,
-                &self.camera_bind_group,
+                camera_bind_group,
                 //&self.light_bind_group,
             );
         }
+        self.picking.render_highlight(
+            encoder,
+            &view,
+            &depth_texture.view,
+            camera_bind_group,
+            &self.instance_buffer,
+        );
         self.snowfall_system
-            .render(encoder, &view, &depth_texture.view, &self.camera_bind_group);
+            .render(encoder, &view, &depth_texture.view, camera_bind_group);
         (texture_desc, texture)
     }
 }
+
+fn create_model_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    source: &str,
+) -> wgpu::RenderPipeline {
+    let shader = wgpu::ShaderModuleDescriptor {
+        label: Some("Normal shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    };
+    create_render_pipeline(
+        device,
+        layout,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        Some(texture::Texture::DEPTH_FORMAT),
+        &[model::ModelVertex::desc(), InstanceRaw::desc()],
+        shader,
+        "render_pipeline",
+    )
+}
+
+// bind pose until the first update, models without a rig keep a single identity
+fn create_joint_buffer(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    obj_model: &model::Model,
+) -> (wgpu::Buffer, usize, wgpu::BindGroup) {
+    let joint_count = obj_model.rig.as_ref().map_or(1, |r| r.skeleton.joints.len().max(1));
+    let joint_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Joint Buffer"),
+        contents: bytemuck::cast_slice(&vec![IDENTITY; joint_count]),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+    let joint_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: joint_buffer.as_entire_binding(),
+        }],
+        label: Some("joint_bind_group"),
+    });
+    (joint_buffer, joint_count, joint_bind_group)
+}
+
+// texture to buffer copies need rows aligned to 256 bytes, any terminal width can be asked for
+fn padded_bytes_per_row(width: u32) -> u32 {
+    (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+// rgba rows of a mapped copy without the padding `padded_bytes_per_row` added
+fn unpad_rows(data: &[u8], (width, height): (u32, u32)) -> Vec<u8> {
+    let (row, padded) = (width as usize * 4, padded_bytes_per_row(width) as usize);
+    let mut pixels = Vec::with_capacity(row * height as usize);
+    for y in 0..height as usize {
+        pixels.extend_from_slice(&data[y * padded..y * padded + row]);
+    }
+    pixels
+}